@@ -0,0 +1,101 @@
+use alga::general::RealField;
+use std::collections::HashMap;
+use vertex_index::Index;
+
+// Grid storing cells of varying depth. A cell at depth d with index idx spans the indexes
+// idx * 2^d to (idx + 1) * 2^d of the finest grid (depth 0).
+#[derive(Clone, Debug)]
+pub struct AdaptiveGrid<S> {
+    // The largest depth cells may have.
+    max_depth: usize,
+    // Values of the 8 corners of each cell, keyed by depth and index at that depth.
+    // Corners are ordered z << 2 | y << 1 | x.
+    cells: HashMap<(usize, Index), [S; 8]>,
+}
+
+impl<S: RealField + From<f32>> AdaptiveGrid<S> {
+    pub fn new(max_depth: usize) -> AdaptiveGrid<S> {
+        AdaptiveGrid {
+            max_depth,
+            cells: HashMap::new(),
+        }
+    }
+    // Size of the largest cell allowed in units of the finest grid.
+    pub fn max_cell_size(&self) -> usize {
+        1 << self.max_depth
+    }
+    pub fn insert(&mut self, depth: usize, idx: Index, corners: [S; 8]) {
+        debug_assert!(depth <= self.max_depth);
+        self.cells.insert((depth, idx), corners);
+    }
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+    // Calls f for all indexes of the finest grid covered by cells, along with the value
+    // interpolated from the corners of the covering cell.
+    pub fn expand<F: FnMut(Index, S)>(&self, mut f: F) {
+        let one: S = From::from(1f32);
+        for (&(depth, idx), corners) in &self.cells {
+            let size = 1 << depth;
+            let size_s: S = From::from(size as f32);
+            for z in 0..=size {
+                for y in 0..=size {
+                    for x in 0..=size {
+                        let t: [S; 3] = [
+                            S::from(x as f32) / size_s,
+                            S::from(y as f32) / size_s,
+                            S::from(z as f32) / size_s,
+                        ];
+                        let mut value: S = From::from(0f32);
+                        for (corner, &corner_value) in corners.iter().enumerate() {
+                            let mut weight = one;
+                            for (dim, &t) in t.iter().enumerate() {
+                                weight *= if corner & (1 << dim) == 0 { one - t } else { t };
+                            }
+                            value += weight * corner_value;
+                        }
+                        f(
+                            [idx[0] * size + x, idx[1] * size + y, idx[2] * size + z],
+                            value,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand() {
+        let mut grid = AdaptiveGrid::<f64>::new(2);
+        assert_eq!(grid.max_cell_size(), 4);
+        // Linear function x + 2y + 3z on the cell [4, 8] x [0, 4] x [0, 4].
+        let mut corners = [0.; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let p = [4 + 4 * (i & 1), 4 * (i >> 1 & 1), 4 * (i >> 2 & 1)];
+            *corner = p[0] as f64 + 2. * p[1] as f64 + 3. * p[2] as f64;
+        }
+        grid.insert(2, [1, 0, 0], corners);
+        assert_eq!(grid.len(), 1);
+        let mut num_values = 0;
+        grid.expand(|idx, v| {
+            num_values += 1;
+            assert!(idx[0] >= 4 && idx[0] <= 8);
+            assert_relative_eq!(
+                v,
+                idx[0] as f64 + 2. * idx[1] as f64 + 3. * idx[2] as f64,
+                epsilon = 1e-9
+            );
+        });
+        assert_eq!(num_values, 5 * 5 * 5);
+        grid.clear();
+        assert_eq!(grid.len(), 0);
+    }
+}
@@ -0,0 +1,113 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use na;
+use num_traits::{Float, ToPrimitive};
+use std::fmt::Debug;
+
+/// Implicit function changing over time, given by keyframes, that are played back at fps frames
+/// per second. Values between keyframes are interpolated linearly, so tessellating different
+/// times only requires evaluating the keyframes. Before the first and after the last keyframe the
+/// function stays constant.
+pub struct AnimatedObject<S: Debug + RealField> {
+    frames: Vec<Box<dyn ImplicitFunction<S> + Send + Sync>>,
+    fps: S,
+    // The current time in seconds.
+    time: S,
+    // Union of the bounding boxes of all frames.
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField + Float + From<f32>> AnimatedObject<S> {
+    /// Create a new AnimatedObject at time 0. Panics, if frames is empty.
+    /// frames: the keyframes
+    /// fps: the number of keyframes per second
+    pub fn new(
+        frames: Vec<Box<dyn ImplicitFunction<S> + Send + Sync>>,
+        fps: S,
+    ) -> AnimatedObject<S> {
+        assert!(!frames.is_empty(), "no frames");
+        let bbox = frames
+            .iter()
+            .skip(1)
+            .fold(frames[0].bbox().clone(), |bbox, f| bbox.union(f.bbox()));
+        AnimatedObject {
+            frames,
+            fps,
+            time: S::zero(),
+            bbox,
+        }
+    }
+    /// Set the time in seconds, at which the function is evaluated.
+    pub fn set_time(&mut self, t: S) {
+        self.time = t;
+    }
+    /// The time in seconds, at which the function is evaluated.
+    pub fn time(&self) -> S {
+        self.time
+    }
+    // The frames before and after the current time and the weight of the latter.
+    fn blend(&self) -> (usize, usize, S) {
+        let last: S = From::from((self.frames.len() - 1) as f32);
+        let position = Float::min(Float::max(self.time * self.fps, S::zero()), last);
+        let i = ToPrimitive::to_usize(&Float::floor(position)).unwrap();
+        let next = (i + 1).min(self.frames.len() - 1);
+        (i, next, position - Float::floor(position))
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for AnimatedObject<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        let (i, next, w) = self.blend();
+        if w == S::zero() {
+            return self.frames[i].value(p);
+        }
+        self.frames[i].value(p) * (S::one() - w) + self.frames[next].value(p) * w
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        let (i, next, w) = self.blend();
+        if w == S::zero() {
+            return self.frames[i].normal(p);
+        }
+        // The gradient of the blend is the blend of the gradients, which is approximated with the
+        // normals of the frames.
+        (self.frames[i].normal(p) * (S::one() - w) + self.frames[next].normal(p) * w).normalize()
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        // Blends are convex combinations, so the largest constant of all frames is a bound.
+        self.frames.iter().try_fold(S::zero(), |max, f| {
+            f.lipschitz_constant().map(|l| Float::max(max, l))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::Sphere;
+
+    fn sphere(radius: f64) -> Box<dyn ImplicitFunction<f64> + Send + Sync> {
+        Box::new(Sphere::new(na::Point3::origin(), radius))
+    }
+
+    #[test]
+    fn animated() {
+        let mut animated = AnimatedObject::new(vec![sphere(1.), sphere(3.)], 2.);
+        let p = na::Point3::new(0., 4., 0.);
+        assert_relative_eq!(animated.value(&p), 3.);
+        assert_eq!(animated.bbox(), sphere(3.).bbox());
+        // Half way between the frames.
+        animated.set_time(0.25);
+        assert_relative_eq!(animated.value(&p), 2.);
+        assert_relative_eq!(animated.normal(&p), na::Vector3::y());
+        // The last frame is kept after the end.
+        animated.set_time(10.);
+        assert_relative_eq!(animated.value(&p), 1.);
+        animated.set_time(-1.);
+        assert_relative_eq!(animated.value(&p), 3.);
+        assert_eq!(animated.lipschitz_constant(), Some(1.));
+    }
+}
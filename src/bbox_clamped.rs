@@ -0,0 +1,118 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Implicit function, that is only evaluated inside its bounding box, as returned by
+/// ImplicitFunction::clamped_to_bbox. Outside of the bounding box the value is the distance to
+/// the bounding box. Since the surface is inside the bounding box, this is a lower bound of the
+/// distance to the surface, so sampling can still skip empty space. (A constant like Float::MAX
+/// would make the sampling skip cells, that reach into the bounding box.)
+pub struct BboxClamped<S: Debug + RealField, F> {
+    f: F,
+    phantom: ::std::marker::PhantomData<S>,
+}
+
+impl<S: Debug + RealField + Float, F: ImplicitFunction<S>> BboxClamped<S, F> {
+    /// Clamp f to its bounding box.
+    pub fn new(f: F) -> BboxClamped<S, F> {
+        BboxClamped {
+            f,
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+    // The point of the bounding box closest to p.
+    fn closest_point(&self, p: &na::Point3<S>) -> na::Point3<S> {
+        let bbox = self.f.bbox();
+        na::Point3::new(
+            Float::min(Float::max(p.x, bbox.min.x), bbox.max.x),
+            Float::min(Float::max(p.y, bbox.min.y), bbox.max.y),
+            Float::min(Float::max(p.z, bbox.min.z), bbox.max.z),
+        )
+    }
+}
+
+impl<S: Debug + RealField + Float, F: ImplicitFunction<S>> ImplicitFunction<S>
+    for BboxClamped<S, F>
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        self.f.bbox()
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        if self.f.bbox().contains(p) {
+            self.f.value(p)
+        } else {
+            na::distance(p, &self.closest_point(p))
+        }
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        if self.f.bbox().contains(p) {
+            self.f.normal(p)
+        } else {
+            (p - self.closest_point(p)).normalize()
+        }
+    }
+    fn is_inside(&self, p: &na::Point3<S>) -> bool {
+        self.f.bbox().contains(p) && self.f.is_inside(p)
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        // The distance to the bounding box has a Lipschitz constant of 1.
+        self.f.lipschitz_constant().map(|l| Float::max(l, S::one()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ManifoldDualContouring;
+
+    // Unit sphere, whose values are garbage outside of the radius 2.1.
+    struct Diverging {
+        bbox: BoundingBox<f64>,
+    }
+
+    impl ImplicitFunction<f64> for Diverging {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            &self.bbox
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            let r = p.coords.norm();
+            if r > 2.1 {
+                f64::NAN
+            } else {
+                r - 1.
+            }
+        }
+        fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+            p.coords.normalize()
+        }
+    }
+
+    #[test]
+    fn clamped_to_bbox() {
+        let r = na::Point3::new(1.2, 1.2, 1.2);
+        let clamped = Diverging {
+            bbox: BoundingBox::new(&-r, &r),
+        }
+        .clamped_to_bbox();
+        assert_relative_eq!(clamped.value(&na::Point3::new(0., 0.5, 0.)), -0.5);
+        assert_relative_eq!(clamped.value(&na::Point3::new(0., 3., 0.)), 1.8);
+        assert_relative_eq!(clamped.value(&na::Point3::new(2.2, 1.2, 0.)), 1.);
+        assert_relative_eq!(
+            clamped.normal(&na::Point3::new(0., 0., -2.)),
+            -na::Vector3::z()
+        );
+        assert!(!clamped.is_inside(&na::Point3::new(5., 0., 0.)));
+        assert_eq!(clamped.lipschitz_constant(), Some(1.));
+        let mesh = ManifoldDualContouring::new(&clamped, 0.2, 0.1)
+            .tessellate()
+            .unwrap();
+        assert!(!mesh.faces.is_empty());
+        for v in &mesh.vertices {
+            let r = na::Vector3::new(v[0], v[1], v[2]).norm();
+            assert!((r - 1.).abs() < 0.1, "{}", r);
+        }
+    }
+}
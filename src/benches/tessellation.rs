@@ -12,7 +12,9 @@ use implicit3d::{
 };
 use nalgebra as na;
 use num_traits::Float;
-use tessellation::{AsUSize, BoundingBox, ImplicitFunction, ManifoldDualContouring};
+use tessellation::{
+    AsUSize, BoundingBox, ImplicitFunction, ManifoldDualContouring, ZeroFindingMethod,
+};
 
 struct ObjectAdaptor<S: RealField> {
     implicit: Box<dyn implicit3d::Object<S>>,
@@ -77,6 +79,24 @@ fn tessellate<S: From<f32> + AsUSize + RealField + Float>(b: &mut Bencher) {
     });
 }
 
-benchmark_group!(bench_tessellation_f32, tessellate<f32>,);
-benchmark_group!(bench_tessellation_f64, tessellate<f64>,);
+fn tessellate_illinois<S: From<f32> + AsUSize + RealField + Float>(b: &mut Bencher) {
+    let o = create_object::<S>();
+    let tess = ManifoldDualContouring::new(&o, From::from(0.02), From::from(0.1))
+        .zero_finding_method(ZeroFindingMethod::Illinois);
+    b.iter(|| {
+        let mut my_tess = tess.clone();
+        my_tess.tessellate();
+    });
+}
+
+benchmark_group!(
+    bench_tessellation_f32,
+    tessellate<f32>,
+    tessellate_illinois<f32>,
+);
+benchmark_group!(
+    bench_tessellation_f64,
+    tessellate<f64>,
+    tessellate_illinois<f64>,
+);
 benchmark_main!(bench_tessellation_f32, bench_tessellation_f64);
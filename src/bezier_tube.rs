@@ -0,0 +1,158 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+// Number of evenly spaced parameters, from which the closest point on the curve is searched.
+const INITIAL_GUESSES: usize = 5;
+// Number of Newton steps from each initial guess.
+const NEWTON_STEPS: usize = 8;
+
+/// Sphere of radius swept along a cubic Bezier curve, e.g. for pipes and wires. The value is the
+/// distance to the curve minus radius.
+pub struct BezierTube<S: Debug + RealField> {
+    control_points: [na::Vector3<S>; 4],
+    radius: S,
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField + Float + From<f32>> BezierTube<S> {
+    /// Create a new BezierTube. Panics, if there are not exactly 4 control points.
+    /// control_points: the control points of the cubic Bezier curve
+    /// radius: the radius of the tube
+    pub fn new(control_points: Vec<na::Point3<S>>, radius: S) -> BezierTube<S> {
+        assert_eq!(control_points.len(), 4, "need 4 control points");
+        // The curve lies within the convex hull of its control points.
+        let mut bbox = BoundingBox::neg_infinity();
+        for p in &control_points {
+            bbox.insert(p);
+        }
+        bbox.dilate(radius);
+        BezierTube {
+            control_points: [
+                control_points[0].coords,
+                control_points[1].coords,
+                control_points[2].coords,
+                control_points[3].coords,
+            ],
+            radius,
+            bbox,
+        }
+    }
+    /// The point of the curve at parameter t in [0, 1].
+    pub fn point(&self, t: S) -> na::Point3<S> {
+        let c = &self.control_points;
+        let three: S = From::from(3f32);
+        let s = S::one() - t;
+        na::Point3::from(
+            c[0] * (s * s * s)
+                + c[1] * (three * s * s * t)
+                + c[2] * (three * s * t * t)
+                + c[3] * (t * t * t),
+        )
+    }
+    // The first and second derivative of the curve at t.
+    fn derivatives(&self, t: S) -> (na::Vector3<S>, na::Vector3<S>) {
+        let c = &self.control_points;
+        let three: S = From::from(3f32);
+        let six: S = From::from(6f32);
+        let two: S = From::from(2f32);
+        let s = S::one() - t;
+        let first = (c[1] - c[0]) * (three * s * s)
+            + (c[2] - c[1]) * (six * s * t)
+            + (c[3] - c[2]) * (three * t * t);
+        let second =
+            (c[2] - c[1] * two + c[0]) * (six * s) + (c[3] - c[2] * two + c[1]) * (six * t);
+        (first, second)
+    }
+    /// Return the parameter of the point of the curve closest to p. Newton's method minimizes
+    /// the squared distance from INITIAL_GUESSES evenly spaced parameters.
+    pub fn closest_parameter(&self, p: &na::Point3<S>) -> S {
+        let last: S = From::from((INITIAL_GUESSES - 1) as f32);
+        let mut best = (S::zero(), Float::infinity());
+        for i in 0..INITIAL_GUESSES {
+            let t: S = From::from(i as f32);
+            let mut t = t / last;
+            for _ in 0..NEWTON_STEPS {
+                // The derivatives of |point(t) - p|² / 2.
+                let d = self.point(t) - p;
+                let (first, second) = self.derivatives(t);
+                let slope = d.dot(&first);
+                let curvature = first.dot(&first) + d.dot(&second);
+                if curvature <= S::zero() {
+                    break;
+                }
+                t = Float::min(Float::max(t - slope / curvature, S::zero()), S::one());
+            }
+            let distance = na::distance_squared(&self.point(t), p);
+            if distance < best.1 {
+                best = (t, distance);
+            }
+        }
+        best.0
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for BezierTube<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        na::distance(&self.point(self.closest_parameter(p)), p) - self.radius
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        (p - self.point(self.closest_parameter(p))).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bezier_tube() {
+        // A straight curve from (0, 0, 0) to (3, 0, 0) is a capsule.
+        let straight = BezierTube::new(
+            vec![
+                na::Point3::new(0., 0., 0.),
+                na::Point3::new(1., 0., 0.),
+                na::Point3::new(2., 0., 0.),
+                na::Point3::new(3., 0., 0.),
+            ],
+            0.5,
+        );
+        assert_relative_eq!(straight.value(&na::Point3::new(1.2, 2., 0.)), 1.5);
+        assert_relative_eq!(straight.value(&na::Point3::new(5., 0., 0.)), 1.5);
+        assert_relative_eq!(straight.value(&na::Point3::new(1.5, 0., 0.)), -0.5);
+        assert_relative_eq!(
+            straight.normal(&na::Point3::new(2.5, 0., -1.)),
+            -na::Vector3::z(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(straight.bbox().min, na::Point3::new(-0.5, -0.5, -0.5));
+        assert_relative_eq!(straight.bbox().max, na::Point3::new(3.5, 0.5, 0.5));
+        // A curved arc, compared with the closest of many points on the curve.
+        let arc = BezierTube::new(
+            vec![
+                na::Point3::new(0., 0., 0.),
+                na::Point3::new(0., 2., 0.),
+                na::Point3::new(2., 2., 1.),
+                na::Point3::new(2., 0., 1.),
+            ],
+            0.2,
+        );
+        for p in &[
+            na::Point3::new(1., 0., 0.),
+            na::Point3::new(1., 3., 0.5),
+            na::Point3::new(-1., 1., 2.),
+            na::Point3::new(2.5, -1., 1.),
+        ] {
+            let expected = (0..=10000)
+                .map(|i| na::distance(&arc.point(i as f64 / 10000.), p))
+                .fold(f64::INFINITY, f64::min);
+            assert_relative_eq!(arc.value(p), expected - 0.2, epsilon = 1e-6);
+        }
+    }
+}
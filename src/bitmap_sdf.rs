@@ -0,0 +1,214 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use finite_difference;
+use grid_function::{BoxedGridFn, GridFunction};
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+use vertex_index::Index;
+use AsUSize;
+
+// Number of times the four sweeps of the fast sweeping method are repeated.
+const SWEEP_ITERATIONS: usize = 2;
+// The first order eikonal solver overestimates distances along diagonals, by at most
+// (1 + 1 / sqrt(2)) / sqrt(2) < 1.25 for nodes diagonally next to a fixed node. The interpolation
+// between pixel centers keeps this bound.
+const LIPSCHITZ_CONSTANT: f32 = 1.25;
+
+/// A 2-D bitmap extruded along the z axis, e.g. to tessellate a logo. The bitmap lies in the
+/// xy plane: pixels[i][j] covers [j, j + 1] * cell_size in x and [i, i + 1] * cell_size in y, and
+/// is inside the object, if true. The extrusion spans [0, extrusion_depth] in z.
+/// Values combine the 2-D distance transform of the bitmap, computed with an eikonal solver, and
+/// the distance along z.
+pub struct BitmapSdf<S: RealField> {
+    // Signed 2-D distance transform at the pixel centers, with a padding of one pixel.
+    distance_transform: GridFunction<S, BoxedGridFn<S>>,
+    bbox: BoundingBox<S>,
+    cell_size: S,
+    extrusion_depth: S,
+}
+
+// Solve the eikonal equation |grad u| = 1 on a grid of cell size h with the fast sweeping method
+// (Zhao 2005), where the finite values of u are fixed. I.e. approximate the distance of each grid
+// node to the fixed nodes plus their values. The grid is indexed by x + dims[0] * y.
+fn fast_sweeping(mut u: Vec<f64>, dims: [usize; 2], h: f64) -> Vec<f64> {
+    let fixed: Vec<bool> = u.iter().map(|v| v.is_finite()).collect();
+    let index = |x: usize, y: usize| x + dims[0] * y;
+    for _ in 0..SWEEP_ITERATIONS {
+        for &(reverse_x, reverse_y) in &[(false, false), (true, false), (true, true), (false, true)]
+        {
+            for y in 0..dims[1] {
+                let y = if reverse_y { dims[1] - 1 - y } else { y };
+                for x in 0..dims[0] {
+                    let x = if reverse_x { dims[0] - 1 - x } else { x };
+                    if fixed[index(x, y)] {
+                        continue;
+                    }
+                    let neighbor = |x: Option<usize>, y: Option<usize>| match (x, y) {
+                        (Some(x), Some(y)) if x < dims[0] && y < dims[1] => u[index(x, y)],
+                        _ => f64::INFINITY,
+                    };
+                    let a = neighbor(x.checked_sub(1), Some(y)).min(neighbor(Some(x + 1), Some(y)));
+                    let b = neighbor(Some(x), y.checked_sub(1)).min(neighbor(Some(x), Some(y + 1)));
+                    let candidate = if (a - b).abs() >= h {
+                        a.min(b) + h
+                    } else {
+                        (a + b + (2. * h * h - (a - b) * (a - b)).sqrt()) / 2.
+                    };
+                    let i = index(x, y);
+                    u[i] = u[i].min(candidate);
+                }
+            }
+        }
+    }
+    u
+}
+
+impl<S> BitmapSdf<S>
+where
+    S: 'static + Debug + RealField + Float + From<f32> + AsUSize,
+{
+    /// Create the extrusion of pixels (rows of equal length) with square pixels of size
+    /// cell_size. Panics, if pixels is empty.
+    pub fn new(pixels: Vec<Vec<bool>>, cell_size: S, extrusion_depth: S) -> BitmapSdf<S> {
+        assert!(!pixels.is_empty() && !pixels[0].is_empty(), "empty bitmap");
+        assert!(
+            pixels.iter().all(|row| row.len() == pixels[0].len()),
+            "rows of different length"
+        );
+        // Pad the bitmap with a pixel outside on each side, so the outline is closed.
+        let dims = [pixels[0].len() + 2, pixels.len() + 2];
+        let mut inside = vec![false; dims[0] * dims[1]];
+        for (i, row) in pixels.iter().enumerate() {
+            for (j, &pixel) in row.iter().enumerate() {
+                inside[j + 1 + dims[0] * (i + 1)] = pixel;
+            }
+        }
+        // Distances of pixel centers to the outline in units of pixels, on either side. The
+        // outline lies half a pixel from the centers next to it.
+        let outline_distance = |side: bool| {
+            let u = (0..inside.len())
+                .map(|i| {
+                    let (x, y) = (i % dims[0], i / dims[0]);
+                    let crosses = |x: usize, y: usize| inside[x + dims[0] * y] != side;
+                    let next_to_outline = (x > 0 && crosses(x - 1, y))
+                        || (x + 1 < dims[0] && crosses(x + 1, y))
+                        || (y > 0 && crosses(x, y - 1))
+                        || (y + 1 < dims[1] && crosses(x, y + 1));
+                    if inside[i] == side && next_to_outline {
+                        0.5
+                    } else {
+                        f64::INFINITY
+                    }
+                })
+                .collect();
+            fast_sweeping(u, dims, 1.)
+        };
+        let (to_inside, to_outside) = (outline_distance(true), outline_distance(false));
+        let values: Vec<S> = (0..inside.len())
+            .map(|i| {
+                let d = if inside[i] {
+                    -to_inside[i]
+                } else {
+                    to_outside[i]
+                };
+                // Without any pixel inside, the distance is infinite.
+                let d: S = From::from(d.min(f64::from(f32::MAX)) as f32);
+                d * cell_size
+            })
+            .collect();
+        let half: S = From::from(0.5f32);
+        let origin = na::Point3::new(-cell_size * half, -cell_size * half, S::zero());
+        let lookup: BoxedGridFn<S> = Box::new(move |idx: Index| values[idx[0] + dims[0] * idx[1]]);
+        let distance_transform =
+            GridFunction::new([dims[0], dims[1], 1], origin, cell_size, lookup);
+        // The bounding box includes the padding, so the sides of the extrusion lie within it.
+        let margin = na::Vector3::new(cell_size, cell_size, cell_size) * half;
+        let size = na::Vector3::new(
+            From::from(pixels[0].len() as f32),
+            From::from(pixels.len() as f32),
+            extrusion_depth / cell_size,
+        ) * cell_size;
+        let bbox = BoundingBox::new(
+            &(na::Point3::origin() - margin),
+            &(na::Point3::from(size) + margin),
+        );
+        BitmapSdf {
+            distance_transform,
+            bbox,
+            cell_size,
+            extrusion_depth,
+        }
+    }
+}
+
+impl<S> ImplicitFunction<S> for BitmapSdf<S>
+where
+    S: 'static + Debug + RealField + Float + From<f32> + AsUSize,
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        let zero = S::zero();
+        let d_xy = self
+            .distance_transform
+            .value(&na::Point3::new(p.x, p.y, zero));
+        let d_z = Float::max(-p.z, p.z - self.extrusion_depth);
+        // Distance to the intersection of the extruded outline and the slab in z.
+        let outside = Float::sqrt(
+            Float::powi(Float::max(d_xy, zero), 2) + Float::powi(Float::max(d_z, zero), 2),
+        );
+        Float::min(Float::max(d_xy, d_z), zero) + outside
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        let h = self.cell_size * From::from(0.5f32);
+        finite_difference::normal(|q| self.value(q), p, h)
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        Some(From::from(LIPSCHITZ_CONSTANT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ManifoldDualContouring;
+
+    #[test]
+    fn extruded_square() {
+        // A 4 x 4 square of pixels of size 0.25 in the corner of a 6 x 5 bitmap.
+        let pixels: Vec<Vec<bool>> = (0..5)
+            .map(|i| (0..6).map(|j| i < 4 && j < 4).collect())
+            .collect();
+        let sdf = BitmapSdf::new(pixels, 0.25, 2.);
+        assert_eq!(sdf.bbox().max, na::Point3::new(1.625, 1.375, 2.125));
+        // The outline lies between the pixel centers next to it.
+        assert_relative_eq!(sdf.value(&na::Point3::new(1., 0.5, 1.)), 0.);
+        assert_relative_eq!(sdf.value(&na::Point3::new(0.875, 0.375, 1.)), -0.125);
+        assert_relative_eq!(sdf.value(&na::Point3::new(1.375, 0.375, 1.)), 0.375);
+        // The first order scheme underestimates the distance to the sides of the square.
+        let center = sdf.value(&na::Point3::new(0.5, 0.5, 1.));
+        assert!((-0.5..-0.3).contains(&center), "{}", center);
+        // Along z the distance is exact.
+        assert_relative_eq!(sdf.value(&na::Point3::new(0.5, 0.5, 0.1)), -0.1);
+        assert_relative_eq!(sdf.value(&na::Point3::new(0.5, 0.5, 2.5)), 0.5);
+        let mesh = ManifoldDualContouring::new(&sdf, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        assert_relative_eq!(mesh.volume(), 1. * 1. * 2., epsilon = 0.1);
+    }
+
+    #[test]
+    fn eikonal() {
+        let mut u = vec![f64::INFINITY; 25];
+        u[12] = 0.;
+        let u = fast_sweeping(u, [5, 5], 1.);
+        assert_eq!(u[14], 2.);
+        assert_eq!(u[2], 2.);
+        // Diagonal distances are overestimated by the first order scheme.
+        assert_relative_eq!(u[18], 1. + 0.5f64.sqrt());
+        assert!(u[0] >= 8f64.sqrt() && u[0] < 8f64.sqrt() * 1.25, "{}", u[0]);
+    }
+}
@@ -0,0 +1,373 @@
+use alga::general::RealField;
+use bbox::BoundingBox;
+use mesh::Mesh;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+// Maximum number of faces stored in a leaf.
+const LEAF_SIZE: usize = 4;
+
+struct Node<S: RealField> {
+    bbox: BoundingBox<S>,
+    // Range of faces for leafs, None for inner nodes.
+    faces: Option<(usize, usize)>,
+    // Indexes of the children of inner nodes.
+    children: (usize, usize),
+}
+
+// Bounding volume hierarchy over the faces of a mesh, for nearest point queries.
+pub struct Bvh<S: RealField> {
+    triangles: Vec<[na::Point3<S>; 3]>,
+    // Indexes into triangles, ordered such that the faces of each leaf are contiguous.
+    faces: Vec<usize>,
+    nodes: Vec<Node<S>>,
+}
+
+// Return the point of the triangle abc closest to p (see Ericson, Real-Time Collision Detection).
+pub fn closest_point_on_triangle<S: RealField>(
+    p: &na::Point3<S>,
+    [a, b, c]: &[na::Point3<S>; 3],
+) -> na::Point3<S> {
+    let zero = S::zero();
+    let (ab, ac, ap) = (b - a, c - a, p - a);
+    let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+    if d1 <= zero && d2 <= zero {
+        return *a;
+    }
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+    if d3 >= zero && d4 <= d3 {
+        return *b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= zero && d1 >= zero && d3 <= zero {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+    if d6 >= zero && d5 <= d6 {
+        return *c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= zero && d2 >= zero && d6 <= zero {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denominator = va + vb + vc;
+    if denominator <= zero {
+        // Degenerate triangle.
+        return *a;
+    }
+    a + ab * (vb / denominator) + ac * (vc / denominator)
+}
+
+// Return the distance t along the ray origin + t * direction, at which it hits the triangle abc,
+// if it does (see Moeller and Trumbore).
+pub fn ray_triangle_intersection<S: RealField>(
+    origin: &na::Point3<S>,
+    direction: &na::Vector3<S>,
+    [a, b, c]: &[na::Point3<S>; 3],
+) -> Option<S> {
+    let (ab, ac) = (b - a, c - a);
+    let p = direction.cross(&ac);
+    let determinant = ab.dot(&p);
+    if determinant == S::zero() {
+        // The ray is parallel to the triangle.
+        return None;
+    }
+    let ao = origin - a;
+    let u = ao.dot(&p) / determinant;
+    if u < S::zero() || u > S::one() {
+        return None;
+    }
+    let q = ao.cross(&ab);
+    let v = direction.dot(&q) / determinant;
+    if v < S::zero() || u + v > S::one() {
+        return None;
+    }
+    Some(ac.dot(&q) / determinant)
+}
+
+// Return the range of t in which the ray origin + t * direction is inside bbox, which is empty if
+// the ray misses bbox.
+fn ray_bbox_intersection<S: RealField + Float>(
+    bbox: &BoundingBox<S>,
+    origin: &na::Point3<S>,
+    direction: &na::Vector3<S>,
+) -> (S, S) {
+    (0..3).fold((S::neg_infinity(), S::infinity()), |(near, far), i| {
+        let inverse = S::one() / direction[i];
+        let t0 = (bbox.min[i] - origin[i]) * inverse;
+        let t1 = (bbox.max[i] - origin[i]) * inverse;
+        (
+            Float::max(near, Float::min(t0, t1)),
+            Float::min(far, Float::max(t0, t1)),
+        )
+    })
+}
+
+// Squared distance of p to bbox, zero if p is inside.
+fn bbox_distance_squared<S: RealField + Float>(bbox: &BoundingBox<S>, p: &na::Point3<S>) -> S {
+    let zero = S::zero();
+    (0..3).fold(zero, |sum, i| {
+        let d = Float::max(Float::max(bbox.min[i] - p[i], p[i] - bbox.max[i]), zero);
+        sum + d * d
+    })
+}
+
+impl<S: 'static + RealField + Float + From<f32> + Debug> Bvh<S> {
+    pub fn new(mesh: &Mesh<S>) -> Bvh<S> {
+        let point = |i: usize| {
+            let v = &mesh.vertices[i];
+            na::Point3::new(v[0], v[1], v[2])
+        };
+        let mut result = Bvh {
+            triangles: mesh
+                .faces
+                .iter()
+                .map(|f| [point(f[0]), point(f[1]), point(f[2])])
+                .collect(),
+            faces: (0..mesh.faces.len()).collect(),
+            nodes: Vec::new(),
+        };
+        if !mesh.faces.is_empty() {
+            result.build(0, mesh.faces.len());
+        }
+        result
+    }
+    // Recursively build the node for the faces in [start, end) and return its index.
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let mut bbox = BoundingBox::neg_infinity();
+        for &f in &self.faces[start..end] {
+            for p in &self.triangles[f] {
+                bbox.insert(p);
+            }
+        }
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bbox: bbox.clone(),
+            faces: Some((start, end)),
+            children: (0, 0),
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+        // Split at the median of the centroids along the largest dimension.
+        let dim = bbox.dim();
+        let axis = if dim.x >= dim.y && dim.x >= dim.z {
+            0
+        } else if dim.y >= dim.z {
+            1
+        } else {
+            2
+        };
+        let triangles = &self.triangles;
+        let centroid =
+            |f: usize| triangles[f][0][axis] + triangles[f][1][axis] + triangles[f][2][axis];
+        self.faces[start..end].sort_by(|&a, &b| centroid(a).partial_cmp(&centroid(b)).unwrap());
+        let middle = (start + end) / 2;
+        let left = self.build(start, middle);
+        let right = self.build(middle, end);
+        self.nodes[index].faces = None;
+        self.nodes[index].children = (left, right);
+        index
+    }
+    // Return the distance of p to the nearest point on the mesh, or None if the mesh has no
+    // faces.
+    pub fn distance(&self, p: &na::Point3<S>) -> Option<S> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        // The smallest squared distance found so far.
+        let mut best: Option<S> = None;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let bound = bbox_distance_squared(&node.bbox, p);
+            if let Some(d) = best {
+                if bound >= d {
+                    continue;
+                }
+            }
+            match node.faces {
+                Some((start, end)) => {
+                    for &f in &self.faces[start..end] {
+                        let q = closest_point_on_triangle(p, &self.triangles[f]);
+                        let d = na::distance_squared(p, &q);
+                        if best.is_none_or(|best_d| d < best_d) {
+                            best = Some(d);
+                        }
+                    }
+                }
+                None => {
+                    // Visit the closer child first.
+                    let (left, right) = node.children;
+                    let dl = bbox_distance_squared(&self.nodes[left].bbox, p);
+                    let dr = bbox_distance_squared(&self.nodes[right].bbox, p);
+                    if dl < dr {
+                        stack.push(right);
+                        stack.push(left);
+                    } else {
+                        stack.push(left);
+                        stack.push(right);
+                    }
+                }
+            }
+        }
+        best.map(Float::sqrt)
+    }
+    // Return the distance t > min_t along the ray origin + t * direction to the first face it
+    // hits, along with the index of that face.
+    pub fn ray_intersect(
+        &self,
+        origin: &na::Point3<S>,
+        direction: &na::Vector3<S>,
+        min_t: S,
+    ) -> Option<(S, usize)> {
+        let mut best: Option<(S, usize)> = None;
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let (near, far) = ray_bbox_intersection(&node.bbox, origin, direction);
+            if near > far || far <= min_t || best.is_some_and(|(t, _)| near >= t) {
+                continue;
+            }
+            match node.faces {
+                Some((start, end)) => {
+                    for &f in &self.faces[start..end] {
+                        if let Some(t) =
+                            ray_triangle_intersection(origin, direction, &self.triangles[f])
+                        {
+                            if t > min_t && best.is_none_or(|(best_t, _)| t < best_t) {
+                                best = Some((t, f));
+                            }
+                        }
+                    }
+                }
+                None => {
+                    stack.push(node.children.0);
+                    stack.push(node.children.1);
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn closest_point() {
+        let t = [
+            na::Point3::new(0., 0., 0.),
+            na::Point3::new(1., 0., 0.),
+            na::Point3::new(0., 1., 0.),
+        ];
+        let cases = [
+            ([0.2, 0.2, 1.], [0.2, 0.2, 0.]),
+            ([-1., -1., 0.], [0., 0., 0.]),
+            ([2., -1., 0.], [1., 0., 0.]),
+            ([0.5, -1., 3.], [0.5, 0., 0.]),
+            ([1., 1., 0.], [0.5, 0.5, 0.]),
+            ([-1., 0.5, 0.], [0., 0.5, 0.]),
+        ];
+        for &(p, expected) in &cases {
+            let q = closest_point_on_triangle(&na::Point3::new(p[0], p[1], p[2]), &t);
+            assert_relative_eq!(q, na::Point3::new(expected[0], expected[1], expected[2]));
+        }
+    }
+
+    #[test]
+    fn ray_triangle() {
+        let t = [
+            na::Point3::new(0., 0., 0.),
+            na::Point3::new(1., 0., 0.),
+            na::Point3::new(0., 1., 0.),
+        ];
+        let down = na::Vector3::new(0., 0., -2.);
+        let hit = ray_triangle_intersection(&na::Point3::new(0.2, 0.2, 1.), &down, &t);
+        assert_relative_eq!(hit.unwrap(), 0.5);
+        let behind = ray_triangle_intersection(&na::Point3::new(0.2, 0.2, -1.), &down, &t);
+        assert_relative_eq!(behind.unwrap(), -0.5);
+        assert!(ray_triangle_intersection(&na::Point3::new(1., 1., 1.), &down, &t).is_none());
+        let parallel = na::Vector3::new(1., 0., 0.);
+        assert!(ray_triangle_intersection(&na::Point3::new(0., 0.2, 0.), &parallel, &t).is_none());
+    }
+
+    // Mesh of 100 random triangles in the unit cube.
+    fn random_triangles() -> Mesh<f64> {
+        let mut rng = rand::thread_rng();
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..100 {
+            for _ in 0..3 {
+                mesh.vertices
+                    .push([rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()]);
+            }
+            mesh.faces.push([3 * i, 3 * i + 1, 3 * i + 2]);
+        }
+        mesh
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut rng = rand::thread_rng();
+        let bvh = Bvh::new(&random_triangles());
+        for _ in 0..100 {
+            let p = na::Point3::new(rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()) * 2.;
+            let expected = bvh
+                .triangles
+                .iter()
+                .map(|t| na::distance(&p, &closest_point_on_triangle(&p, t)))
+                .fold(f64::INFINITY, f64::min);
+            assert_relative_eq!(bvh.distance(&p).unwrap(), expected);
+        }
+        assert!(Bvh::<f64>::new(&Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        })
+        .distance(&na::Point3::origin())
+        .is_none());
+    }
+
+    #[test]
+    fn ray_intersect_matches_brute_force() {
+        let mut rng = rand::thread_rng();
+        let bvh = Bvh::new(&random_triangles());
+        for _ in 0..100 {
+            let origin = na::Point3::new(rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>());
+            let direction = na::Vector3::new(
+                rng.gen::<f64>() - 0.5,
+                rng.gen::<f64>() - 0.5,
+                rng.gen::<f64>() - 0.5,
+            );
+            let expected = bvh
+                .triangles
+                .iter()
+                .filter_map(|t| ray_triangle_intersection(&origin, &direction, t))
+                .filter(|&t| t > 0.)
+                .fold(None, |best: Option<f64>, t| {
+                    Some(best.map_or(t, |b| b.min(t)))
+                });
+            assert_eq!(
+                bvh.ray_intersect(&origin, &direction, 0.).map(|(t, _)| t),
+                expected
+            );
+        }
+    }
+}
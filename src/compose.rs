@@ -0,0 +1,152 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use finite_difference;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+// Combination of two implicit functions with value f(a.value(p), b.value(p)).
+struct Composed<S: Debug + RealField, A, B, F> {
+    a: A,
+    b: B,
+    f: F,
+    // Whether f is min or max, so the normal of the selected function is exact.
+    selects: bool,
+    bbox: BoundingBox<S>,
+}
+
+impl<S, A, B, F> ImplicitFunction<S> for Composed<S, A, B, F>
+where
+    S: Debug + RealField + Float + From<f32>,
+    A: ImplicitFunction<S>,
+    B: ImplicitFunction<S>,
+    F: Fn(S, S) -> S,
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        (self.f)(self.a.value(p), self.b.value(p))
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        if self.selects {
+            let av = self.a.value(p);
+            return if (self.f)(av, self.b.value(p)) == av {
+                self.a.normal(p)
+            } else {
+                self.b.normal(p)
+            };
+        }
+        let dim = self.bbox.dim();
+        let mut h = Float::max(dim.x, Float::max(dim.y, dim.z)) * From::from(1e-5f32);
+        if !Float::is_finite(h) || h == From::from(0f32) {
+            h = From::from(1e-5f32);
+        }
+        finite_difference::normal(|p| self.value(p), p, h)
+    }
+}
+
+/// Combine a and b into an implicit function with value f(a.value(p), b.value(p)).
+/// The bounding box is the union of both bounding boxes. Normals are computed via finite
+/// differences.
+pub fn compose<S, A, B, F>(a: A, b: B, f: F) -> impl ImplicitFunction<S>
+where
+    S: Debug + RealField + Float + From<f32>,
+    A: ImplicitFunction<S>,
+    B: ImplicitFunction<S>,
+    F: Fn(S, S) -> S,
+{
+    let bbox = a.bbox().union(b.bbox());
+    Composed {
+        a,
+        b,
+        f,
+        selects: false,
+        bbox,
+    }
+}
+
+/// Union of a and b. Normals are the exact normals of the closer function.
+pub fn union<S, A, B>(a: A, b: B) -> impl ImplicitFunction<S>
+where
+    S: Debug + RealField + Float + From<f32>,
+    A: ImplicitFunction<S>,
+    B: ImplicitFunction<S>,
+{
+    let bbox = a.bbox().union(b.bbox());
+    Composed {
+        a,
+        b,
+        f: Float::min,
+        selects: true,
+        bbox,
+    }
+}
+
+/// Intersection of a and b. Normals are the exact normals of the farther function.
+pub fn intersection<S, A, B>(a: A, b: B) -> impl ImplicitFunction<S>
+where
+    S: Debug + RealField + Float + From<f32>,
+    A: ImplicitFunction<S>,
+    B: ImplicitFunction<S>,
+{
+    let bbox = a.bbox().intersection(b.bbox());
+    Composed {
+        a,
+        b,
+        f: Float::max,
+        selects: true,
+        bbox,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util;
+
+    // Sphere of radius 1 around (x, 0, 0).
+    fn sphere(x: f64) -> test_util::Sphere {
+        test_util::Sphere::new(na::Point3::new(x, 0., 0.), 1.)
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let u = union(sphere(0.), sphere(1.5));
+        assert_relative_eq!(u.value(&na::Point3::new(-1., 0., 0.)), 0.);
+        assert_relative_eq!(u.value(&na::Point3::new(2.5, 0., 0.)), 0.);
+        assert_relative_eq!(u.bbox().min.x, -1.);
+        assert_relative_eq!(u.bbox().max.x, 2.5);
+        assert_relative_eq!(
+            u.normal(&na::Point3::new(-2., 0., 0.)),
+            na::Vector3::new(-1., 0., 0.)
+        );
+
+        let i = intersection(sphere(0.), sphere(1.5));
+        assert_relative_eq!(i.value(&na::Point3::new(0.75, 0., 0.)), -0.25);
+        assert_relative_eq!(i.value(&na::Point3::new(1.25, 0., 0.)), 0.25);
+        assert_relative_eq!(i.bbox().min.x, 0.5);
+        assert_relative_eq!(i.bbox().max.x, 1.);
+    }
+
+    #[test]
+    fn blend() {
+        let difference = compose(sphere(0.), sphere(1.5), |a, b| f64::max(a, -b));
+        assert_relative_eq!(
+            difference.normal(&na::Point3::new(0.3, 0., 0.)),
+            na::Vector3::new(1., 0., 0.),
+            epsilon = 1e-6
+        );
+        let blend = compose(sphere(0.), sphere(1.5), |a, b| (a + b) / 2.);
+        assert_relative_eq!(blend.value(&na::Point3::new(0., 0., 0.)), -0.25);
+        let p = na::Point3::new(0.5, 1., 0.);
+        let expected = ((p - sphere(0.).center).normalize() + (p - sphere(1.5).center).normalize())
+            .normalize();
+        assert_relative_eq!(blend.normal(&p), expected, epsilon = 1e-6);
+        // On the bisector the blend equals both arguments, but its normal is neither of theirs.
+        let p = na::Point3::new(0.75, 0.5, 0.);
+        assert_eq!(blend.value(&p), sphere(0.).value(&p));
+        assert_relative_eq!(blend.normal(&p), na::Vector3::y(), epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,48 @@
+use alga::general::RealField;
+use na;
+
+/// One of the three coordinate axes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// The x axis.
+    X,
+    /// The y axis.
+    Y,
+    /// The z axis.
+    Z,
+}
+
+impl Axis {
+    /// Return the index of the coordinate along this axis, i.e. 0 for X, 1 for Y and 2 for Z.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Intersection of a mesh with a plane perpendicular to one of the coordinate axes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contour<S: RealField> {
+    /// The axis perpendicular to the plane.
+    pub axis: Axis,
+    /// The position of the plane along axis.
+    pub offset: S,
+    /// The polylines the plane intersects the mesh in. Closed polylines repeat their first point
+    /// at the end.
+    pub polylines: Vec<Vec<na::Point3<S>>>,
+}
+
+impl<S: RealField> Contour<S> {
+    /// Return true, if all polylines are closed.
+    pub fn is_closed(&self) -> bool {
+        self.polylines
+            .iter()
+            .all(|l| l.len() > 1 && l.first() == l.last())
+    }
+    /// Return the total length of all polylines.
+    pub fn length(&self) -> S {
+        self.polylines
+            .iter()
+            .flat_map(|l| l.windows(2))
+            .fold(S::zero(), |sum, w| sum + na::distance(&w[0], &w[1]))
+    }
+}
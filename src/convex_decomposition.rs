@@ -0,0 +1,273 @@
+use alga::general::RealField;
+use mesh::Mesh;
+use na;
+use num_traits::Float;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+// Affinity of adjacent faces is exp(-CONCAVITY_WEIGHT * concavity), so cuts follow concave edges.
+const CONCAVITY_WEIGHT: f32 = 10.;
+// Edges count as concave, if a face lies further than this (relative to the edge length) in
+// front of the plane of its neighbor.
+const CONVEXITY_TOLERANCE: f32 = 1e-6;
+// Number of power iterations to approximate the Fiedler vector of a part.
+const POWER_ITERATIONS: usize = 1000;
+
+// Edge of the dual graph between faces f and g.
+struct DualEdge<S> {
+    f: usize,
+    g: usize,
+    // 1 - cos of the dihedral angle for concave edges, zero otherwise.
+    concavity: S,
+    affinity: S,
+}
+
+fn point<S: RealField>(mesh: &Mesh<S>, v: usize) -> na::Point3<S> {
+    let v = mesh.vertices[v];
+    na::Point3::new(v[0], v[1], v[2])
+}
+
+fn face_normal<S: RealField>(mesh: &Mesh<S>, f: usize) -> na::Vector3<S> {
+    let face = mesh.faces[f];
+    let (a, b, c) = (
+        point(mesh, face[0]),
+        point(mesh, face[1]),
+        point(mesh, face[2]),
+    );
+    (b - a)
+        .cross(&(c - a))
+        .try_normalize(S::zero())
+        .unwrap_or_else(na::Vector3::zeros)
+}
+
+// Return the edges between faces sharing a manifold edge of mesh.
+fn dual_edges<S: 'static + RealField + Float + From<f32> + Debug>(
+    mesh: &Mesh<S>,
+) -> Vec<DualEdge<S>> {
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (f, face) in mesh.faces.iter().enumerate() {
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            edge_faces.entry((a.min(b), a.max(b))).or_default().push(f);
+        }
+    }
+    let mut edges: Vec<_> = edge_faces.into_iter().collect();
+    edges.sort_by_key(|&(edge, _)| edge);
+    let tolerance: S = From::from(CONVEXITY_TOLERANCE);
+    let weight: S = From::from(CONCAVITY_WEIGHT);
+    edges
+        .into_iter()
+        .filter_map(|((a, b), faces)| match faces[..] {
+            [f, g] => Some((a, b, f, g)),
+            _ => None,
+        })
+        .map(|(a, b, f, g)| {
+            let (nf, ng) = (face_normal(mesh, f), face_normal(mesh, g));
+            let length = na::distance(&point(mesh, a), &point(mesh, b));
+            let opposite = |face: usize| {
+                let v = *mesh.faces[face]
+                    .iter()
+                    .find(|&&v| v != a && v != b)
+                    .unwrap();
+                point(mesh, v) - point(mesh, a)
+            };
+            let concave =
+                Float::max(nf.dot(&opposite(g)), ng.dot(&opposite(f))) > tolerance * length;
+            let concavity = if concave {
+                S::one() - nf.dot(&ng)
+            } else {
+                S::zero()
+            };
+            DualEdge {
+                f,
+                g,
+                concavity,
+                affinity: Float::exp(-weight * concavity),
+            }
+        })
+        .collect()
+}
+
+// Split part (a list of faces) into two with a normalized cut of its dual graph. Disconnected
+// parts are split into their first connected component and the rest.
+fn split<S: 'static + RealField + Float + From<f32> + Debug>(
+    part: &[usize],
+    edges: &[DualEdge<S>],
+) -> (Vec<usize>, Vec<usize>) {
+    let local: HashMap<usize, usize> = part.iter().enumerate().map(|(i, &f)| (f, i)).collect();
+    let n = part.len();
+    let mut neighbors = vec![Vec::new(); n];
+    for e in edges {
+        if let (Some(&i), Some(&j)) = (local.get(&e.f), local.get(&e.g)) {
+            neighbors[i].push((j, e.affinity));
+            neighbors[j].push((i, e.affinity));
+        }
+    }
+    // Flood fill the first component.
+    let mut in_first = vec![false; n];
+    in_first[0] = true;
+    let mut stack = vec![0];
+    let mut component_size = 1;
+    while let Some(i) = stack.pop() {
+        for &(j, _) in &neighbors[i] {
+            if !in_first[j] {
+                in_first[j] = true;
+                component_size += 1;
+                stack.push(j);
+            }
+        }
+    }
+    let in_a = if component_size < n {
+        in_first
+    } else {
+        normalized_cut(&neighbors)
+    };
+    let side = |a: bool| {
+        part.iter()
+            .zip(&in_a)
+            .filter(|&(_, &in_a)| in_a == a)
+            .map(|(&f, _)| f)
+            .collect()
+    };
+    (side(true), side(false))
+}
+
+// Return the side of each node of the connected graph with weighted adjacency lists neighbors,
+// according to the Fiedler vector of its normalized Laplacian. The threshold minimizes the
+// normalized cut.
+fn normalized_cut<S: 'static + RealField + Float + From<f32> + Debug>(
+    neighbors: &[Vec<(usize, S)>],
+) -> Vec<bool> {
+    let n = neighbors.len();
+    let degrees: Vec<S> = neighbors
+        .iter()
+        .map(|adjacent| adjacent.iter().fold(S::zero(), |sum, &(_, w)| sum + w))
+        .collect();
+    let sqrt_degrees: Vec<S> = degrees.iter().map(|&d| Float::sqrt(d)).collect();
+    // Largest eigenvector of D^-1/2 W D^-1/2, which is projected out.
+    let norm = Float::sqrt(degrees.iter().fold(S::zero(), |sum, &d| sum + d));
+    let top: Vec<S> = sqrt_degrees.iter().map(|&d| d / norm).collect();
+    // Power iteration with (I + D^-1/2 W D^-1/2) / 2, whose eigenvalues are non-negative.
+    let half: S = From::from(0.5f32);
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut x: Vec<S> = (0..n).map(|_| From::from(rng.gen::<f32>() - 0.5)).collect();
+    for _ in 0..POWER_ITERATIONS {
+        let mut y: Vec<S> = (0..n)
+            .map(|i| {
+                let sum = neighbors[i]
+                    .iter()
+                    .fold(S::zero(), |sum, &(j, w)| sum + w * x[j] / sqrt_degrees[j]);
+                (x[i] + sum / sqrt_degrees[i]) * half
+            })
+            .collect();
+        let projection = y
+            .iter()
+            .zip(&top)
+            .fold(S::zero(), |sum, (&a, &b)| sum + a * b);
+        for (a, &b) in y.iter_mut().zip(&top) {
+            *a -= projection * b;
+        }
+        let length = Float::sqrt(y.iter().fold(S::zero(), |sum, &a| sum + a * a));
+        if length == S::zero() {
+            break;
+        }
+        x = y.into_iter().map(|a| a / length).collect();
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| {
+        let (a, b) = (x[i] / sqrt_degrees[i], x[j] / sqrt_degrees[j]);
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    });
+    // Sweep the threshold along the order and keep the smallest normalized cut.
+    let total = degrees.iter().fold(S::zero(), |sum, &d| sum + d);
+    let mut in_a = vec![false; n];
+    let (mut cut, mut volume) = (S::zero(), S::zero());
+    let mut best = (S::infinity(), 1);
+    for (k, &i) in order.iter().enumerate().take(n - 1) {
+        in_a[i] = true;
+        volume += degrees[i];
+        for &(j, w) in &neighbors[i] {
+            if in_a[j] {
+                cut -= w;
+            } else {
+                cut += w;
+            }
+        }
+        let ncut = cut / volume + cut / (total - volume);
+        if ncut < best.0 {
+            best = (ncut, k + 1);
+        }
+    }
+    let mut result = vec![false; n];
+    for &i in &order[..best.1] {
+        result[i] = true;
+    }
+    result
+}
+
+// Return the faces of mesh in part as separate mesh.
+pub fn part_mesh<S: RealField>(mesh: &Mesh<S>, part: &[usize]) -> Mesh<S> {
+    let mut index_map = HashMap::new();
+    let mut result = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
+        normals: Vec::new(),
+    };
+    for &f in part {
+        let mut face = mesh.faces[f];
+        for v in face.iter_mut() {
+            let vertices = &mut result.vertices;
+            let normals = &mut result.normals;
+            *v = *index_map.entry(*v).or_insert_with(|| {
+                vertices.push(mesh.vertices[*v]);
+                if !mesh.normals.is_empty() {
+                    normals.push(mesh.normals[*v]);
+                }
+                vertices.len() - 1
+            });
+        }
+        result.faces.push(face);
+    }
+    result
+}
+
+// Split mesh into at most max_parts parts, that have no concave edges between their faces. The
+// part with the largest total concavity is split by a normalized cut of the dual graph, whose
+// edge weights decrease with concavity, until all parts are convex or max_parts is reached.
+pub fn decompose<S: 'static + RealField + Float + From<f32> + Debug>(
+    mesh: &Mesh<S>,
+    max_parts: usize,
+) -> Vec<Mesh<S>> {
+    if mesh.faces.is_empty() {
+        return Vec::new();
+    }
+    let edges = dual_edges(mesh);
+    let mut part_of = vec![0; mesh.faces.len()];
+    let mut parts = vec![(0..mesh.faces.len()).collect::<Vec<_>>()];
+    while parts.len() < max_parts {
+        let mut concavities = vec![S::zero(); parts.len()];
+        for e in &edges {
+            if part_of[e.f] == part_of[e.g] {
+                concavities[part_of[e.f]] += e.concavity;
+            }
+        }
+        let (most_concave, &concavity) = concavities
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(Ordering::Equal))
+            .unwrap();
+        if concavity == S::zero() {
+            break;
+        }
+        let (a, b) = split(&parts[most_concave], &edges);
+        for &f in &b {
+            part_of[f] = parts.len();
+        }
+        parts[most_concave] = a;
+        parts.push(b);
+    }
+    parts.iter().map(|part| part_mesh(mesh, part)).collect()
+}
@@ -0,0 +1,351 @@
+use alga::general::RealField;
+use mesh::Mesh;
+use na;
+use num_traits::Float;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::Debug;
+
+// Weight of the constraint planes at sharp edges relative to the squared edge length. This is
+// large enough to pin vertices to the feature lines they lie on.
+const SHARP_EDGE_WEIGHT: f32 = 1e4;
+
+// Edge keys are ordered vertex pairs.
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Error quadric of the plane with unit normal n through p, scaled by weight.
+fn plane_quadric<S: RealField>(n: &na::Vector3<S>, p: &na::Point3<S>, weight: S) -> na::Matrix4<S> {
+    let h = na::Vector4::new(n.x, n.y, n.z, -n.dot(&p.coords));
+    h * h.transpose() * weight
+}
+
+// Sum of the weighted squared distances of p to all planes of quadric q.
+fn quadric_error<S: RealField>(q: &na::Matrix4<S>, p: &na::Point3<S>) -> S {
+    let h = na::Vector4::new(p.x, p.y, p.z, S::one());
+    h.dot(&(q * h))
+}
+
+// Candidate for collapsing the edge (u, v) into position.
+struct Collapse<S: RealField> {
+    cost: S,
+    u: usize,
+    v: usize,
+    // Versions of u and v at the time this candidate was created.
+    versions: (usize, usize),
+    position: na::Point3<S>,
+}
+
+impl<S: RealField> PartialEq for Collapse<S> {
+    fn eq(&self, other: &Collapse<S>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S: RealField> Eq for Collapse<S> {}
+
+impl<S: RealField> PartialOrd for Collapse<S> {
+    fn partial_cmp(&self, other: &Collapse<S>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Reversed, so the BinaryHeap pops the cheapest collapse first. Ties are broken by the edge,
+// so the result does not depend on the order candidates were pushed in.
+impl<S: RealField> Ord for Collapse<S> {
+    fn cmp(&self, other: &Collapse<S>) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| (other.u, other.v).cmp(&(self.u, self.v)))
+    }
+}
+
+struct Decimation<S: RealField> {
+    positions: Vec<na::Point3<S>>,
+    quadrics: Vec<na::Matrix4<S>>,
+    faces: Vec<Option<[usize; 3]>>,
+    num_faces: usize,
+    // Faces adjacent to each vertex. May contain faces that were removed or no longer contain
+    // the vertex.
+    vertex_faces: Vec<Vec<usize>>,
+    // Incremented each time a vertex is changed, invalidating all its candidates in heap.
+    versions: Vec<usize>,
+    // Edges that must not be collapsed.
+    fixed_edges: HashSet<(usize, usize)>,
+    heap: BinaryHeap<Collapse<S>>,
+}
+
+impl<S: 'static + RealField + Float + From<f32> + Debug> Decimation<S> {
+    fn new(mesh: &Mesh<S>, sharp_angle: S) -> Decimation<S> {
+        let positions: Vec<na::Point3<S>> = mesh
+            .vertices
+            .iter()
+            .map(|v| na::Point3::new(v[0], v[1], v[2]))
+            .collect();
+        let mut quadrics = vec![na::Matrix4::zeros(); positions.len()];
+        let mut vertex_faces = vec![Vec::new(); positions.len()];
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        let mut normals = Vec::with_capacity(mesh.faces.len());
+        let half: S = From::from(0.5f32);
+        for (i, face) in mesh.faces.iter().enumerate() {
+            let [a, b, c] = [positions[face[0]], positions[face[1]], positions[face[2]]];
+            let scaled_normal = (b - a).cross(&(c - a));
+            let area = scaled_normal.norm() * half;
+            let normal = scaled_normal.normalize();
+            if area > S::zero() {
+                let q = plane_quadric(&normal, &a, area);
+                for &v in face {
+                    quadrics[v] += q;
+                }
+            }
+            normals.push(normal);
+            for j in 0..3 {
+                vertex_faces[face[j]].push(i);
+                edge_faces
+                    .entry(edge_key(face[j], face[(j + 1) % 3]))
+                    .or_default()
+                    .push(i);
+            }
+        }
+        // Visit edges in a fixed order, so quadrics are summed in the same order on each run.
+        let mut edges: Vec<(usize, usize)> = edge_faces.keys().cloned().collect();
+        edges.sort();
+        // Boundary and non-manifold edges are fixed as well as sharp edges.
+        let mut fixed_edges = HashSet::new();
+        let sharp_weight: S = From::from(SHARP_EDGE_WEIGHT);
+        for &(a, b) in &edges {
+            let faces = &edge_faces[&(a, b)];
+            let sharp = match faces[..] {
+                [f, g] => normals[f].angle(&normals[g]) > sharp_angle,
+                _ => true,
+            };
+            if !sharp {
+                continue;
+            }
+            fixed_edges.insert((a, b));
+            let edge = positions[b] - positions[a];
+            for &f in faces {
+                // Plane containing the edge, perpendicular to the face.
+                let n = edge.cross(&normals[f]).normalize();
+                if n.iter().all(|c| Float::is_finite(*c)) {
+                    let q = plane_quadric(&n, &positions[a], sharp_weight * edge.norm_squared());
+                    quadrics[a] += q;
+                    quadrics[b] += q;
+                }
+            }
+        }
+        let mut result = Decimation {
+            versions: vec![0; positions.len()],
+            positions,
+            quadrics,
+            faces: mesh.faces.iter().map(|&f| Some(f)).collect(),
+            num_faces: mesh.faces.len(),
+            vertex_faces,
+            fixed_edges,
+            heap: BinaryHeap::new(),
+        };
+        for (u, v) in edges {
+            result.push_candidate(u, v);
+        }
+        result
+    }
+    // Iterate over the indexes of all faces currently adjacent to v.
+    fn live_faces<'b>(&'b self, v: usize) -> impl Iterator<Item = usize> + 'b {
+        let mut seen = HashSet::new();
+        self.vertex_faces[v].iter().cloned().filter(move |&f| {
+            seen.insert(f)
+                && match self.faces[f] {
+                    Some(face) => face.contains(&v),
+                    None => false,
+                }
+        })
+    }
+    fn neighbours(&self, v: usize) -> HashSet<usize> {
+        self.live_faces(v)
+            .flat_map(|f| self.faces[f].unwrap().to_vec())
+            .filter(|&w| w != v)
+            .collect()
+    }
+    fn push_candidate(&mut self, u: usize, v: usize) {
+        if self.fixed_edges.contains(&edge_key(u, v)) {
+            return;
+        }
+        let q = self.quadrics[u] + self.quadrics[v];
+        let (pu, pv) = (self.positions[u], self.positions[v]);
+        let midpoint = na::center(&pu, &pv);
+        let mut options = vec![pu, pv, midpoint];
+        // The position minimizing the error. Ill-conditioned systems may yield far away
+        // positions, which are discarded.
+        let a: na::Matrix3<S> = q.fixed_slice::<na::U3, na::U3>(0, 0).into_owned();
+        if let Some(inverse) = a.try_inverse() {
+            let optimum = na::Point3::from(-(inverse * q.fixed_slice::<na::U3, na::U1>(0, 3)));
+            if na::distance(&optimum, &midpoint) <= na::distance(&pu, &pv) {
+                options.push(optimum);
+            }
+        }
+        let (cost, position) = options
+            .into_iter()
+            .map(|p| (quadric_error(&q, &p), p))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+            .unwrap();
+        self.heap.push(Collapse {
+            cost,
+            u,
+            v,
+            versions: (self.versions[u], self.versions[v]),
+            position,
+        });
+    }
+    // Return true, if collapsing (u, v) into position keeps the mesh manifold and flips no face.
+    fn is_valid(&self, u: usize, v: usize, position: &na::Point3<S>) -> bool {
+        // Link condition: The common neighbours of u and v must be the opposite vertices of the
+        // faces containing both.
+        let opposite: HashSet<usize> = self
+            .live_faces(u)
+            .filter_map(|f| {
+                let face = self.faces[f].unwrap();
+                if face.contains(&v) {
+                    face.iter().cloned().find(|&w| w != u && w != v)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let common: HashSet<usize> = self
+            .neighbours(u)
+            .intersection(&self.neighbours(v))
+            .cloned()
+            .collect();
+        if common != opposite {
+            return false;
+        }
+        for &w in &[u, v] {
+            for f in self.live_faces(w) {
+                let face = self.faces[f].unwrap();
+                if face.contains(&u) && face.contains(&v) {
+                    continue;
+                }
+                let before = face.iter().map(|&i| self.positions[i]).collect::<Vec<_>>();
+                let after = face
+                    .iter()
+                    .map(|&i| if i == w { *position } else { self.positions[i] })
+                    .collect::<Vec<_>>();
+                let normal = |p: &[na::Point3<S>]| (p[1] - p[0]).cross(&(p[2] - p[0]));
+                if normal(&before).dot(&normal(&after)) <= S::zero() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    // Collapse v into u, which is moved to position.
+    fn collapse(&mut self, u: usize, v: usize, position: na::Point3<S>) {
+        let v_faces: Vec<usize> = self.live_faces(v).collect();
+        for f in v_faces {
+            let mut face = self.faces[f].unwrap();
+            if face.contains(&u) {
+                self.faces[f] = None;
+                self.num_faces -= 1;
+                continue;
+            }
+            for i in face.iter_mut() {
+                if *i == v {
+                    *i = u;
+                }
+            }
+            self.faces[f] = Some(face);
+            self.vertex_faces[u].push(f);
+        }
+        let fixed: Vec<(usize, usize)> = self
+            .fixed_edges
+            .iter()
+            .filter(|&&(a, b)| a == v || b == v)
+            .cloned()
+            .collect();
+        for (a, b) in fixed {
+            self.fixed_edges.remove(&(a, b));
+            let w = if a == v { b } else { a };
+            self.fixed_edges.insert(edge_key(u, w));
+        }
+        self.positions[u] = position;
+        let q = self.quadrics[v];
+        self.quadrics[u] += q;
+        self.versions[u] += 1;
+        self.versions[v] += 1;
+        for w in self.neighbours(u) {
+            self.push_candidate(u, w);
+        }
+    }
+    // Collapse the valid edges with the least error, for which should_collapse returns true,
+    // until at most target faces remain.
+    fn run<F: Fn(usize, usize, S) -> bool>(&mut self, target: usize, should_collapse: F) {
+        while self.num_faces > target {
+            let c = match self.heap.pop() {
+                Some(c) => c,
+                None => break,
+            };
+            if c.versions != (self.versions[c.u], self.versions[c.v])
+                || !self.is_valid(c.u, c.v, &c.position)
+                || !should_collapse(c.u, c.v, c.cost)
+            {
+                continue;
+            }
+            self.collapse(c.u, c.v, c.position);
+        }
+    }
+    fn into_mesh(self) -> Mesh<S> {
+        let mut index_map = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut faces = Vec::with_capacity(self.num_faces);
+        for face in self.faces.iter().filter_map(|&f| f) {
+            let mut new_face = [0; 3];
+            for (new, &old) in new_face.iter_mut().zip(&face) {
+                *new = *index_map.entry(old).or_insert_with(|| {
+                    let p = self.positions[old];
+                    vertices.push([p.x, p.y, p.z]);
+                    vertices.len() - 1
+                });
+            }
+            faces.push(new_face);
+        }
+        Mesh {
+            vertices,
+            faces,
+            normals: Vec::new(),
+        }
+    }
+}
+
+// Decimate mesh with quadric error metrics (Garland and Heckbert), by collapsing the edges with
+// the least error, until at most target faces remain or no edge can be collapsed anymore.
+// Edges where the normals of the adjacent faces differ by more than sharp_angle, as well as
+// boundary edges, are never collapsed and constrain their vertices to the feature lines.
+pub fn decimate<S: 'static + RealField + Float + From<f32> + Debug>(
+    mesh: &Mesh<S>,
+    target: usize,
+    sharp_angle: S,
+) -> Mesh<S> {
+    let mut decimation = Decimation::new(mesh, sharp_angle);
+    decimation.run(target, |_, _, _| true);
+    decimation.into_mesh()
+}
+
+// Decimate mesh with quadric error metrics like decimate, but collapse each valid edge (u, v)
+// with error e in order of increasing error, if and only if should_collapse(u, v, e) returns true.
+// Only boundary and non-manifold edges are fixed.
+pub fn decimate_with_callback<S, F>(mesh: &Mesh<S>, should_collapse: F) -> Mesh<S>
+where
+    S: 'static + RealField + Float + From<f32> + Debug,
+    F: Fn(usize, usize, S) -> bool,
+{
+    let mut decimation = Decimation::new(mesh, S::pi());
+    decimation.run(0, should_collapse);
+    decimation.into_mesh()
+}
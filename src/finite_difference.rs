@@ -0,0 +1,44 @@
+use alga::general::RealField;
+use na;
+
+// Approximate the gradient of f at p using central differences with step size h.
+pub fn gradient<S, F>(f: F, p: &na::Point3<S>, h: S) -> na::Vector3<S>
+where
+    S: RealField + From<f32>,
+    F: Fn(&na::Point3<S>) -> S,
+{
+    let two: S = From::from(2f32);
+    let mut result = na::Vector3::zeros();
+    for i in 0..3 {
+        let mut forward = *p;
+        forward[i] += h;
+        let mut backward = *p;
+        backward[i] -= h;
+        result[i] = (f(&forward) - f(&backward)) / (two * h);
+    }
+    result
+}
+
+// Approximate the normal of the surface defined by f at p, i.e. the normalized gradient.
+pub fn normal<S, F>(f: F, p: &na::Point3<S>, h: S) -> na::Vector3<S>
+where
+    S: RealField + From<f32>,
+    F: Fn(&na::Point3<S>) -> S,
+{
+    gradient(f, p, h).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_of_sphere() {
+        let sphere = |p: &na::Point3<f64>| p.coords.norm() - 1.;
+        let g = gradient(sphere, &na::Point3::new(2., 0., 0.), 1e-4);
+        assert_relative_eq!(g, na::Vector3::new(1., 0., 0.), epsilon = 1e-6);
+        let n = normal(sphere, &na::Point3::new(0., 3., 3.), 1e-4);
+        let expected = na::Vector3::new(0., 1., 1.).normalize();
+        assert_relative_eq!(n, expected, epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,200 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use finite_difference;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Transformation applied by FractalSdf in every iteration. It receives the object of the
+/// previous iteration along with the index of the current iteration and returns the refined
+/// object.
+pub type FractalTransform<S> =
+    Box<dyn Fn(Box<dyn ImplicitFunction<S>>, usize) -> Box<dyn ImplicitFunction<S>> + Send + Sync>;
+
+/// Implicit function, that is generated by recursively applying a transform to a base function.
+/// This allows to define fractal surfaces (e.g. from L-systems) of arbitrary detail.
+pub struct FractalSdf<S: Debug + RealField> {
+    iterations: usize,
+    transform: FractalTransform<S>,
+    // The result of applying transform iterations times to base.
+    object: Box<dyn ImplicitFunction<S>>,
+}
+
+impl<S: Debug + RealField> FractalSdf<S> {
+    /// Create a new FractalSdf.
+    /// base: function to start with
+    /// iterations: how often transform is applied to base
+    /// transform: refinement step, called with the object of the previous step and the iteration
+    /// index (starting at 0).
+    pub fn new(
+        base: Box<dyn ImplicitFunction<S>>,
+        iterations: usize,
+        transform: FractalTransform<S>,
+    ) -> FractalSdf<S> {
+        let object = (0..iterations).fold(base, |object, i| transform(object, i));
+        FractalSdf {
+            iterations,
+            transform,
+            object,
+        }
+    }
+    /// The number of times transform was applied to the base function.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+    /// Apply the transform for one more iteration.
+    pub fn refine(self) -> FractalSdf<S> {
+        let object = (self.transform)(self.object, self.iterations);
+        FractalSdf {
+            iterations: self.iterations + 1,
+            transform: self.transform,
+            object,
+        }
+    }
+}
+
+impl<S: Debug + RealField> ImplicitFunction<S> for FractalSdf<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        self.object.bbox()
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.object.value(p)
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        self.object.normal(p)
+    }
+}
+
+// Axis aligned cube centered at the origin.
+struct Cube<S: Debug + RealField> {
+    half_size: S,
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for Cube<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        let q = p.coords.map(|c| Float::abs(c) - self.half_size);
+        let zero: S = From::from(0f32);
+        let outside = q.map(|c| Float::max(c, zero)).norm();
+        let inside = Float::min(Float::max(q.x, Float::max(q.y, q.z)), zero);
+        outside + inside
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        finite_difference::normal(|p| self.value(p), p, self.half_size * From::from(1e-4f32))
+    }
+}
+
+// One level of the Menger sponge: Removes a periodic cross of holes with a period of
+// 2 / 3^level from inner.
+struct MengerLevel<S: Debug + RealField> {
+    inner: Box<dyn ImplicitFunction<S>>,
+    scale: S,
+}
+
+impl<S: Debug + RealField + Float + From<f32>> MengerLevel<S> {
+    fn holes(&self, p: &na::Point3<S>) -> S {
+        let one: S = From::from(1f32);
+        let two: S = From::from(2f32);
+        let three: S = From::from(3f32);
+        let r = p.coords.map(|c| {
+            let c = c * self.scale;
+            let a = c - two * Float::floor(c / two) - one;
+            Float::abs(one - three * Float::abs(a))
+        });
+        let da = Float::max(r.x, r.y);
+        let db = Float::max(r.y, r.z);
+        let dc = Float::max(r.z, r.x);
+        (Float::min(da, Float::min(db, dc)) - one) / (self.scale * three)
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for MengerLevel<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        self.inner.bbox()
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        Float::max(self.inner.value(p), self.holes(p))
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        if self.inner.value(p) >= self.holes(p) {
+            return self.inner.normal(p);
+        }
+        let h: S = From::from(1e-4f32);
+        finite_difference::normal(|p| self.holes(p), p, h / self.scale)
+    }
+}
+
+/// Create a Menger (Sierpinski) sponge spanning [-1, 1] in each dimension.
+/// iterations: the number of levels of holes.
+pub fn sierpinski_sponge<S: 'static + Debug + RealField + Float + From<f32>>(
+    iterations: usize,
+) -> Box<dyn ImplicitFunction<S>> {
+    let one: S = From::from(1f32);
+    let three: S = From::from(3f32);
+    let cube = Cube {
+        half_size: one,
+        bbox: BoundingBox::new(
+            &na::Point3::new(-one, -one, -one),
+            &na::Point3::new(one, one, one),
+        ),
+    };
+    Box::new(FractalSdf::new(
+        Box::new(cube),
+        iterations,
+        Box::new(move |inner, i| {
+            Box::new(MengerLevel {
+                inner,
+                scale: Float::powi(three, i as i32),
+            })
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sponge() {
+        let solid = sierpinski_sponge::<f64>(0);
+        assert!(solid.value(&na::Point3::new(0., 0., 0.)) < 0.);
+        assert_relative_eq!(solid.value(&na::Point3::new(2., 0., 0.)), 1.);
+
+        let sponge = sierpinski_sponge::<f64>(1);
+        // The center is removed by the first level of holes.
+        assert_relative_eq!(sponge.value(&na::Point3::new(0., 0., 0.)), 1. / 3.);
+        // The corners remain.
+        assert!(sponge.value(&na::Point3::new(0.9, 0.9, 0.9)) < 0.);
+
+        let sponge = sierpinski_sponge::<f64>(2);
+        // The center of a corner sub-cube is removed by the second level.
+        assert!(sponge.value(&na::Point3::new(2. / 3., 2. / 3., 2. / 3.)) > 0.);
+        assert_relative_eq!(
+            sponge.normal(&na::Point3::new(1.5, 0.1, 0.2)),
+            na::Vector3::new(1., 0., 0.),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn refine() {
+        let sponge = FractalSdf::new(
+            sierpinski_sponge::<f64>(0),
+            0,
+            Box::new(|inner, i| {
+                Box::new(MengerLevel {
+                    inner,
+                    scale: Float::powi(3., i as i32),
+                })
+            }),
+        );
+        assert_eq!(sponge.iterations(), 0);
+        let sponge = sponge.refine();
+        assert_eq!(sponge.iterations(), 1);
+        assert!(sponge.value(&na::Point3::new(0., 0., 0.)) > 0.);
+    }
+}
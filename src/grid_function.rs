@@ -0,0 +1,145 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use finite_difference;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+use vertex_index::Index;
+use AsUSize;
+
+// Positions closer than this to a grid node (relative to res) are evaluated at that node.
+const SNAP_TOLERANCE: f32 = 1e-4;
+
+// Function on grid indexes owning its values, e.g. read from a file.
+pub type BoxedGridFn<S> = Box<dyn Fn(Index) -> S + Send + Sync>;
+
+// Function defined by values on a regular grid, e.g. computed on a GPU. Between grid nodes, values
+// are interpolated trilinearly. Outside of the grid, values grow with the distance to the grid,
+// so surfaces touching the sides of the grid are closed.
+pub struct GridFunction<S: RealField, F> {
+    f: F,
+    dims: [usize; 3],
+    origin: na::Point3<S>,
+    res: S,
+    bbox: BoundingBox<S>,
+}
+
+impl<S, F> GridFunction<S, F>
+where
+    S: Debug + RealField + Float + From<f32> + AsUSize,
+    F: Fn(Index) -> S,
+{
+    pub fn new(dims: [usize; 3], origin: na::Point3<S>, res: S, f: F) -> GridFunction<S, F> {
+        assert!(dims.iter().all(|&d| d > 0), "empty grid: {:?}", dims);
+        let size = na::Vector3::new(
+            From::from((dims[0] - 1) as f32),
+            From::from((dims[1] - 1) as f32),
+            From::from((dims[2] - 1) as f32),
+        ) * res;
+        GridFunction {
+            f,
+            dims,
+            origin,
+            res,
+            bbox: BoundingBox::new(&origin, &(origin + size)),
+        }
+    }
+    // Interpolate the values of the grid nodes at p, which is clamped to the grid.
+    fn interpolate(&self, p: &na::Point3<S>) -> S {
+        let zero: S = From::from(0f32);
+        let one: S = From::from(1f32);
+        let snap: S = From::from(SNAP_TOLERANCE);
+        let mut base = [0; 3];
+        let mut weights = [zero; 3];
+        for i in 0..3 {
+            let max = From::from((self.dims[i] - 1) as f32);
+            let x = Float::min(Float::max((p[i] - self.origin[i]) / self.res, zero), max);
+            let mut floor = Float::floor(x);
+            if x - floor > one - snap {
+                floor += one;
+            }
+            let mut weight = x - floor;
+            if Float::abs(weight) < snap {
+                weight = zero;
+            }
+            base[i] = floor.as_usize();
+            weights[i] = weight;
+        }
+        let mut result = zero;
+        for corner in 0..8 {
+            let mut weight = one;
+            let mut idx = base;
+            for i in 0..3 {
+                if corner & (1 << i) == 0 {
+                    weight *= one - weights[i];
+                } else {
+                    weight *= weights[i];
+                    idx[i] += 1;
+                }
+            }
+            if weight != zero {
+                result += (self.f)(idx) * weight;
+            }
+        }
+        result
+    }
+}
+
+impl<S, F> ImplicitFunction<S> for GridFunction<S, F>
+where
+    S: Debug + RealField + Float + From<f32> + AsUSize,
+    F: Fn(Index) -> S,
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        let zero: S = From::from(0f32);
+        let outside = (0..3).fold(zero, |sum, i| {
+            let d = Float::max(
+                Float::max(self.bbox.min[i] - p[i], p[i] - self.bbox.max[i]),
+                zero,
+            );
+            sum + d * d
+        });
+        let value = self.interpolate(p);
+        if outside > zero {
+            Float::max(value, Float::sqrt(outside))
+        } else {
+            value
+        }
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        let h = self.res * From::from(0.5f32);
+        finite_difference::normal(|q| self.value(q), p, h)
+    }
+    // Values on the grid are not necessarily distances and closing the surface at the sides of
+    // the grid makes them jump there. So no subcube may be skipped and every grid index is sampled.
+    fn lipschitz_constant(&self) -> Option<S> {
+        Some(Float::infinity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn interpolation() {
+        let calls = Cell::new(0);
+        let g = GridFunction::new([3, 3, 3], na::Point3::new(1., 1., 1.), 0.5, |idx: Index| {
+            calls.set(calls.get() + 1);
+            (idx[0] + 10 * idx[1] + 100 * idx[2]) as f64
+        });
+        // Grid nodes evaluate f exactly once.
+        assert_relative_eq!(g.value(&na::Point3::new(1.5, 2., 1.)), 21.);
+        assert_eq!(calls.get(), 1);
+        // f is linear, so trilinear interpolation is exact.
+        assert_relative_eq!(g.value(&na::Point3::new(1.25, 1.75, 1.6)), 0.5 + 15. + 120.);
+        // Outside of the grid values are at least the distance to the grid.
+        assert_relative_eq!(g.value(&na::Point3::new(0., 1., 1.)), 1.);
+        assert_relative_eq!(g.value(&na::Point3::new(3., 2., 2.)), 222.);
+    }
+}
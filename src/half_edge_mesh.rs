@@ -0,0 +1,262 @@
+use mesh::{Mesh, MeshSink};
+use std::collections::HashMap;
+
+/// Half-edge of a HalfEdgeMesh. The half-edges of face f are 3 * f, 3 * f + 1 and 3 * f + 2,
+/// in the winding order of the face.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HalfEdge {
+    /// The vertex this half-edge points to.
+    pub vertex: usize,
+    /// The face this half-edge belongs to.
+    pub face: usize,
+    /// The next half-edge of the face.
+    pub next: usize,
+    /// The half-edge of the adjacent face pointing in the opposite direction, or None at
+    /// boundaries and non-manifold edges.
+    pub twin: Option<usize>,
+}
+
+/// Triangle mesh with half-edge connectivity, so adjacent faces and vertices can be found
+/// without searching, e.g. as returned by ManifoldDualContouring::tessellate_half_edge.
+#[derive(Clone, Debug)]
+pub struct HalfEdgeMesh<S> {
+    vertices: Vec<[S; 3]>,
+    // The normals of the vertices added with add_vertex_with_normal.
+    normals: Vec<[S; 3]>,
+    half_edges: Vec<HalfEdge>,
+    // One outgoing half-edge of each vertex, None for isolated vertices.
+    outgoing: Vec<Option<usize>>,
+    // Half-edges by their start and end vertices, to find twins while adding faces.
+    directed_edges: HashMap<(usize, usize), usize>,
+}
+
+impl<S: Copy> HalfEdgeMesh<S> {
+    /// Create an empty mesh.
+    pub fn new() -> HalfEdgeMesh<S> {
+        HalfEdgeMesh {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            half_edges: Vec::new(),
+            outgoing: Vec::new(),
+            directed_edges: HashMap::new(),
+        }
+    }
+    /// Add vertex v and return its index.
+    pub fn add_vertex(&mut self, v: [S; 3]) -> usize {
+        self.vertices.push(v);
+        self.outgoing.push(None);
+        self.vertices.len() - 1
+    }
+    /// Add vertex v with normal n and return its index.
+    pub fn add_vertex_with_normal(&mut self, v: [S; 3], n: [S; 3]) -> usize {
+        self.normals.push(n);
+        self.add_vertex(v)
+    }
+    /// Add the triangle of vertex indexes face, connect it to the adjacent faces and return its
+    /// index. Panics, if face refers to a vertex, that was not added yet.
+    pub fn add_face(&mut self, face: [usize; 3]) -> usize {
+        assert!(
+            face.iter().all(|&v| v < self.vertices.len()),
+            "face {:?} refers to missing vertex",
+            face
+        );
+        let f = self.num_faces();
+        let first = self.half_edges.len();
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            let h = first + i;
+            let twin = match self.directed_edges.get(&(b, a)) {
+                Some(&t) if self.half_edges[t].twin.is_none() => Some(t),
+                _ => None,
+            };
+            if let Some(t) = twin {
+                self.half_edges[t].twin = Some(h);
+            }
+            self.half_edges.push(HalfEdge {
+                vertex: b,
+                face: f,
+                next: first + (i + 1) % 3,
+                twin,
+            });
+            self.directed_edges.entry((a, b)).or_insert(h);
+            self.outgoing[a].get_or_insert(h);
+        }
+        f
+    }
+    /// The vertices of the mesh.
+    pub fn vertices(&self) -> &[[S; 3]] {
+        &self.vertices
+    }
+    /// The normal of each vertex, if all vertices were added with add_vertex_with_normal. Empty
+    /// otherwise.
+    pub fn normals(&self) -> &[[S; 3]] {
+        if self.normals.len() == self.vertices.len() {
+            &self.normals
+        } else {
+            &[]
+        }
+    }
+    /// The half-edges of the mesh.
+    pub fn half_edges(&self) -> &[HalfEdge] {
+        &self.half_edges
+    }
+    /// The number of faces.
+    pub fn num_faces(&self) -> usize {
+        self.half_edges.len() / 3
+    }
+    /// Return the vertex half-edge h starts at.
+    pub fn origin(&self, h: usize) -> usize {
+        self.half_edges[self.previous(h)].vertex
+    }
+    // Return the half-edge before h in its face.
+    fn previous(&self, h: usize) -> usize {
+        self.half_edges[self.half_edges[h].next].next
+    }
+    /// Return the vertex indexes of face f.
+    pub fn face_vertices(&self, f: usize) -> [usize; 3] {
+        let h = &self.half_edges[3 * f..3 * f + 3];
+        [h[2].vertex, h[0].vertex, h[1].vertex]
+    }
+    /// Return the faces sharing an edge with face f.
+    pub fn adjacent_faces(&self, f: usize) -> Vec<usize> {
+        self.half_edges[3 * f..3 * f + 3]
+            .iter()
+            .filter_map(|h| h.twin)
+            .map(|t| self.half_edges[t].face)
+            .collect()
+    }
+    /// Return the vertices connected to vertex v by an edge. Around vertices at boundaries or
+    /// non-manifold edges, only the neighbors reachable via twins of one outgoing half-edge are
+    /// returned.
+    pub fn vertex_neighbors(&self, v: usize) -> Vec<usize> {
+        let start = match self.outgoing[v] {
+            Some(h) => h,
+            None => return Vec::new(),
+        };
+        let mut result = Vec::new();
+        // Rotate around v via the twins of the incoming half-edges.
+        let mut h = start;
+        loop {
+            result.push(self.half_edges[h].vertex);
+            let incoming = self.previous(h);
+            match self.half_edges[incoming].twin {
+                Some(t) if t == start => return result,
+                Some(t) => h = t,
+                None => {
+                    result.push(self.origin(incoming));
+                    break;
+                }
+            }
+        }
+        // Hit a boundary, so rotate the other way from start.
+        let mut h = start;
+        while let Some(t) = self.half_edges[h].twin {
+            h = self.half_edges[t].next;
+            if h == start {
+                break;
+            }
+            result.push(self.half_edges[h].vertex);
+        }
+        result
+    }
+    /// Returns true, if every half-edge has a twin, i.e. if the mesh has no boundary.
+    pub fn is_closed(&self) -> bool {
+        self.half_edges.iter().all(|h| h.twin.is_some())
+    }
+    /// Convert to a Mesh, with the normals of the vertices, if all have one.
+    pub fn to_mesh(&self) -> Mesh<S> {
+        Mesh {
+            vertices: self.vertices.clone(),
+            faces: (0..self.num_faces())
+                .map(|f| self.face_vertices(f))
+                .collect(),
+            normals: self.normals().to_vec(),
+        }
+    }
+}
+
+impl<S: Copy> MeshSink<S> for HalfEdgeMesh<S> {
+    fn add_vertex(&mut self, position: [S; 3], normal: [S; 3]) -> usize {
+        self.add_vertex_with_normal(position, normal)
+    }
+    fn add_face(&mut self, face: [usize; 3]) {
+        HalfEdgeMesh::add_face(self, face);
+    }
+}
+
+impl<S: Copy> Default for HalfEdgeMesh<S> {
+    fn default() -> Self {
+        HalfEdgeMesh::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HalfEdgeMesh;
+
+    fn tetrahedron() -> HalfEdgeMesh<f64> {
+        let mut m = HalfEdgeMesh::new();
+        for &v in &[[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]] {
+            m.add_vertex(v);
+        }
+        for &f in &[[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]] {
+            m.add_face(f);
+        }
+        m
+    }
+
+    #[test]
+    fn connectivity() {
+        let m = tetrahedron();
+        assert!(m.is_closed());
+        assert_eq!(m.num_faces(), 4);
+        assert_eq!(m.face_vertices(2), [1, 2, 3]);
+        for (h, half_edge) in m.half_edges().iter().enumerate() {
+            let twin = m.half_edges()[half_edge.twin.unwrap()];
+            assert_eq!(twin.twin, Some(h));
+            assert_eq!(twin.vertex, m.origin(h));
+        }
+        let mut adjacent = m.adjacent_faces(0);
+        adjacent.sort();
+        assert_eq!(adjacent, vec![1, 2, 3]);
+        let mut neighbors = m.vertex_neighbors(0);
+        neighbors.sort();
+        assert_eq!(neighbors, vec![1, 2, 3]);
+        assert_eq!(
+            m.to_mesh().faces,
+            vec![[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]]
+        );
+    }
+
+    #[test]
+    fn boundary() {
+        let mut m = HalfEdgeMesh::new();
+        for &v in &[[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [-1., 0., 0.]] {
+            m.add_vertex(v);
+        }
+        m.add_face([0, 1, 2]);
+        m.add_face([0, 2, 3]);
+        assert!(!m.is_closed());
+        assert_eq!(m.adjacent_faces(0), vec![1]);
+        let mut neighbors = m.vertex_neighbors(0);
+        neighbors.sort();
+        assert_eq!(neighbors, vec![1, 2, 3]);
+        assert_eq!(m.vertex_neighbors(1), vec![2, 0]);
+        assert!(m.normals().is_empty());
+        assert!(m.to_mesh().normals.is_empty());
+    }
+
+    #[test]
+    fn normals() {
+        let mut m = HalfEdgeMesh::new();
+        for &v in &[[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]] {
+            m.add_vertex_with_normal(v, [0., 0., 1.]);
+        }
+        m.add_face([0, 1, 2]);
+        assert_eq!(m.normals(), &[[0., 0., 1.]; 3][..]);
+        assert_eq!(m.to_mesh().normals, vec![[0., 0., 1.]; 3]);
+        // Without a normal for each vertex, there are none.
+        m.add_vertex([1., 1., 0.]);
+        assert!(m.normals().is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Implicit function inflated by offset, i.e. with value inner.value(p) - offset, as returned by
+/// ImplicitFunction::offset. A negative offset deflates the function. For signed distance
+/// functions this moves the surface by offset along its normals.
+pub struct Inflated<S: Debug + RealField, O> {
+    inner: O,
+    offset: S,
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField + Float, O: ImplicitFunction<S>> Inflated<S, O> {
+    /// Inflate inner by offset. The bounding box is dilated (or contracted) by offset.
+    pub fn new(inner: O, offset: S) -> Inflated<S, O> {
+        let mut bbox = inner.bbox().clone();
+        bbox.dilate(offset);
+        Inflated {
+            inner,
+            offset,
+            bbox,
+        }
+    }
+    /// The inflated function.
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+    /// The distance, by which inner is inflated.
+    pub fn distance(&self) -> S {
+        self.offset
+    }
+}
+
+impl<S: Debug + RealField, O: ImplicitFunction<S>> ImplicitFunction<S> for Inflated<S, O> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.inner.value(p) - self.offset
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        self.inner.normal(p)
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        self.inner.lipschitz_constant()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::Sphere;
+
+    #[test]
+    fn inflated() {
+        let inflated = Sphere::unit().offset(0.5);
+        assert_relative_eq!(inflated.value(&na::Point3::new(0., 2., 0.)), 0.5);
+        assert_relative_eq!(inflated.value(&na::Point3::new(1.5, 0., 0.)), 0.);
+        assert_eq!(inflated.bbox().max, na::Point3::new(1.5, 1.5, 1.5));
+        assert_eq!(inflated.distance(), 0.5);
+        let deflated = Inflated::new(Sphere::unit(), -0.25);
+        assert!(!deflated.is_inside(&na::Point3::new(0., 0., 0.8)));
+        assert_eq!(deflated.bbox().min, na::Point3::new(-0.75, -0.75, -0.75));
+        assert_relative_eq!(
+            deflated.normal(&na::Point3::new(0., 3., 0.)),
+            na::Vector3::y()
+        );
+        // Trait objects can name the type.
+        let boxed: Box<Inflated<f64, Sphere>> = Box::new(deflated);
+        assert_relative_eq!(boxed.inner().value(&na::Point3::origin()), -1.);
+    }
+}
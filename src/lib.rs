@@ -43,6 +43,8 @@ extern crate alga;
 extern crate bbox;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "memoize")]
+extern crate lru;
 extern crate nalgebra as na;
 extern crate num_traits;
 extern crate rand;
@@ -51,18 +53,70 @@ extern crate time;
 
 use alga::general::RealField;
 pub use bbox::BoundingBox;
+use num_traits::Float;
 use std::fmt::Debug;
 
+mod adaptive_grid;
+mod animated;
+mod bbox_clamped;
+mod bezier_tube;
+mod bitmap_sdf;
 mod bitset;
+mod bvh;
 mod cell_configs;
+mod compose;
+mod contour;
+mod convex_decomposition;
+mod decimate;
+mod finite_difference;
+mod fractal;
+mod grid_function;
+mod half_edge_mesh;
+mod inflated;
 mod manifold_dual_contouring;
+#[cfg(feature = "memoize")]
+mod memoize;
 mod mesh;
+#[cfg(feature = "neural-sdf")]
+mod neural_sdf;
+mod nrrd;
+mod perlin;
 mod plane;
+mod potential_field;
 mod qef;
+mod sampling;
+mod sparse_octree;
+mod streaming_mesh_writer;
+#[cfg(test)]
+mod test_util;
+mod transformed;
 mod vertex_index;
 
-pub use self::manifold_dual_contouring::ManifoldDualContouring;
-pub use self::mesh::Mesh;
+pub use self::animated::AnimatedObject;
+pub use self::bbox_clamped::BboxClamped;
+pub use self::bezier_tube::BezierTube;
+pub use self::bitmap_sdf::BitmapSdf;
+pub use self::compose::{compose, intersection, union};
+pub use self::contour::{Axis, Contour};
+pub use self::fractal::{sierpinski_sponge, FractalSdf, FractalTransform};
+pub use self::half_edge_mesh::{HalfEdge, HalfEdgeMesh};
+pub use self::inflated::Inflated;
+pub use self::manifold_dual_contouring::{
+    CellDebugInfo, ClampStrategy, GridCacheError, GridStats, IncrementalTessellator,
+    ManifoldDualContouring, TessellationConfig, TessellationStats, VerboseEvent, VertexPlacement,
+    ZeroFindingMethod,
+};
+#[cfg(feature = "memoize")]
+pub use self::memoize::MemoizedObject;
+pub use self::mesh::{Mesh, MeshDiff, MeshSink, TopologyDiff, TopologyError};
+#[cfg(feature = "neural-sdf")]
+pub use self::neural_sdf::{ActivationFn, NeuralSdf};
+pub use self::nrrd::NrrdSdf;
+pub use self::perlin::{NoisyObject, PerlinNoise, PerlinNoiseSurface};
+pub use self::plane::Plane;
+pub use self::potential_field::PotentialField;
+pub use self::streaming_mesh_writer::StreamingMeshWriter;
+pub use self::transformed::Transformed;
 
 /// Trait to be implemented by functions that should be tessellated.
 pub trait ImplicitFunction<S: Debug + RealField> {
@@ -77,11 +131,147 @@ pub trait ImplicitFunction<S: Debug + RealField> {
     fn value(&self, p: &na::Point3<S>) -> S;
     /// Compute the normal of the function at p.
     fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S>;
+    /// Evaluate the function on all points and return the values in the same order. The default
+    /// calls value for each point. Functions, that can evaluate many points at once more
+    /// efficiently, e.g. vectorized or in parallel, may override this. The tessellator uses it to
+    /// evaluate the corners of subcubes.
+    fn evaluate_batch(&self, points: &[na::Point3<S>]) -> Vec<S> {
+        points.iter().map(|p| self.value(p)).collect()
+    }
+    /// Return whether p is inside the object. The default is value(p) < 0. Functions, that can
+    /// classify points exactly, may override this, e.g. to decide points with a value of zero.
+    /// Tessellation classifies samples with a value of zero with is_inside, samples with other
+    /// values by their sign.
+    fn is_inside(&self, p: &na::Point3<S>) -> bool {
+        self.value(p) < S::zero()
+    }
+    /// Return the Lipschitz constant of value, i.e. the maximum of the magnitude of its gradient,
+    /// or None, if it is unknown. The default of 1 is exact for signed distance functions.
+    /// If None, the magnitude of the gradient is approximated to estimate distances from values.
+    fn lipschitz_constant(&self) -> Option<S> {
+        Some(S::one())
+    }
+    /// Approximate the magnitude of the gradient of value at p with central differences of step
+    /// size res. This allows to normalize functions, that are no exact distance functions.
+    fn approximate_gradient_magnitude(&self, p: &na::Point3<S>, res: S) -> S
+    where
+        S: From<f32>,
+    {
+        finite_difference::gradient(|q| self.value(q), p, res).norm()
+    }
+    /// Estimate the Lipschitz constant of value near p, as the maximum of the central
+    /// differences |f(p + h e_i) - f(p - h e_i)| / 2h along the axes e_i for h = radius / 10.
+    /// Used to estimate distances during sampling, if lipschitz_constant is unknown.
+    fn locally_lipschitz_constant(&self, p: &na::Point3<S>, radius: S) -> S
+    where
+        S: Float + From<f32>,
+    {
+        let h = radius / From::from(10f32);
+        (0..3).fold(S::zero(), |max, i| {
+            let mut d = na::Vector3::zeros();
+            d[i] = h;
+            let slope = Float::abs(self.value(&(p + d)) - self.value(&(p - d))) / (h + h);
+            Float::max(max, slope)
+        })
+    }
+    /// Sample n points on the surface with a random walk (Markov chain Monte Carlo), e.g. for
+    /// Monte Carlo rendering or statistics. The walk starts where a random ray through the
+    /// bounding box first hits the surface and approximates the uniform distribution on that
+    /// connected component of the surface. Consecutive points are correlated. The same seed yields
+    /// the same points. Returns an empty Vec, if no surface is found.
+    fn importance_sample(&self, n: usize, seed: u64) -> Vec<na::Point3<S>>
+    where
+        S: Float + From<f32>,
+    {
+        sampling::importance_sample(self, n, seed)
+    }
+    /// Estimate the surface area with Monte Carlo sampling, without tessellating: n uniform
+    /// samples of the bounding box estimate the co-area formula, i.e. the integral of
+    /// delta(value) |gradient|, from the samples in a thin band around the surface. The error
+    /// decreases with sqrt(n). The same n always yields the same estimate.
+    #[cfg(feature = "monte-carlo")]
+    fn approximate_surface_area(&self, n: usize) -> S
+    where
+        S: Float + From<f32>,
+    {
+        sampling::approximate_surface_area(self, n)
+    }
+    /// Inflate the function by offset, i.e. subtract offset from its values. A negative offset
+    /// deflates the function.
+    fn offset(self, offset: S) -> Inflated<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        Inflated::new(self, offset)
+    }
+    /// Only evaluate the function inside its bounding box, e.g. for functions, that diverge or
+    /// return garbage outside their valid domain. Outside, the value is the distance to the
+    /// bounding box, see BboxClamped.
+    fn clamped_to_bbox(self) -> BboxClamped<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        BboxClamped::new(self)
+    }
+    /// Transform the function by matrix from object to world space. Values are evaluated at the
+    /// inverse transformed point, normals are transformed with the inverse transpose.
+    /// Panics, if matrix is not invertible.
+    fn transform_by(self, matrix: na::Matrix4<S>) -> Transformed<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        Transformed::new(self, matrix)
+    }
+    /// Translate the function by v.
+    fn translate(self, v: na::Vector3<S>) -> Transformed<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        self.transform_by(na::Matrix4::new_translation(&v))
+    }
+    /// Rotate the function around the x axis by angle (in radians).
+    fn rotate_x(self, angle: S) -> Transformed<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        self.transform_by(na::Matrix4::from_scaled_axis(na::Vector3::x() * angle))
+    }
+    /// Rotate the function around the y axis by angle (in radians).
+    fn rotate_y(self, angle: S) -> Transformed<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        self.transform_by(na::Matrix4::from_scaled_axis(na::Vector3::y() * angle))
+    }
+    /// Rotate the function around the z axis by angle (in radians).
+    fn rotate_z(self, angle: S) -> Transformed<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        self.transform_by(na::Matrix4::from_scaled_axis(na::Vector3::z() * angle))
+    }
+    /// Scale the function by factor in all dimensions. The Lipschitz constant is divided by
+    /// factor, so values stay valid distance bounds.
+    fn scale_uniform(self, factor: S) -> Transformed<S, Self>
+    where
+        Self: Sized,
+        S: Float,
+    {
+        self.transform_by(na::Matrix4::new_scaling(factor))
+    }
 }
 
 /// Trait which allows to convert Self to usize, since To<usize> is not implemented by f32 and f64.
 pub trait AsUSize {
     /// Convert Self to usize.
+    #[allow(clippy::wrong_self_convention)]
     fn as_usize(self) -> usize;
 }
 
@@ -1,23 +1,65 @@
 use super::{AsUSize, ImplicitFunction};
+use adaptive_grid::AdaptiveGrid;
 use alga::general::RealField;
 use bbox::BoundingBox;
 use bitset::BitSet;
 use cell_configs::CELL_CONFIGS;
-use mesh::Mesh;
+use contour::{Axis, Contour};
+use finite_difference;
+use grid_function::GridFunction;
+use half_edge_mesh::HalfEdgeMesh;
+use mesh::{Mesh, MeshSink};
 use na;
-use num_traits::Float;
+use num_traits::{Float, NumCast};
 use plane::Plane;
 use qef;
-use rand;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
+use sparse_octree::SparseOctree;
 use std::cell::{Cell, RefCell};
 use std::cmp;
-use std::collections::{BTreeSet, HashMap};
-use std::{error, fmt};
-use vertex_index::{neg_offset, offset, Index, VarIndex, VertexIndex, EDGES_ON_FACE};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::iter;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{error, fmt, io, mem};
+use transformed::transform_bbox;
+use vertex_index::{neg_offset, offset, wrap, Index, VarIndex, VertexIndex, EDGES_ON_FACE};
 
-// How accurately find zero crossings.
-const PRECISION: f32 = 0.05;
+// Magic number and version of the files written by save_value_grid.
+const VALUE_GRID_MAGIC: [u8; 4] = *b"TSVG";
+const VALUE_GRID_VERSION: u8 = 1;
+
+// Number of pairs of points sampled by measure_sdf_lipschitz.
+const LIPSCHITZ_SAMPLES: usize = 10000;
+// measure_sdf_lipschitz warns about Lipschitz constants above 1 plus this tolerance.
+const LIPSCHITZ_TOLERANCE: f32 = 0.01;
+
+// Number of cells along the longest side of the coarse grid, which
+// estimate_memory_for_resolution samples to find the cells near the surface.
+const MEMORY_ESTIMATE_CELLS: usize = 32;
+
+// Minimal angle (in radians) between the tangent planes of a sharp feature in
+// tessellate_with_feature_lines.
+const FEATURE_LINE_ANGLE: f32 = ::std::f32::consts::PI / 6.;
+
+// GridStats::is_suspicious is true for surface fractions outside of this range.
+const MIN_SURFACE_FRACTION: f32 = 0.001;
+const MAX_SURFACE_FRACTION: f32 = 0.5;
+
+// Seed of the padding of retried tessellations, so a retry lands on the same grid every time,
+// e.g. when an incremental tessellation is continued.
+const RETRY_SEED: u64 = 0;
+
+// Default of TessellationConfig::precision.
+const DEFAULT_PRECISION: f32 = 0.05;
+// Default of TessellationConfig::max_find_zero_depth.
+const DEFAULT_MAX_FIND_ZERO_DEPTH: usize = 64;
 
 //  Edge indexes
 //
@@ -72,6 +114,13 @@ impl Edge {
     pub fn base(self) -> Edge {
         Edge::from_usize(self as usize % 3)
     }
+    // Returns the other three edges of the quad (see QUADS) containing this edge, in quad order
+    // starting after this edge.
+    pub fn quad_neighbours(self) -> [Edge; 3] {
+        let quad = &QUADS[self.base() as usize];
+        let i = quad.iter().position(|&e| e == self).unwrap();
+        [quad[(i + 1) % 4], quad[(i + 2) % 4], quad[(i + 3) % 4]]
+    }
 }
 
 // Cell offsets of edges
@@ -110,6 +159,333 @@ lazy_static! {
     ];
 }
 
+/// Callback computing the position of a mesh vertex from the tangent planes of all edges of the
+/// sampling grid contributing to it, and the index of its cell. For vertices of the octree
+/// above the leafs, the index is given in units of the cell size of that octree layer.
+pub type VertexPlacement<S> = Arc<dyn Fn(&[Plane<S>], [usize; 3]) -> na::Point3<S> + Send + Sync>;
+
+/// Method used to find the zero crossing of the function along the edges of the sampling grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZeroFindingMethod {
+    /// Halve the bracketing interval in each step.
+    Bisection,
+    /// Linearly interpolate the zero crossing in each step. Converges slowly for functions,
+    /// that are convex or concave along the edge, since one end of the interval stays fixed.
+    RegulaFalsi,
+    /// Regula falsi, that halves the value of an end of the interval, if it is retained twice
+    /// in a row. This converges super-linearly, while still bracketing the zero.
+    Illinois,
+}
+
+/// Strategy to place a mesh vertex, if the solution of its QEF lies outside of its cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClampStrategy {
+    /// Binary search the cell for the point with the smallest QEF residual.
+    BinarySearch,
+    /// Use the mean of the positions of the tangent planes. This is fine for smooth objects, but
+    /// pulls vertices away from sharp features.
+    MeanOfPlanes,
+    /// Project the QEF solution onto the nearest side of the cell.
+    ClampToBounds,
+    /// Use the position of the tangent plane with the smallest QEF residual.
+    NearestEdgeCrossing,
+}
+
+/// Configuration of the accuracy of tessellation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TessellationConfig<S> {
+    precision: S,
+    max_find_zero_depth: usize,
+    clamp_strategy: ClampStrategy,
+    near_surface_refinement_factor: usize,
+}
+
+impl<S: From<f32>> Default for TessellationConfig<S> {
+    fn default() -> Self {
+        TessellationConfig {
+            precision: From::from(DEFAULT_PRECISION),
+            max_find_zero_depth: DEFAULT_MAX_FIND_ZERO_DEPTH,
+            clamp_strategy: ClampStrategy::BinarySearch,
+            near_surface_refinement_factor: 1,
+        }
+    }
+}
+
+impl<S: From<f32>> TessellationConfig<S> {
+    /// Create the default configuration.
+    pub fn new() -> Self {
+        TessellationConfig::default()
+    }
+    /// Set how accurately zero crossings are found, relative to the resolution. The default is
+    /// 0.05.
+    pub fn precision(mut self, precision: S) -> Self {
+        self.precision = precision;
+        self
+    }
+    /// Set the maximum number of steps to find a zero crossing, which guards against slow
+    /// convergence on near-degenerate crossings. The default is 64.
+    pub fn max_find_zero_depth(mut self, depth: usize) -> Self {
+        self.max_find_zero_depth = depth;
+        self
+    }
+    /// Set the strategy used, if the solution of a QEF lies outside of its cell. The default is
+    /// ClampStrategy::BinarySearch.
+    pub fn clamp_strategy(mut self, strategy: ClampStrategy) -> Self {
+        self.clamp_strategy = strategy;
+        self
+    }
+    /// Subdivide the cells near the surface by factor, i.e. sample them at res / factor. Cells of
+    /// size res, whose center is farther than half their diagonal from the surface, are only
+    /// sampled at their center and corners and interpolated. Since the error threshold for
+    /// simplifying the mesh stays relative to res, mesh vertices only keep the fine resolution,
+    /// where the surface needs it. The default of 1 disables refinement. Interpolating cells of
+    /// size 2 saves no evaluations, so only factors of at least 4 reduce sampling. Panics, if
+    /// factor is no power of two.
+    pub fn near_surface_refinement_factor(mut self, factor: usize) -> Self {
+        assert!(
+            factor.is_power_of_two(),
+            "refinement factor must be a power of two"
+        );
+        self.near_surface_refinement_factor = factor;
+        self
+    }
+}
+
+/// Statistics of a tessellation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TessellationStats {
+    /// Number of solved QEFs.
+    pub solved_qefs: usize,
+    /// Number of QEF solutions outside of their cell, that were placed with the ClampStrategy.
+    pub clamps: usize,
+}
+
+/// Statistics of the sampling grid, as returned by ManifoldDualContouring::report_grid_stats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridStats<S> {
+    /// Number of cells of the sampling grid.
+    pub total_cells: usize,
+    /// Number of cells with a sign change, i.e. cells the surface passes through.
+    pub surface_cells: usize,
+    /// surface_cells / total_cells.
+    pub surface_fraction: S,
+    /// Number of triangles before simplification: two per edge with a zero crossing.
+    pub estimated_triangle_count: usize,
+}
+
+impl<S: RealField + From<f32>> GridStats<S> {
+    /// Return true, if the surface passes through more than half of the cells, which suggests a
+    /// resolution too coarse for the function, or through less than 0.1 % of them, which suggests
+    /// a resolution finer than needed.
+    pub fn is_suspicious(&self) -> bool {
+        self.surface_fraction > From::from(MAX_SURFACE_FRACTION)
+            || self.surface_fraction < From::from(MIN_SURFACE_FRACTION)
+    }
+}
+
+/// Progress of a tessellation, which is passed to the sink set with
+/// ManifoldDualContouring::set_verbose_sink. Without a sink, events are dropped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VerboseEvent<S: 'static + RealField> {
+    /// The tessellation started.
+    Start {
+        /// The resolution.
+        res: S,
+        /// The bounding box of the function.
+        bbox: BoundingBox<S>,
+    },
+    /// A value of exactly zero was sampled, so the sampling grid is moved and sampled again.
+    Retry {
+        /// Description of the error.
+        error: String,
+        /// The offset, by which the sampling grid is moved.
+        padding: na::Vector3<S>,
+    },
+    /// The value grid was sampled.
+    ValueGridStats {
+        /// The number of cells of the sampling grid.
+        cell_count: usize,
+        /// The number of sampled values, that are kept.
+        value_count: usize,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// Values without a sign change next to them were removed from the value grid.
+    CompactedValueGridStats {
+        /// The number of cells of the sampling grid.
+        cell_count: usize,
+        /// The number of values, that are kept.
+        value_count: usize,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// Flat subcubes of curvature adaptive tessellation were interpolated into the value grid.
+    FlatSubcubeStats {
+        /// The number of flat subcubes.
+        subcube_count: usize,
+    },
+    /// The zero crossings along the edges of the sampling grid were found.
+    EdgeGridStats {
+        /// The number of edges with a zero crossing.
+        edge_count: usize,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// The leaf vertices of the octtree were generated.
+    LeafVertexStats {
+        /// The number of leaf vertices.
+        vertex_count: usize,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// The vertex octtree was subsampled.
+    OcttreeStats {
+        /// The number of layers of the octtree.
+        layer_count: usize,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// The QEFs of the octtree were solved.
+    QefStats {
+        /// The statistics of the QEFs.
+        stats: TessellationStats,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// The quads of the mesh were generated.
+    QuadStats {
+        /// The number of quads.
+        quad_count: usize,
+        /// The time spent.
+        elapsed: Duration,
+    },
+    /// The mesh was computed.
+    MeshStats {
+        /// The number of faces of the mesh.
+        face_count: usize,
+    },
+    /// The statistics of the sampling grid are suspicious, see GridStats::is_suspicious.
+    GridStatsWarning {
+        /// The statistics of the sampling grid.
+        stats: GridStats<S>,
+    },
+    /// The Lipschitz constant measured by measure_sdf_lipschitz exceeds the expected one.
+    LipschitzWarning {
+        /// The measured Lipschitz constant.
+        measured: S,
+        /// The expected Lipschitz constant.
+        expected: S,
+    },
+}
+
+impl<S: 'static + RealField> fmt::Display for VerboseEvent<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent =
+            |value_count: usize, cell_count: usize| (100 * value_count) as f64 / cell_count as f64;
+        match *self {
+            VerboseEvent::Start { res, ref bbox } => {
+                write!(f, "ManifoldDualContouring: res: {:} {:?}", res, bbox)
+            }
+            VerboseEvent::Retry { ref error, padding } => {
+                write!(f, "Error: {}. moving by {:?} and retrying.", error, padding)
+            }
+            VerboseEvent::ValueGridStats {
+                cell_count,
+                value_count,
+                elapsed,
+            } => write!(
+                f,
+                "generated value_grid with {:} % of {:} cells in {:?}.",
+                percent(value_count, cell_count),
+                cell_count,
+                elapsed
+            ),
+            VerboseEvent::CompactedValueGridStats {
+                cell_count,
+                value_count,
+                elapsed,
+            } => write!(
+                f,
+                "compacted value_grid, now {:} % of {:} cells in {:?}.",
+                percent(value_count, cell_count),
+                cell_count,
+                elapsed
+            ),
+            VerboseEvent::FlatSubcubeStats { subcube_count } => {
+                write!(f, "interpolated {} flat subcubes.", subcube_count)
+            }
+            VerboseEvent::EdgeGridStats {
+                edge_count,
+                elapsed,
+            } => write!(
+                f,
+                "generated edge_grid with {} edges: {:?}",
+                edge_count, elapsed
+            ),
+            VerboseEvent::LeafVertexStats {
+                vertex_count,
+                elapsed,
+            } => write!(
+                f,
+                "generated {:?} leaf vertices: {:?}",
+                vertex_count, elapsed
+            ),
+            VerboseEvent::OcttreeStats {
+                layer_count,
+                elapsed,
+            } => write!(
+                f,
+                "subsampled octtree to {} layers: {:?}",
+                layer_count, elapsed
+            ),
+            VerboseEvent::QefStats { stats, elapsed } => write!(
+                f,
+                "solved {} qefs, clamped {}: {:?}",
+                stats.solved_qefs, stats.clamps, elapsed
+            ),
+            VerboseEvent::QuadStats {
+                quad_count,
+                elapsed,
+            } => write!(f, "generated {} quads: {:?}", quad_count, elapsed),
+            VerboseEvent::MeshStats { face_count } => {
+                write!(f, "computed mesh with {:?} faces.", face_count)
+            }
+            VerboseEvent::GridStatsWarning { ref stats } => write!(
+                f,
+                "Warning: the surface passes through {} % of {} cells. The resolution may not \
+                 fit the function.",
+                percent(stats.surface_cells, stats.total_cells),
+                stats.total_cells
+            ),
+            VerboseEvent::LipschitzWarning { measured, expected } => write!(
+                f,
+                "Warning: measured Lipschitz constant {} exceeds {}. The function may not be a \
+                 proper distance function.",
+                measured, expected
+            ),
+        }
+    }
+}
+
+/// Diagnostic information about a solved QEF, i.e. a cell of the vertex octtree, as returned by
+/// ManifoldDualContouring::export_cell_debug_info.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellDebugInfo<S: 'static + RealField> {
+    /// The layer of the octtree. Cells of layer l span 2^l sampling cells per dimension.
+    pub layer: usize,
+    /// The index of the cell within its layer.
+    pub index: [usize; 3],
+    /// The signs of the corners of the cell: Bit z << 2 | y << 1 | x is set, if corner [x, y, z]
+    /// is inside.
+    pub bitset: u32,
+    /// The error of the QEF solution.
+    pub qef_residual: S,
+    /// Whether the QEF solution was outside of the cell and placed with the ClampStrategy.
+    pub was_clamped: bool,
+    /// The solution of the QEF.
+    pub vertex_pos: na::Point3<S>,
+}
+
 #[derive(Debug)]
 pub enum DualContouringError {
     HitZero(String),
@@ -131,6 +507,46 @@ impl fmt::Display for DualContouringError {
     }
 }
 
+/// Error loading a value grid with ManifoldDualContouring::load_value_grid.
+#[derive(Debug)]
+pub enum GridCacheError {
+    /// Reading the value grid failed.
+    Io(io::Error),
+    /// The magic number or version of the value grid is not supported.
+    InvalidHeader(String),
+    /// Resolution, origin or number of cells of the value grid do not match the
+    /// ManifoldDualContouring.
+    DimensionMismatch(String),
+}
+
+impl From<io::Error> for GridCacheError {
+    fn from(e: io::Error) -> GridCacheError {
+        GridCacheError::Io(e)
+    }
+}
+
+impl error::Error for GridCacheError {
+    fn description(&self) -> &str {
+        match *self {
+            GridCacheError::Io(_) => "Could not read value grid.",
+            GridCacheError::InvalidHeader(_) => "Unsupported value grid format.",
+            GridCacheError::DimensionMismatch(_) => "Value grid does not match.",
+        }
+    }
+}
+
+impl fmt::Display for GridCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GridCacheError::Io(ref e) => write!(f, "Could not read value grid: {}", e),
+            GridCacheError::InvalidHeader(ref s) => write!(f, "Unsupported value grid: {}", s),
+            GridCacheError::DimensionMismatch(ref s) => {
+                write!(f, "Value grid does not match: {}", s)
+            }
+        }
+    }
+}
+
 // A vertex of the mesh. This can be either a primary vertex of the sampled mesh or a vertex
 // generated by joining multiple vertices in the octree.
 #[derive(Debug)]
@@ -204,19 +620,407 @@ impl EdgeIndex {
 /// Struct containing all the intermediary state for the different stages of tessellation.
 #[derive(Clone)]
 pub struct ManifoldDualContouring<'a, S: RealField> {
-    function: &'a dyn ImplicitFunction<S>,
+    sampler: Sampler<'a, S>,
     origin: na::Point3<S>,
     dim: [usize; 3],
-    mesh: RefCell<Mesh<S>>,
     res: S,
     error: S,
-    value_grid: HashMap<Index, S>,
+    value_grid: SparseOctree<S>,
+    // Whether value_grid was loaded with load_value_grid and must not be sampled.
+    value_grid_loaded: bool,
+    // Only used for curvature adaptive tessellation: Flat subcubes, that are interpolated into
+    // value_grid instead of being sampled.
+    adaptive_grid: Option<AdaptiveGrid<S>>,
+    // Replaces the QEF solution as position of mesh vertices, if set.
+    vertex_placement: Option<VertexPlacement<S>>,
+    // Only used for periodic tessellation: The number of cells per period in each dimension.
+    // Indexes are wrapped into [period, 2 * period) (see vertex_index::wrap).
+    period: Option<Index>,
+    // Only used for adaptive tessellation: The deepest layer of vertex_octtree, that is used for
+    // the mesh. Mesh vertices then cluster at most 2^max_octtree_layer cells in each dimension.
+    max_octtree_layer: Option<usize>,
     edge_grid: RefCell<HashMap<EdgeIndex, Plane<S>>>,
     // The Vertex Octtree. vertex_octtree[0] stores the leaf vertices. vertex_octtree[1] the next
     // layer and so on. vertex_octtree.len() is the depth of the octtree.
     vertex_octtree: Vec<Vec<Vertex<S>>>,
     // Map from VertexIndex to vertex_octtree[0]
     vertex_index_map: HashMap<VertexIndex, usize>,
+    // Layer and index in vertex_octtree of the mesh vertex of each leaf vertex.
+    selected_vertices: Vec<(usize, usize)>,
+    stats: Cell<TessellationStats>,
+    // Whether solve_qefs records cell_debug_info.
+    debug_cells: bool,
+    cell_debug_info: RefCell<Vec<CellDebugInfo<S>>>,
+    // Receives the progress of tessellation. If None, it is dropped.
+    verbose_sink: Option<Arc<dyn Fn(VerboseEvent<S>) + Send + Sync>>,
+}
+
+// Subcubes of at least this size (in cells per dimension) are sampled in parallel.
+const MIN_PARALLEL_SUBCUBE_SIZE: usize = 16;
+
+// Everything needed to evaluate the function, which is shared between threads while sampling.
+#[derive(Clone)]
+struct Sampler<'a, S: RealField> {
+    function: SampledFunction<'a, S>,
+    res: S,
+    zero_finding_method: ZeroFindingMethod,
+    config: TessellationConfig<S>,
+    // Only used for transformed tessellation: The transform from object to world space and its
+    // inverse, which is applied to all points before evaluating function.
+    object_transform: Option<(na::Matrix4<S>, na::Matrix4<S>)>,
+    // Only used for curvature adaptive tessellation: The size of the largest flat subcubes.
+    max_flat_subcube_size: Option<usize>,
+    // Only used for narrow band tessellation: Values are only stored, if their distance estimate
+    // is within the band.
+    narrow_band: Option<S>,
+}
+
+// The function sampled by Sampler, which is either borrowed from the caller or owned, e.g. if it
+// wraps a closure on grid indexes.
+#[derive(Clone)]
+enum SampledFunction<'a, S: RealField> {
+    Borrowed(&'a (dyn ImplicitFunction<S> + Sync)),
+    Owned(Arc<dyn ImplicitFunction<S> + Send + Sync + 'a>),
+}
+
+impl<'a, S: RealField> Deref for SampledFunction<'a, S> {
+    type Target = dyn ImplicitFunction<S> + Sync + 'a;
+    fn deref(&self) -> &Self::Target {
+        match *self {
+            SampledFunction::Borrowed(f) => f,
+            SampledFunction::Owned(ref f) => f.as_ref(),
+        }
+    }
+}
+
+// The result of sampling the value grid.
+struct Samples<S> {
+    values: Vec<(Index, S)>,
+    // Flat subcubes for curvature adaptive tessellation as depth, index and corner values (see
+    // AdaptiveGrid::insert).
+    flat_subcubes: Vec<(usize, Index, [S; 8])>,
+}
+
+impl<S> Samples<S> {
+    fn new() -> Samples<S> {
+        Samples {
+            values: Vec::new(),
+            flat_subcubes: Vec::new(),
+        }
+    }
+    fn append(&mut self, mut other: Samples<S>) {
+        self.values.append(&mut other.values);
+        self.flat_subcubes.append(&mut other.flat_subcubes);
+    }
+}
+
+impl<'a, S: From<f32> + RealField + Float> Sampler<'a, S> {
+    // Return the bounding box of function in world space.
+    fn bbox(&self) -> BoundingBox<S> {
+        match self.object_transform {
+            Some((ref transform, _)) => transform_bbox(self.function.bbox(), transform),
+            None => self.function.bbox().clone(),
+        }
+    }
+    // Evaluate function at p in world space.
+    fn value(&self, p: &na::Point3<S>) -> S {
+        match self.object_transform {
+            Some((_, ref inverse)) => self.function.value(&inverse.transform_point(p)),
+            None => self.function.value(p),
+        }
+    }
+    // Evaluate function at all points in world space.
+    fn value_batch(&self, points: &[na::Point3<S>]) -> Vec<S> {
+        match self.object_transform {
+            Some((_, ref inverse)) => {
+                let points: Vec<_> = points.iter().map(|p| inverse.transform_point(p)).collect();
+                self.function.evaluate_batch(&points)
+            }
+            None => self.function.evaluate_batch(points),
+        }
+    }
+    // Return value, unless it is zero. Then is_inside decides the sign of the returned value,
+    // which is the smallest value of that sign, so the sample classifies p.
+    fn classify_zero(&self, p: &na::Point3<S>, value: S) -> S {
+        if value != From::from(0f32) {
+            return value;
+        }
+        let inside = match self.object_transform {
+            Some((_, ref inverse)) => self.function.is_inside(&inverse.transform_point(p)),
+            None => self.function.is_inside(p),
+        };
+        if inside {
+            -S::min_positive_value()
+        } else {
+            S::min_positive_value()
+        }
+    }
+    // Return the normal of function at p in world space.
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        match self.object_transform {
+            Some((_, ref inverse)) => {
+                // Normals transform with the inverse transpose.
+                let n = self.function.normal(&inverse.transform_point(p));
+                (inverse.fixed_slice::<na::U3, na::U3>(0, 0).transpose() * n).normalize()
+            }
+            None => self.function.normal(p),
+        }
+    }
+    // Estimate the distance of p to the surface from the value at p. This is a lower bound, if
+    // the Lipschitz constant of function is known. Otherwise the gradient is estimated at p and
+    // within radius of p.
+    fn distance_estimate(&self, p: &na::Point3<S>, value: S, radius: S) -> S {
+        let gradient_magnitude = match (self.function.lipschitz_constant(), self.object_transform) {
+            (Some(lipschitz_constant), _) => lipschitz_constant,
+            (None, None) => Float::max(
+                self.function.approximate_gradient_magnitude(p, self.res),
+                self.function.locally_lipschitz_constant(p, radius),
+            ),
+            (None, Some(_)) => finite_difference::gradient(|q| self.value(q), p, self.res).norm(),
+        };
+        if gradient_magnitude > From::from(0f32) {
+            Float::abs(value) / gradient_magnitude
+        } else {
+            // Flat regions might still contain the surface.
+            From::from(0f32)
+        }
+    }
+    // Whether the sample at p is stored in the value grid, i.e. there is no narrow band or p is
+    // within it.
+    fn in_narrow_band(&self, p: &na::Point3<S>, value: S) -> bool {
+        match self.narrow_band {
+            Some(band_width) => self.distance_estimate(p, value, band_width) <= band_width,
+            None => true,
+        }
+    }
+    // Sample the subcube of given size at idx and pos, whose value at pos is val, into samples.
+    fn sample_value_grid(
+        &self,
+        idx: Index,
+        pos: na::Point3<S>,
+        size: usize,
+        val: S,
+        samples: &mut Samples<S>,
+    ) -> Result<(), DualContouringError> {
+        debug_assert!(size > 1);
+        let mut midx = idx;
+        let size = size / 2;
+        let size_s: S = From::from(size as f32);
+        let vpos = [
+            pos,
+            pos + na::Vector3::new(self.res, self.res, self.res) * size_s,
+        ];
+        let sub_cube_diagonal = size_s * self.res * Float::sqrt(From::from(3f32));
+
+        // Evaluate the corners of the subcubes at once. The first corner is pos itself.
+        let corners: Vec<na::Point3<S>> = (1..8)
+            .map(|i| na::Point3::new(vpos[i & 1].x, vpos[i >> 1 & 1].y, vpos[i >> 2].z))
+            .collect();
+        let mut values = vec![val];
+        values.append(&mut self.value_batch(&corners));
+
+        // Subcubes, that need to be sampled recursively.
+        let mut subcubes = Vec::new();
+        for z in 0..2 {
+            for y in 0..2 {
+                for x in 0..2 {
+                    let mpos = na::Point3::new(vpos[x].x, vpos[y].y, vpos[z].z);
+                    let value = self.classify_zero(&mpos, values[z << 2 | y << 1 | x]);
+
+                    if size > 1
+                        && self.distance_estimate(&mpos, value, sub_cube_diagonal)
+                            <= sub_cube_diagonal
+                    {
+                        if let Some(corners) = self
+                            .flat_subcube(mpos, size, value)
+                            .or_else(|| self.distant_subcube(mpos, size, value))
+                        {
+                            // Values will be interpolated by expand_adaptive_grid.
+                            let depth = size.trailing_zeros() as usize;
+                            let cell_index = [midx[0] / size, midx[1] / size, midx[2] / size];
+                            samples.flat_subcubes.push((depth, cell_index, corners));
+                        } else {
+                            subcubes.push((midx, mpos, value));
+                        }
+                    } else if self.in_narrow_band(&mpos, value) {
+                        samples.values.push((midx, value));
+                    }
+                    midx[0] += size;
+                }
+                midx[0] -= 2 * size;
+                midx[1] += size;
+            }
+            midx[1] -= 2 * size;
+            midx[2] += size;
+        }
+        if size >= MIN_PARALLEL_SUBCUBE_SIZE {
+            let results: Result<Vec<Samples<S>>, DualContouringError> = subcubes
+                .into_par_iter()
+                .map(|(midx, mpos, value)| {
+                    let mut subcube_samples = Samples::new();
+                    self.sample_value_grid(midx, mpos, size, value, &mut subcube_samples)?;
+                    Ok(subcube_samples)
+                })
+                .collect();
+            for subcube_samples in results? {
+                samples.append(subcube_samples);
+            }
+        } else {
+            for (midx, mpos, value) in subcubes {
+                self.sample_value_grid(midx, mpos, size, value, samples)?;
+            }
+        }
+        Ok(())
+    }
+
+    // For curvature adaptive tessellation: Check whether the subcube of given size at pos is flat
+    // enough to be approximated by trilinear interpolation of its corners. If so, return the
+    // values of its corners.
+    // The curvature proxy is the deviation between the distance estimate |value| / |gradient| at
+    // the center of the subcube and the interpolated distance estimate.
+    fn flat_subcube(&self, pos: na::Point3<S>, size: usize, val: S) -> Option<[S; 8]> {
+        // Testing subcubes of size 2 costs as many evaluations as sampling them.
+        match self.max_flat_subcube_size {
+            Some(max_size) if size > 2 && size <= max_size => {}
+            _ => return None,
+        }
+        let zero: S = From::from(0f32);
+        let size_s: S = From::from(size as f32);
+        let corners = self.subcube_corners(pos, size, val)?;
+        let half: S = From::from(0.5f32);
+        let center = pos + na::Vector3::new(self.res, self.res, self.res) * size_s * half;
+        let center_value = self.value(&center);
+        // Estimate the gradient from the differences between opposing faces of the subcube.
+        let mut gradient = na::Vector3::new(zero, zero, zero);
+        for (i, &corner) in corners.iter().enumerate() {
+            for dim in 0..3 {
+                if i & (1 << dim) == 0 {
+                    gradient[dim] -= corner;
+                } else {
+                    gradient[dim] += corner;
+                }
+            }
+        }
+        let gradient_magnitude = gradient.norm() / (size_s * self.res * From::from(4f32));
+        if gradient_magnitude == zero {
+            return None;
+        }
+        let interpolated = corners.iter().fold(zero, |sum, &v| sum + v) / From::from(8f32);
+        let precision = self.config.precision;
+        if Float::abs(center_value - interpolated) / gradient_magnitude > precision * self.res {
+            return None;
+        }
+        Some(corners)
+    }
+
+    // For near surface refinement: Check whether the subcube of given size at pos is a cell of
+    // the coarse grid, that the surface does not cross, i.e. whose distance estimate at its
+    // center exceeds half of its diagonal. If so, return the values of its corners, which are
+    // interpolated instead of sampling the fine grid.
+    fn distant_subcube(&self, pos: na::Point3<S>, size: usize, val: S) -> Option<[S; 8]> {
+        let factor = self.config.near_surface_refinement_factor;
+        if factor == 1 || size != factor {
+            return None;
+        }
+        let size_s: S = From::from(size as f32);
+        let half: S = From::from(0.5f32);
+        let half_diagonal = size_s * self.res * Float::sqrt(From::from(3f32)) * half;
+        let center = pos + na::Vector3::new(self.res, self.res, self.res) * size_s * half;
+        let center_value = self.value(&center);
+        if self.distance_estimate(&center, center_value, half_diagonal) <= half_diagonal {
+            return None;
+        }
+        self.subcube_corners(pos, size, val)
+    }
+
+    // Sample the values of the corners of the subcube of given size at pos, whose value at pos is
+    // val. Returns None, if one of them is zero, which is left to sample_value_grid.
+    fn subcube_corners(&self, pos: na::Point3<S>, size: usize, val: S) -> Option<[S; 8]> {
+        let size_s: S = From::from(size as f32);
+        let points: Vec<na::Point3<S>> = (1..8)
+            .map(|i| {
+                let offset = na::Vector3::new(
+                    From::from((i & 1) as f32),
+                    From::from((i >> 1 & 1) as f32),
+                    From::from((i >> 2 & 1) as f32),
+                );
+                pos + offset * size_s * self.res
+            })
+            .collect();
+        let mut corners = [val; 8];
+        for (corner, value) in corners.iter_mut().skip(1).zip(self.value_batch(&points)) {
+            if value == From::from(0f32) {
+                return None;
+            }
+            *corner = value;
+        }
+        Some(corners)
+    }
+
+    // If a is inside the object and b outside - this method returns the point on the line between
+    // a and b where the object edge is. It also returns the normal on that point.
+    // av and bv represent the object values at a and b.
+    fn find_zero(&self, a: na::Point3<S>, av: S, b: na::Point3<S>, bv: S) -> Option<Plane<S>> {
+        assert!(a != b);
+        if Float::signum(av) == Float::signum(bv) {
+            return None;
+        }
+        let (mut a, mut av, mut b, mut bv) = (a, av, b, bv);
+        // The values used for interpolation. The Illinois method scales them down, if the same
+        // end of the interval is retained twice in a row.
+        let (mut ai, mut bi) = (av, bv);
+        let mut retained_a = None;
+        let precision = self.config.precision;
+        let half: S = From::from(0.5f32);
+        let mut depth = 0;
+        loop {
+            let d = a - b;
+            let mut distance = Float::max(
+                Float::max(Float::abs(d.x), Float::abs(d.y)),
+                Float::abs(d.z),
+            );
+            distance = Float::min(Float::min(distance, Float::abs(av)), Float::abs(bv));
+            if distance < precision * self.res || depth >= self.config.max_find_zero_depth {
+                let result = if Float::abs(bv) < Float::abs(av) {
+                    &b
+                } else {
+                    &a
+                };
+                return Some(Plane {
+                    p: *result,
+                    // We need a precise normal here.
+                    n: self.normal(result),
+                });
+            }
+            let n = match self.zero_finding_method {
+                ZeroFindingMethod::Bisection => na::center(&a, &b),
+                // Linear interpolation of the zero crossing.
+                ZeroFindingMethod::RegulaFalsi | ZeroFindingMethod::Illinois => {
+                    a + (b - a) * (Float::abs(ai) / Float::abs(bi - ai))
+                }
+            };
+            let nv = self.value(&n);
+            let illinois = self.zero_finding_method == ZeroFindingMethod::Illinois;
+            if Float::signum(av) != Float::signum(nv) {
+                b = n;
+                bv = nv;
+                bi = nv;
+                if illinois && retained_a == Some(true) {
+                    ai *= half;
+                }
+                retained_a = Some(true);
+            } else {
+                a = n;
+                av = nv;
+                ai = nv;
+                if illinois && retained_a == Some(false) {
+                    bi *= half;
+                }
+                retained_a = Some(false);
+            }
+            depth += 1;
+        }
+    }
 }
 
 // Returns the next largest power of 2
@@ -263,6 +1067,83 @@ fn get_connected_edges_from_edge_set(edge_set: BitSet, cell: BitSet) -> Vec<BitS
     result
 }
 
+// Return the signs of the corners of cell idx as in ManifoldDualContouring::bitset_for_cell, or
+// None, if value_grid does not contain all corners of the cell.
+fn cell_bitset<S: RealField + From<f32>>(
+    value_grid: &SparseOctree<S>,
+    period: Option<Index>,
+    idx: Index,
+) -> Option<BitSet> {
+    let mut idx = idx;
+    let mut result = BitSet::zero();
+    for z in 0..2 {
+        for y in 0..2 {
+            for x in 0..2 {
+                if *value_grid.get(&wrap(idx, period))? < From::from(0f32) {
+                    result.set(z << 2 | y << 1 | x);
+                }
+                idx[0] += 1;
+            }
+            idx[0] -= 2;
+            idx[1] += 1;
+        }
+        idx[1] -= 2;
+        idx[2] += 1;
+    }
+    Some(result)
+}
+
+// The vertices of the quad around an edge of the sampling grid as layer and index in the
+// Octtree, in the order of the edge and its quad_neighbours.
+struct Quad {
+    vertices: [(usize, usize); 4],
+    // Whether the edge points from inside to outside, so the quad is reversed.
+    reversed: bool,
+}
+
+// The state needed to find the vertices of quads, which, unlike ManifoldDualContouring, can be
+// shared between threads.
+struct QuadLookup<'c, S: 'c> {
+    value_grid: &'c SparseOctree<S>,
+    period: Option<Index>,
+    vertex_index_map: &'c HashMap<VertexIndex, usize>,
+    selected_vertices: &'c [(usize, usize)],
+}
+
+impl<'c, S: RealField + From<f32>> QuadLookup<'c, S> {
+    fn quad(&self, edge_index: EdgeIndex) -> Quad {
+        debug_assert!((edge_index.edge as usize) < 4);
+        debug_assert!(edge_index.index.iter().all(|&i| i > 0));
+
+        let mut vertices = [(0, 0); 4];
+        let edge = edge_index.edge;
+        for (vertex, &quad_egde) in vertices
+            .iter_mut()
+            .zip(iter::once(&edge).chain(&edge.quad_neighbours()))
+        {
+            let idx = wrap(
+                neg_offset(edge_index.index, EDGE_OFFSET[quad_egde as usize]),
+                self.period,
+            );
+            let bitset = match cell_bitset(self.value_grid, self.period, idx) {
+                Some(bitset) => bitset,
+                None => panic!("did not find value_grid for cell {:?}", idx),
+            };
+            // Generate the proper vertex Index from a single edge and an Index.
+            let vertex_index = VertexIndex {
+                edges: get_connected_edges(quad_egde, bitset),
+                index: idx,
+            };
+            *vertex = self.selected_vertices[self.vertex_index_map[&vertex_index]];
+        }
+        let reversed = match self.value_grid.get(&edge_index.index) {
+            Some(&v) => v < From::from(0f32),
+            None => false,
+        };
+        Quad { vertices, reversed }
+    }
+}
+
 fn half_index(input: &Index) -> Index {
     [input[0] / 2, input[1] / 2, input[2] / 2]
 }
@@ -334,10 +1215,118 @@ fn subsample_euler_characteristics<S: RealField>(
     (intersections, euler)
 }
 
+// Return origin and number of cells of a sampling grid with resolution res, that covers bbox with
+// some padding.
+fn sampling_grid<S: RealField + Float + From<f32> + AsUSize>(
+    bbox: &BoundingBox<S>,
+    res: S,
+) -> (na::Point3<S>, [usize; 3]) {
+    let one: S = From::from(1f32);
+    let mut bbox = bbox.clone();
+    bbox.dilate(one + res * From::from(1.1f32));
+    (
+        bbox.min,
+        [
+            Float::ceil(bbox.dim()[0] / res).as_usize(),
+            Float::ceil(bbox.dim()[1] / res).as_usize(),
+            Float::ceil(bbox.dim()[2] / res).as_usize(),
+        ],
+    )
+}
+
+// Estimate the number of cells of the sampling grid of f at res, that contain the surface, as
+// surface area / res². Cells containing the surface are counted on a grid of at most
+// MEMORY_ESTIMATE_CELLS cells per dimension, which approximates the surface area as their count
+// times their size squared (including the same factor for surfaces crossing cells diagonally).
+fn estimate_surface_cells<S: RealField + Float + From<f32> + AsUSize>(
+    f: &dyn ImplicitFunction<S>,
+    res: S,
+) -> usize {
+    let (origin, dim) = sampling_grid(f.bbox(), res);
+    let max_dim: S = From::from(*dim.iter().max().unwrap() as f32);
+    let h = Float::max(
+        res,
+        res * max_dim / From::from(MEMORY_ESTIMATE_CELLS as f32),
+    );
+    let coarse_dim: Vec<usize> = dim
+        .iter()
+        .map(|&d| {
+            let d: S = From::from(d as f32);
+            Float::ceil(d * res / h).as_usize()
+        })
+        .collect();
+    let corner = |x: usize, y: usize, z: usize| {
+        let p = na::Vector3::new(
+            From::from(x as f32),
+            From::from(y as f32),
+            From::from(z as f32),
+        );
+        f.value(&(origin + p * h)) < S::zero()
+    };
+    let mut inside = Vec::new();
+    for z in 0..=coarse_dim[2] {
+        for y in 0..=coarse_dim[1] {
+            for x in 0..=coarse_dim[0] {
+                inside.push(corner(x, y, z));
+            }
+        }
+    }
+    let index =
+        |x: usize, y: usize, z: usize| (z * (coarse_dim[1] + 1) + y) * (coarse_dim[0] + 1) + x;
+    let mut surface_cells = 0;
+    for z in 0..coarse_dim[2] {
+        for y in 0..coarse_dim[1] {
+            for x in 0..coarse_dim[0] {
+                let first = inside[index(x, y, z)];
+                if (0..8)
+                    .any(|i| inside[index(x + (i & 1), y + (i >> 1 & 1), z + (i >> 2))] != first)
+                {
+                    surface_cells += 1;
+                }
+            }
+        }
+    }
+    let scale = h / res;
+    let surface_cells: S = From::from(surface_cells as f32);
+    cmp::min(
+        Float::round(surface_cells * scale * scale).as_usize(),
+        dim[0] * dim[1] * dim[2],
+    )
+}
+
+// Return an octtree layer, that contains one parent for each vertex in base, without merging any
+// vertices. Since the top layer of the octtree is never used for the mesh, this allows to stop
+// subsampling.
+fn sentinel_octtree_layer<S: RealField>(base: &[Vertex<S>]) -> Vec<Vertex<S>> {
+    base.iter()
+        .enumerate()
+        .map(|(i, vertex)| {
+            vertex.parent.set(Some(i));
+            Vertex {
+                index: half_index(&vertex.index),
+                qef: vertex.qef.clone(),
+                neighbors: [
+                    Vec::new(),
+                    Vec::new(),
+                    Vec::new(),
+                    Vec::new(),
+                    Vec::new(),
+                    Vec::new(),
+                ],
+                parent: Cell::new(None),
+                children: vec![i],
+                mesh_index: Cell::new(None),
+                edge_intersections: vertex.edge_intersections,
+                euler_characteristic: vertex.euler_characteristic,
+            }
+        })
+        .collect()
+}
+
 fn subsample_octtree<S: RealField + Float + From<f32>>(base: &[Vertex<S>]) -> Vec<Vertex<S>> {
     let mut result = Vec::new();
     for (i, vertex) in base.iter().enumerate() {
-        if vertex.parent.get() == None {
+        if vertex.parent.get().is_none() {
             let mut neighbor_set = BTreeSet::new();
             neighbor_set.insert(i);
             add_connected_vertices_in_subcell(base, vertex, &mut neighbor_set);
@@ -362,7 +1351,7 @@ fn subsample_octtree<S: RealField + Float + From<f32>>(base: &[Vertex<S>]) -> Ve
             for &neighbor_index in &neighbor_set {
                 let child = &base[neighbor_index];
                 debug_assert!(
-                    child.parent.get() == None,
+                    child.parent.get().is_none(),
                     "child #{:?} already has parent #{:?}",
                     neighbor_index,
                     child.parent.get().unwrap()
@@ -398,70 +1387,638 @@ impl Timer {
     fn new() -> Timer {
         Timer { t: ::time::now() }
     }
-    fn elapsed(&mut self) -> ::time::Duration {
+    fn elapsed(&mut self) -> Duration {
         let now = ::time::now();
         let result = now - self.t;
         self.t = now;
-        result
+        result.to_std().unwrap_or_default()
     }
 }
 
 impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a, S> {
     /// Constructor
-    /// f: function to tessellate
+    /// f: function to tessellate, which is evaluated from multiple threads
     /// res: resolution
     /// relative_error: acceptable error threshold when simplifying the mesh.
     pub fn new(
-        f: &'a dyn ImplicitFunction<S>,
+        f: &'a (dyn ImplicitFunction<S> + Sync),
         res: S,
         relative_error: S,
     ) -> ManifoldDualContouring<'a, S> {
-        let one: S = From::from(1f32);
-        let mut bbox = f.bbox().clone();
-        bbox.dilate(one + res * From::from(1.1f32));
-        ManifoldDualContouring {
-            function: f,
-            origin: bbox.min,
-            dim: [
-                Float::ceil(bbox.dim()[0] / res).as_usize(),
-                Float::ceil(bbox.dim()[1] / res).as_usize(),
-                Float::ceil(bbox.dim()[2] / res).as_usize(),
-            ],
-            mesh: RefCell::new(Mesh {
-                vertices: Vec::new(),
-                faces: Vec::new(),
-            }),
-            res,
-            error: res * relative_error,
-            value_grid: HashMap::new(),
-            edge_grid: RefCell::new(HashMap::new()),
-            vertex_octtree: Vec::new(),
+        ManifoldDualContouring::with_config(f, res, relative_error, TessellationConfig::default())
+    }
+    /// Constructor with a configuration of the accuracy of tessellation.
+    /// f: function to tessellate, which is evaluated from multiple threads
+    /// res: resolution
+    /// relative_error: acceptable error threshold when simplifying the mesh.
+    /// config: accuracy of zero crossings and placement of vertices outside of their cell
+    pub fn with_config(
+        f: &'a (dyn ImplicitFunction<S> + Sync),
+        res: S,
+        relative_error: S,
+        config: TessellationConfig<S>,
+    ) -> ManifoldDualContouring<'a, S> {
+        ManifoldDualContouring::with_function(
+            SampledFunction::Borrowed(f),
+            res,
+            relative_error,
+            config,
+        )
+    }
+    fn with_function(
+        function: SampledFunction<'a, S>,
+        res: S,
+        relative_error: S,
+        config: TessellationConfig<S>,
+    ) -> ManifoldDualContouring<'a, S> {
+        let error = res * relative_error;
+        let factor = config.near_surface_refinement_factor;
+        let res = res / From::from(factor as f32);
+        let (origin, dim) = sampling_grid(function.bbox(), res);
+        // Distant cells of the coarse grid are interpolated like flat subcubes.
+        let adaptive_grid = if factor > 1 {
+            Some(AdaptiveGrid::new(factor.trailing_zeros() as usize))
+        } else {
+            None
+        };
+        ManifoldDualContouring {
+            sampler: Sampler {
+                function,
+                res,
+                zero_finding_method: ZeroFindingMethod::RegulaFalsi,
+                config,
+                object_transform: None,
+                max_flat_subcube_size: None,
+                narrow_band: None,
+            },
+            origin,
+            dim,
+            res,
+            error,
+            value_grid: SparseOctree::new(),
+            value_grid_loaded: false,
+            adaptive_grid,
+            vertex_placement: None,
+            period: None,
+            max_octtree_layer: None,
+            edge_grid: RefCell::new(HashMap::new()),
+            vertex_octtree: Vec::new(),
             vertex_index_map: HashMap::new(),
+            selected_vertices: Vec::new(),
+            stats: Cell::new(TessellationStats::default()),
+            debug_cells: false,
+            cell_debug_info: RefCell::new(Vec::new()),
+            verbose_sink: None,
+        }
+    }
+    /// Constructor for value grids already computed in index space, e.g. on a GPU. Instead of an
+    /// ImplicitFunction, f maps grid indexes to values, which are negative inside the object.
+    /// Grid index [i, j, k] is located at origin + [i, j, k] * res. During sampling f is called for
+    /// grid indexes in place of ImplicitFunction::value. Between grid indexes values are
+    /// interpolated trilinearly. Surfaces touching the sides of the grid are closed there.
+    /// dims: number of grid indexes in each dimension
+    /// origin: position of grid index [0, 0, 0]
+    /// res: resolution, i.e. the distance of neighboring grid indexes
+    /// f: function on grid indexes, which is evaluated from multiple threads
+    /// relative_error: acceptable error threshold when simplifying the mesh.
+    pub fn with_grid_fn<F>(
+        dims: [usize; 3],
+        origin: na::Point3<S>,
+        res: S,
+        f: F,
+        relative_error: S,
+    ) -> ManifoldDualContouring<'a, S>
+    where
+        F: Fn(Index) -> S + Send + Sync + 'a,
+    {
+        let function = GridFunction::new(dims, origin, res, f);
+        let mut result = ManifoldDualContouring::with_function(
+            SampledFunction::Owned(Arc::new(function)),
+            res,
+            relative_error,
+            TessellationConfig::default(),
+        );
+        // Align the sampling grid to the grid of f, with two more grid indexes on each side, so
+        // surfaces closed at the sides of the grid do not touch the sides of the sampling grid.
+        let padding: S = From::from(2f32);
+        result.origin = origin - na::Vector3::new(res, res, res) * padding;
+        result.dim = [dims[0] + 3, dims[1] + 3, dims[2] + 3];
+        result
+    }
+    /// Constructor for curvature adaptive tessellation. Flat regions of the surface are sampled
+    /// at a coarser resolution than curved regions and interpolated.
+    /// f: function to tessellate
+    /// min_res: resolution used for curved regions
+    /// max_res: coarsest resolution used for flat regions
+    /// relative_error: acceptable error threshold when simplifying the mesh.
+    pub fn new_curvature_adaptive(
+        f: &'a (dyn ImplicitFunction<S> + Sync),
+        min_res: S,
+        max_res: S,
+        relative_error: S,
+    ) -> ManifoldDualContouring<'a, S> {
+        let mut result = ManifoldDualContouring::new(f, min_res, relative_error);
+        let ratio = (max_res / min_res).as_usize();
+        let mut max_depth = 0;
+        while 2 << max_depth <= ratio {
+            max_depth += 1;
+        }
+        let grid = AdaptiveGrid::new(max_depth);
+        result.sampler.max_flat_subcube_size = Some(grid.max_cell_size());
+        result.adaptive_grid = Some(grid);
+        result
+    }
+    /// Constructor for adaptive tessellation, which samples flat regions at a coarser resolution
+    /// like new_curvature_adaptive and bounds the size of clustered cells by max_res.
+    /// Compared to new, this saves function evaluations in flat regions and keeps faces smaller
+    /// than max_res, e.g. for simulation meshes. It never yields fewer faces than new with the
+    /// same relative_error, since new already clusters cells without bound.
+    /// f: function to tessellate
+    /// min_res: resolution used for fine detail
+    /// max_res: resolution of the coarsest cells, used for sampling and meshing flat regions
+    /// relative_error: acceptable error threshold when clustering cells.
+    pub fn new_adaptive(
+        f: &'a (dyn ImplicitFunction<S> + Sync),
+        min_res: S,
+        max_res: S,
+        relative_error: S,
+    ) -> ManifoldDualContouring<'a, S> {
+        let mut result =
+            ManifoldDualContouring::new_curvature_adaptive(f, min_res, max_res, relative_error);
+        let max_cell_size = result.adaptive_grid.as_ref().unwrap().max_cell_size();
+        result.max_octtree_layer = Some(max_cell_size.trailing_zeros() as usize);
+        result
+    }
+    /// Constructor for periodic tessellation, e.g. of crystals or foams. The grid wraps around,
+    /// so the surface is connected across opposite sides of the box starting at the minimum of
+    /// the bounding box of f, with size period in each dimension. f must be periodic as well.
+    /// Faces crossing the sides of the box connect vertices on opposite sides.
+    /// f: function to tessellate
+    /// period: size of the box, which should be a multiple of res in each dimension.
+    /// res: resolution
+    /// relative_error: acceptable error threshold when simplifying the mesh.
+    pub fn new_periodic(
+        f: &'a (dyn ImplicitFunction<S> + Sync),
+        period: [S; 3],
+        res: S,
+        relative_error: S,
+    ) -> ManifoldDualContouring<'a, S> {
+        let mut result = ManifoldDualContouring::new(f, res, relative_error);
+        let half: S = From::from(0.5f32);
+        let cells = [
+            (period[0] / res + half).as_usize(),
+            (period[1] / res + half).as_usize(),
+            (period[2] / res + half).as_usize(),
+        ];
+        // Index cells[i] is located at the minimum of the bounding box.
+        result.origin = f.bbox().min
+            - na::Vector3::new(
+                From::from(cells[0] as f32),
+                From::from(cells[1] as f32),
+                From::from(cells[2] as f32),
+            ) * res;
+        result.dim = cells;
+        result.period = Some(cells);
+        result
+    }
+    /// Tessellate the function transformed by transform from object to world space, e.g. to
+    /// translate or rotate it, without wrapping it into another ImplicitFunction. The sampling
+    /// grid is fitted to the transformed bounding box. For periodic tessellation only the
+    /// position of the box is changed, so transform should be a translation.
+    /// Panics, if transform is not invertible.
+    pub fn set_object_transform(&mut self, transform: na::Matrix4<S>) {
+        let inverse = transform
+            .try_inverse()
+            .expect("object transform is not invertible");
+        self.sampler.object_transform = Some((transform, inverse));
+        let bbox = self.sampler.bbox();
+        match self.period {
+            Some(cells) => {
+                self.origin = bbox.min
+                    - na::Vector3::new(
+                        From::from(cells[0] as f32),
+                        From::from(cells[1] as f32),
+                        From::from(cells[2] as f32),
+                    ) * self.res
+            }
+            None => {
+                let (origin, dim) = sampling_grid(&bbox, self.res);
+                self.origin = origin;
+                self.dim = dim;
+            }
+        }
+    }
+    /// Select the method used to find the zero crossings along the edges of the sampling grid.
+    /// The default is ZeroFindingMethod::RegulaFalsi.
+    pub fn zero_finding_method(mut self, method: ZeroFindingMethod) -> Self {
+        self.sampler.zero_finding_method = method;
+        self
+    }
+    /// Only store values within band_width of the surface in the value grid, which saves memory
+    /// for large objects, where most of the grid is far from the surface. Cells outside the band
+    /// are treated as having a constant sign, so they have no edge crossings. The band is at
+    /// least the diagonal of a cell, so all cells at the surface are kept.
+    pub fn with_narrow_band(mut self, band_width: S) -> Self {
+        let diagonal = self.res * Float::sqrt(From::from(3f32));
+        self.sampler.narrow_band = Some(Float::max(band_width, diagonal));
+        self
+    }
+    /// Record diagnostic information about every solved QEF, which can be retrieved with
+    /// export_cell_debug_info after tessellation. The default is false.
+    pub fn debug_cells(mut self, enabled: bool) -> Self {
+        self.debug_cells = enabled;
+        self
+    }
+    /// Pass the progress of tessellation to sink, e.g. to log it. Without a sink, no progress is
+    /// reported. Events implement Display, so a sink printing them to stdout is
+    /// `Box::new(|event| println!("{}", event))`.
+    pub fn set_verbose_sink(&mut self, sink: Box<dyn Fn(VerboseEvent<S>) + Send + Sync>) {
+        self.verbose_sink = Some(Arc::from(sink));
+    }
+    // Pass event to verbose_sink, if there is one.
+    fn emit(&self, event: VerboseEvent<S>) {
+        if let Some(ref sink) = self.verbose_sink {
+            sink(event);
         }
     }
+    /// Place mesh vertices with f instead of the QEF solver, e.g. at the centroid of the edge
+    /// intersections. The QEF is still used to decide which vertices of the octree are merged.
+    pub fn vertex_placement<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[Plane<S>], [usize; 3]) -> na::Point3<S> + Send + Sync + 'static,
+    {
+        self.vertex_placement = Some(Arc::new(f));
+        self
+    }
     /// Tessellate the given function.
     pub fn tessellate(&mut self) -> Option<Mesh<S>> {
-        println!(
-            "ManifoldDualContouring: res: {:} {:?}",
-            self.res,
-            self.function.bbox()
-        );
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        self.generate_polygons(&mut mesh);
+        Some(mesh)
+    }
+
+    /// Tessellate the given function like tessellate and also return the sharp features of the
+    /// mesh as pairs of vertex indexes, e.g. to render creases. Each pair of adjacent quads
+    /// belongs to two edges of the sampling grid. The edge shared by the quads is sharp, if the
+    /// normals of the tangent planes at the zero crossings of both grid edges differ by more than
+    /// 30 degrees. Vertex positions are the QEF solutions as in tessellate, which already place
+    /// vertices on sharp features.
+    #[allow(clippy::type_complexity)]
+    pub fn tessellate_with_feature_lines(&mut self) -> Option<(Mesh<S>, Vec<(usize, usize)>)> {
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        let polygons = self.generate_polygons(&mut mesh);
+        let mut edge_normals: HashMap<(usize, usize), Vec<na::Vector3<S>>> = HashMap::new();
+        for (edge, polygon) in &polygons {
+            let n = self.get_edge_tangent_plane(edge).n;
+            for i in 0..polygon.len() {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                edge_normals
+                    .entry((cmp::min(a, b), cmp::max(a, b)))
+                    .or_default()
+                    .push(n);
+            }
+        }
+        let min_cos = Float::cos(<S as From<f32>>::from(FEATURE_LINE_ANGLE));
+        let mut features: Vec<(usize, usize)> = edge_normals
+            .into_iter()
+            .filter(|(_, normals)| normals.len() == 2 && normals[0].dot(&normals[1]) < min_cos)
+            .map(|(edge, _)| edge)
+            .collect();
+        features.sort();
+        Some((mesh, features))
+    }
+
+    // Do all steps of tessellate, adding the vertices and faces to sink, and return the polygons
+    // of the mesh along with the edges of the sampling grid they belong to. Polygons are quads,
+    // or triangles, if two vertices of a quad were merged.
+    fn generate_polygons(&mut self, sink: &mut impl MeshSink<S>) -> Vec<(EdgeIndex, Vec<usize>)> {
+        self.prepare();
+        let mut t = Timer::new();
+        // Look up the vertices of the quads in parallel, but add them to the mesh sequentially,
+        // so the mesh does not depend on scheduling.
+        let edges = self.sorted_edges();
+        let quads: Vec<Quad> = {
+            let lookup = self.quad_lookup();
+            edges.par_iter().map(|&edge| lookup.quad(edge)).collect()
+        };
+        let quad_count = quads.len();
+        let polygons: Vec<(EdgeIndex, Vec<usize>)> = edges
+            .into_iter()
+            .zip(quads)
+            .map(|(edge, quad)| (edge, self.add_quad(sink, quad)))
+            .filter(|(_, polygon)| polygon.len() > 2)
+            .collect();
+        self.emit(VerboseEvent::QuadStats {
+            quad_count,
+            elapsed: t.elapsed(),
+        });
+        self.emit(VerboseEvent::MeshStats {
+            face_count: polygons.iter().map(|(_, polygon)| polygon.len() - 2).sum(),
+        });
+        polygons
+    }
+
+    /// Tessellate the given function like tessellate, but add the vertices, with their normals,
+    /// and faces to a HalfEdgeMesh while they are generated, instead of to a Mesh.
+    pub fn tessellate_half_edge(&mut self) -> Option<HalfEdgeMesh<S>> {
+        let mut result = HalfEdgeMesh::new();
+        self.generate_polygons(&mut result);
+        Some(result)
+    }
+
+    /// Tessellate the given function like tessellate and return statistics of the tessellation
+    /// alongside the mesh, e.g. to check how often the ClampStrategy was used.
+    pub fn tessellate_with_stats(&mut self) -> Option<(Mesh<S>, TessellationStats)> {
+        let mesh = self.tessellate()?;
+        Some((mesh, self.stats.get()))
+    }
+
+    /// Return statistics of the sampling grid of the last tessellation, e.g. to check whether the
+    /// resolution fits the function. Tessellation warns about suspicious statistics.
+    pub fn report_grid_stats(&self) -> GridStats<S> {
+        let total_cells = self.dim[0] * self.dim[1] * self.dim[2];
+        let surface_cells = self
+            .vertex_index_map
+            .keys()
+            .map(|vertex_index| vertex_index.index)
+            .collect::<HashSet<_>>()
+            .len();
+        let surface_fraction = if total_cells == 0 {
+            S::zero()
+        } else {
+            From::from(surface_cells as f32 / total_cells as f32)
+        };
+        GridStats {
+            total_cells,
+            surface_cells,
+            surface_fraction,
+            estimated_triangle_count: 2 * self.edge_grid.borrow().len(),
+        }
+    }
+
+    /// Return diagnostic information about the cells solved by the last tessellation, e.g. to
+    /// find the cause of tessellation artifacts. Empty, unless debug_cells was enabled.
+    pub fn export_cell_debug_info(&self) -> Vec<CellDebugInfo<S>> {
+        self.cell_debug_info.borrow().clone()
+    }
+
+    /// Estimate the Lipschitz constant of the function, i.e. the maximum of
+    /// |f(a) - f(b)| / |a - b| over random pairs of points a and b in the bounding box, with b
+    /// within one cell of a. Pruning of the sampling grid relies on this being at most 1 (unless
+    /// ImplicitFunction::lipschitz_constant says otherwise). If it exceeds 1, a warning is
+    /// printed, since the function may not be a proper distance function.
+    pub fn measure_sdf_lipschitz(&self) -> S {
+        let mut rng = StdRng::seed_from_u64(0);
+        let bbox = self.sampler.bbox();
+        let random_vector = |rng: &mut StdRng| {
+            na::Vector3::new(
+                From::from(rng.gen::<f32>()),
+                From::from(rng.gen::<f32>()),
+                From::from(rng.gen::<f32>()),
+            )
+        };
+        let one = na::Vector3::new(S::one(), S::one(), S::one());
+        let mut result = S::zero();
+        for _ in 0..LIPSCHITZ_SAMPLES {
+            let a = bbox.min + bbox.dim().component_mul(&random_vector(&mut rng));
+            let two: S = From::from(2f32);
+            let offset: na::Vector3<S> = (random_vector(&mut rng) * two - one) * self.res;
+            let b = a + offset;
+            let distance = na::distance(&a, &b);
+            if distance > S::zero() {
+                let slope = Float::abs(self.sampler.value(&a) - self.sampler.value(&b)) / distance;
+                result = Float::max(result, slope);
+            }
+        }
+        let expected = self
+            .sampler
+            .function
+            .lipschitz_constant()
+            .unwrap_or_else(S::one);
+        if result > expected + From::from(LIPSCHITZ_TOLERANCE) {
+            self.emit(VerboseEvent::LipschitzWarning {
+                measured: result,
+                expected,
+            });
+        }
+        result
+    }
+
+    /// Estimate the memory in bytes, which tessellate needs for f at resolution res, without
+    /// tessellating, e.g. to choose a resolution, that does not run out of memory. The number of
+    /// cells near the surface is estimated as surface area / res², where the surface area is
+    /// approximated from the sign changes on a coarse grid over the bounding box.
+    pub fn estimate_memory_for_resolution(f: &dyn ImplicitFunction<S>, res: S) -> usize {
+        // Per cell near the surface: the values at its corners, its leaf vertex and the entry in
+        // vertex_index_map, the zero crossings on three edges and a mesh vertex with two faces.
+        let bytes_per_cell = 8 * mem::size_of::<(Index, S)>()
+            + mem::size_of::<Vertex<S>>()
+            + mem::size_of::<(VertexIndex, usize)>()
+            + 3 * mem::size_of::<(EdgeIndex, Plane<S>)>()
+            + mem::size_of::<[S; 3]>()
+            + 2 * mem::size_of::<[usize; 3]>();
+        estimate_surface_cells(f, res) * bytes_per_cell
+    }
+
+    /// Tessellate each of functions with its own ManifoldDualContouring in parallel, e.g. for
+    /// scenes of many objects. Returns the results of tessellate in the order of functions.
+    /// res: resolution
+    /// relative_error: acceptable error threshold when simplifying the meshes.
+    pub fn parallel_tessellate(
+        functions: &[&'a (dyn ImplicitFunction<S> + Sync)],
+        res: S,
+        relative_error: S,
+    ) -> Vec<Option<Mesh<S>>> {
+        functions
+            .par_iter()
+            .map(|&f| ManifoldDualContouring::new(f, res, relative_error).tessellate())
+            .collect()
+    }
+
+    /// Write the value grid sampled by tessellate, so it can be loaded with load_value_grid to
+    /// tessellate again without sampling, e.g. with a different TessellationConfig.
+    /// The format is binary little endian: The magic number "TSVG", a version byte, the number
+    /// of cells in each dimension as u64, the resolution and origin as f64, the number of values
+    /// as u64 and then each grid index as three u32 followed by its value as f64.
+    pub fn save_value_grid(&self, writer: &mut impl Write) -> io::Result<()> {
+        let to_f64 = |x: S| x.to_f64().unwrap_or(f64::NAN);
+        let mut values: Vec<_> = self.value_grid.iter().collect();
+        values.sort_by_key(|&(idx, _)| idx);
+        // Check indexes up front, so oversized grids do not leave truncated files behind.
+        let max_index = values
+            .iter()
+            .flat_map(|(idx, _)| idx.iter())
+            .chain(self.dim.iter())
+            .max();
+        if let Some(&i) = max_index {
+            if i > u32::MAX as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("grid index {} exceeds u32", i),
+                ));
+            }
+        }
+        writer.write_all(&VALUE_GRID_MAGIC)?;
+        writer.write_all(&[VALUE_GRID_VERSION])?;
+        for &d in &self.dim {
+            writer.write_all(&(d as u64).to_le_bytes())?;
+        }
+        writer.write_all(&to_f64(self.res).to_le_bytes())?;
+        for i in 0..3 {
+            writer.write_all(&to_f64(self.origin[i]).to_le_bytes())?;
+        }
+        writer.write_all(&(values.len() as u64).to_le_bytes())?;
+        for (idx, value) in values {
+            for &i in &idx {
+                writer.write_all(&(i as u32).to_le_bytes())?;
+            }
+            writer.write_all(&to_f64(value).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Load a value grid written by save_value_grid, which replaces sampling the function in the
+    /// next call of tessellate. Fails, if resolution, origin or number of cells of the value grid
+    /// differ from this ManifoldDualContouring.
+    pub fn load_value_grid(&mut self, reader: &mut impl Read) -> Result<(), GridCacheError> {
+        let mut magic = [0u8; 5];
+        reader.read_exact(&mut magic)?;
+        if magic[..4] != VALUE_GRID_MAGIC || magic[4] != VALUE_GRID_VERSION {
+            return Err(GridCacheError::InvalidHeader(format!(
+                "magic number {:?} version {}",
+                &magic[..4],
+                magic[4]
+            )));
+        }
+        let mut buffer = [0u8; 8];
+        let mut read_u64 = |reader: &mut dyn Read| -> io::Result<u64> {
+            reader.read_exact(&mut buffer)?;
+            Ok(u64::from_le_bytes(buffer))
+        };
+        let mut dim = [0; 3];
+        for d in &mut dim {
+            *d = read_u64(reader)? as usize;
+        }
+        let res = f64::from_bits(read_u64(reader)?);
+        let mut origin = [0.; 3];
+        for o in &mut origin {
+            *o = f64::from_bits(read_u64(reader)?);
+        }
+        let to_f64 = |x: S| x.to_f64().unwrap_or(f64::NAN);
+        if dim != self.dim
+            || res != to_f64(self.res)
+            || (0..3).any(|i| origin[i] != to_f64(self.origin[i]))
+        {
+            return Err(GridCacheError::DimensionMismatch(format!(
+                "got {:?} cells, res {} and origin {:?}, expected {:?} cells, res {} and origin {}",
+                dim, res, origin, self.dim, self.res, self.origin
+            )));
+        }
+        // Sampling covers a cube with a power of two cells as side, starting at the period.
+        let maxdim = cmp::max(self.dim[0], cmp::max(self.dim[1], self.dim[2]));
+        let start = self.period.unwrap_or([0, 0, 0]);
+        let limit = [
+            start[0] + pow2roundup(maxdim),
+            start[1] + pow2roundup(maxdim),
+            start[2] + pow2roundup(maxdim),
+        ];
+        let num_values = read_u64(reader)?;
+        let mut value_grid = SparseOctree::new();
+        let mut entry = [0u8; 20];
+        for _ in 0..num_values {
+            reader.read_exact(&mut entry)?;
+            let mut idx = [0; 3];
+            for (i, x) in idx.iter_mut().enumerate() {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(&entry[4 * i..4 * i + 4]);
+                *x = u32::from_le_bytes(bytes) as usize;
+            }
+            if (0..3).any(|i| idx[i] > limit[i]) {
+                return Err(GridCacheError::DimensionMismatch(format!(
+                    "value at {:?} outside of the sampled grid up to {:?}",
+                    idx, limit
+                )));
+            }
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&entry[12..]);
+            let value = <S as NumCast>::from(f64::from_le_bytes(bytes))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "value out of range"))?;
+            value_grid.insert(idx, value);
+        }
+        self.value_grid = value_grid;
+        self.value_grid_loaded = true;
+        if let Some(ref mut adaptive_grid) = self.adaptive_grid {
+            adaptive_grid.clear();
+        }
+        self.edge_grid.borrow_mut().clear();
+        self.vertex_octtree.clear();
+        self.vertex_index_map.clear();
+        Ok(())
+    }
+
+    /// Tessellate the given function incrementally. Everything but the generation of the faces
+    /// is done upfront. The returned iterator then generates the faces for one edge of the
+    /// sampling grid per call to next().
+    pub fn tessellate_incremental<'b>(&'b mut self) -> IncrementalTessellator<'b, 'a, S> {
+        self.tessellate_incremental_into(Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        })
+    }
+
+    /// Tessellate the given function incrementally like tessellate_incremental, but add the
+    /// vertices and faces to sink instead of to a Mesh. So the mesh is never kept in memory,
+    /// if sink does not keep it, e.g. a StreamingMeshWriter.
+    pub fn tessellate_incremental_into<'b, K: MeshSink<S>>(
+        &'b mut self,
+        sink: K,
+    ) -> IncrementalTessellator<'b, 'a, S, K> {
+        self.prepare();
+        IncrementalTessellator {
+            edges: self.sorted_edges(),
+            mdc: self,
+            position: 0,
+            sink,
+        }
+    }
+
+    // Do all steps of the tessellation, but generating the faces.
+    fn prepare(&mut self) {
+        self.emit(VerboseEvent::Start {
+            res: self.res,
+            bbox: self.sampler.bbox().clone(),
+        });
+        let mut rng = StdRng::seed_from_u64(RETRY_SEED);
         loop {
-            match self.try_tessellate() {
-                Ok(mesh) => return Some(mesh),
+            match self.try_prepare() {
+                Ok(()) => return,
                 // Tessellation failed, b/c the value in one of the grid cells was exactly zero.
-                // Retry with some random padding and hope for the best.
+                // Retry with some pseudo random padding and hope for the best.
                 Err(e) => {
                     let padding = na::Vector3::new(
-                        -self.res / From::from(10. + rand::random::<f32>().abs()),
-                        -self.res / From::from(10. + rand::random::<f32>().abs()),
-                        -self.res / From::from(10. + rand::random::<f32>().abs()),
+                        -self.res / From::from(10. + rng.gen::<f32>()),
+                        -self.res / From::from(10. + rng.gen::<f32>()),
+                        -self.res / From::from(10. + rng.gen::<f32>()),
                     );
-                    println!("Error: {:?}. moving by {:?} and retrying.", e, padding);
+                    self.emit(VerboseEvent::Retry {
+                        error: format!("{:?}", e),
+                        padding,
+                    });
                     self.origin += padding;
                     self.value_grid.clear();
-                    self.mesh.borrow_mut().vertices.clear();
-                    self.mesh.borrow_mut().faces.clear();
+                    self.value_grid_loaded = false;
+                    if let Some(ref mut adaptive_grid) = self.adaptive_grid {
+                        adaptive_grid.clear();
+                    }
                     self.vertex_octtree.clear();
                     self.vertex_index_map.clear();
                 }
@@ -471,126 +2028,146 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
 
     fn tessellation_step1(&mut self) -> Option<DualContouringError> {
         let maxdim = cmp::max(self.dim[0], cmp::max(self.dim[1], self.dim[2]));
-        let origin = self.origin;
-        let origin_value = self.function.value(&origin);
+        let start = self.period.unwrap_or([0, 0, 0]);
+        let origin = self.origin
+            + na::Vector3::new(
+                From::from(start[0] as f32),
+                From::from(start[1] as f32),
+                From::from(start[2] as f32),
+            ) * self.res;
+        let origin_value = self.sampler.value(&origin);
 
-        self.sample_value_grid([0, 0, 0], origin, pow2roundup(maxdim), origin_value)
+        let mut samples = Samples::new();
+        let result = self
+            .sampler
+            .sample_value_grid(
+                start,
+                origin,
+                pow2roundup(maxdim),
+                origin_value,
+                &mut samples,
+            )
+            .err();
+        self.value_grid.extend(samples.values);
+        if let Some(ref mut grid) = self.adaptive_grid {
+            for (depth, cell_index, corners) in samples.flat_subcubes {
+                grid.insert(depth, cell_index, corners);
+            }
+        }
+        if let Some(period) = self.period {
+            // Drop indexes beyond the period. They are sampled again as the start of the next
+            // period.
+            self.value_grid
+                .retain(|idx, _| (0..3).all(|i| idx[i] < 2 * period[i]));
+        }
+        result
     }
 
     // This method does the main work of tessellation.
     // It may fail, if the value in one of the grid cells yields exactly zero.
-    fn try_tessellate(&mut self) -> Result<Mesh<S>, DualContouringError> {
+    fn try_prepare(&mut self) -> Result<(), DualContouringError> {
         let mut t = Timer::new();
-        if let Some(e) = self.tessellation_step1() {
-            return Err(e);
-        }
         let total_cells = self.dim[0] * self.dim[1] * self.dim[2];
-        println!(
-            "generated value_grid with {:} % of {:} cells in {:}.",
-            (100 * self.value_grid.len()) as f64 / total_cells as f64,
-            total_cells,
-            t.elapsed()
-        );
+        if !self.value_grid_loaded {
+            if let Some(e) = self.tessellation_step1() {
+                return Err(e);
+            }
+            if let Some(e) = self.expand_adaptive_grid() {
+                return Err(e);
+            }
+        }
+        self.emit(VerboseEvent::ValueGridStats {
+            cell_count: total_cells,
+            value_count: self.value_grid.len(),
+            elapsed: t.elapsed(),
+        });
 
         self.compact_value_grid();
-        println!(
-            "compacted value_grid, now {:} % of {:} cells in {:}.",
-            (100 * self.value_grid.len()) as f64 / total_cells as f64,
-            total_cells,
-            t.elapsed()
-        );
+        self.emit(VerboseEvent::CompactedValueGridStats {
+            cell_count: total_cells,
+            value_count: self.value_grid.len(),
+            elapsed: t.elapsed(),
+        });
 
         self.generate_edge_grid();
 
-        println!(
-            "generated edge_grid with {} edges: {:}",
-            self.edge_grid.borrow().len(),
-            t.elapsed()
-        );
+        self.emit(VerboseEvent::EdgeGridStats {
+            edge_count: self.edge_grid.borrow().len(),
+            elapsed: t.elapsed(),
+        });
 
         let (leafs, index_map) = self.generate_leaf_vertices();
         self.vertex_index_map = index_map;
+        // Drop the octree of a previous tessellation, whose vertices refer to another mesh.
+        self.vertex_octtree.clear();
         self.vertex_octtree.push(leafs);
 
-        println!(
-            "generated {:?} leaf vertices: {:}",
-            self.vertex_octtree[0].len(),
-            t.elapsed()
-        );
+        self.emit(VerboseEvent::LeafVertexStats {
+            vertex_count: self.vertex_octtree[0].len(),
+            elapsed: t.elapsed(),
+        });
+        let grid_stats = self.report_grid_stats();
+        if grid_stats.is_suspicious() {
+            self.emit(VerboseEvent::GridStatsWarning { stats: grid_stats });
+        }
 
         loop {
+            // Subcells of deeper layers would not align with the sides of the period.
+            let period_layer = self
+                .period
+                .map(|period| period.iter().map(|p| p.trailing_zeros()).min().unwrap() as usize);
+            let max_layer = match (period_layer, self.max_octtree_layer) {
+                (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                (a, b) => a.or(b),
+            };
+            if let Some(max_layer) = max_layer {
+                if self.vertex_octtree.len() > max_layer {
+                    let top = sentinel_octtree_layer(self.vertex_octtree.last().unwrap());
+                    self.vertex_octtree.push(top);
+                    break;
+                }
+            }
             let next = subsample_octtree(self.vertex_octtree.last().unwrap());
             if next.len() == self.vertex_octtree.last().unwrap().len() {
                 break;
             }
             self.vertex_octtree.push(next);
         }
-        println!("subsampled octtree {:}", t.elapsed());
-
-        let num_qefs_solved = self.solve_qefs();
+        self.emit(VerboseEvent::OcttreeStats {
+            layer_count: self.vertex_octtree.len(),
+            elapsed: t.elapsed(),
+        });
 
-        println!("solved {} qefs: {:}", num_qefs_solved, t.elapsed());
+        let stats = self.solve_qefs();
+        self.stats.set(stats);
+        self.precompute_all_qef_solutions();
+        let stats = self.stats.get();
 
-        for edge_index in self.edge_grid.borrow().keys() {
-            self.compute_quad(*edge_index);
-        }
-        println!("generated quads: {:}", t.elapsed());
-
-        println!(
-            "computed mesh with {:?} faces.",
-            self.mesh.borrow().faces.len()
-        );
+        self.emit(VerboseEvent::QefStats {
+            stats,
+            elapsed: t.elapsed(),
+        });
 
-        Ok(self.mesh.borrow().clone())
+        Ok(())
     }
 
-    fn sample_value_grid(
-        &mut self,
-        idx: Index,
-        pos: na::Point3<S>,
-        size: usize,
-        val: S,
-    ) -> Option<DualContouringError> {
-        debug_assert!(size > 1);
-        let mut midx = idx;
-        let size = size / 2;
-        let size_s: S = From::from(size as f32);
-        let vpos = [
-            pos,
-            pos + na::Vector3::new(self.res, self.res, self.res) * size_s,
-        ];
-        let sub_cube_diagonal = size_s * self.res * Float::sqrt(From::from(3f32));
-
-        for z in 0..2 {
-            for y in 0..2 {
-                for x in 0..2 {
-                    let mpos = na::Point3::new(vpos[x].x, vpos[y].y, vpos[z].z);
-                    let value = if midx == idx {
-                        val
-                    } else {
-                        self.function.value(&mpos)
-                    };
-
-                    if value == From::from(0f32) {
-                        return Some(DualContouringError::HitZero(format!("{}", mpos)));
-                    }
-
-                    if size > 1 && Float::abs(value) <= sub_cube_diagonal {
-                        if let Some(e) = self.sample_value_grid(midx, mpos, size, value) {
-                            return Some(e);
-                        }
-                    } else {
-                        self.value_grid.insert(midx, value);
-                    }
-                    midx[0] += size;
-                }
-                midx[0] -= 2 * size;
-                midx[1] += size;
+    // Interpolate the flat subcubes of adaptive_grid into value_grid.
+    fn expand_adaptive_grid(&mut self) -> Option<DualContouringError> {
+        let grid = self.adaptive_grid.as_ref()?;
+        let value_grid = &mut self.value_grid;
+        let mut zero_index = None;
+        grid.expand(|idx, value| {
+            if value == From::from(0f32) {
+                zero_index = Some(idx);
             }
-            midx[1] -= 2 * size;
-            midx[2] += size;
-        }
-        None
+            if !value_grid.contains_key(&idx) {
+                value_grid.insert(idx, value);
+            }
+        });
+        self.emit(VerboseEvent::FlatSubcubeStats {
+            subcube_count: grid.len(),
+        });
+        zero_index.map(|idx| DualContouringError::HitZero(format!("{:?}", idx)))
     }
 
     // Delete all values from value grid that do not have a value of opposing signum in any
@@ -599,9 +2176,10 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
     fn compact_value_grid(&mut self) {
         // Collect all indexes to remove.
         let value_grid = &mut self.value_grid;
+        let period = self.period;
         let keys_to_remove: Vec<_> = value_grid
             .par_iter()
-            .filter(|&(idx, &v)| {
+            .filter(|&(idx, v)| {
                 if idx[0] == 0 || idx[1] == 0 || idx[2] == 0 {
                     // This grid cell does not have neighbors in some directions. Ignore.
                     return false;
@@ -609,7 +2187,8 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
                 for z in 0..3 {
                     for y in 0..3 {
                         for x in 0..3 {
-                            let adjacent_idx = [idx[0] + x - 1, idx[1] + y - 1, idx[2] + z - 1];
+                            let adjacent_idx =
+                                wrap([idx[0] + x - 1, idx[1] + y - 1, idx[2] + z - 1], period);
                             if let Some(&adjacent_value) = value_grid.get(&adjacent_idx) {
                                 if Float::signum(v) != Float::signum(adjacent_value) {
                                     // Don't collect indexes with
@@ -621,60 +2200,70 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
                     }
                 }
                 true
-            }).map(|(k, _)| *k)
+            })
+            .map(|(k, _)| k)
             .collect();
+        // Empty subtrees of value_grid are pruned on removal.
         for k in keys_to_remove {
             value_grid.remove(&k);
         }
-        value_grid.shrink_to_fit();
     }
 
     // Store crossing positions of edges in edge_grid
     fn generate_edge_grid(&mut self) {
-        let mut edge_grid = self.edge_grid.borrow_mut();
-        for (&point_idx, &point_value) in &self.value_grid {
-            for &edge in &[Edge::A, Edge::B, Edge::C] {
-                let mut adjacent_idx = point_idx;
-                adjacent_idx[edge as usize] += 1;
-                if let Some(&adjacent_value) = self.value_grid.get(&adjacent_idx) {
-                    let point_pos = self.origin
+        let value_grid = &self.value_grid;
+        let sampler = &self.sampler;
+        let (origin, res, period) = (self.origin, self.res, self.period);
+        let edge_grid = value_grid
+            .par_iter()
+            .flat_map_iter(|(point_idx, point_value)| {
+                [Edge::A, Edge::B, Edge::C].iter().filter_map(move |&edge| {
+                    let mut adjacent_idx = point_idx;
+                    adjacent_idx[edge as usize] += 1;
+                    let adjacent_value = *value_grid.get(&wrap(adjacent_idx, period))?;
+                    let point_pos = origin
                         + na::Vector3::new(
                             From::from(point_idx[0] as f32),
                             From::from(point_idx[1] as f32),
                             From::from(point_idx[2] as f32),
-                        ) * self.res;
+                        ) * res;
                     let mut adjacent_pos = point_pos;
-                    adjacent_pos[edge as usize] += self.res;
-                    if let Some(plane) =
-                        self.find_zero(point_pos, point_value, adjacent_pos, adjacent_value)
-                    {
-                        edge_grid.insert(
-                            EdgeIndex {
-                                edge,
-                                index: point_idx,
-                            },
-                            plane,
-                        );
-                    }
-                }
-            }
-        }
+                    adjacent_pos[edge as usize] += res;
+                    let plane =
+                        sampler.find_zero(point_pos, point_value, adjacent_pos, adjacent_value)?;
+                    Some((
+                        EdgeIndex {
+                            edge,
+                            index: point_idx,
+                        },
+                        plane,
+                    ))
+                })
+            })
+            .collect();
+        *self.edge_grid.borrow_mut() = edge_grid;
     }
 
     // Solves QEFs in vertex stack, starting at the highest level, down all layers until the qef
     // error is below threshold.
-    // Returns the number of solved QEFs.
-    fn solve_qefs(&self) -> usize {
-        let mut num_solved = 0;
+    // Returns the number of solved and clamped QEFs.
+    fn solve_qefs(&self) -> TessellationStats {
+        let mut stats = TessellationStats::default();
+        self.cell_debug_info.borrow_mut().clear();
         if let Some(top_layer) = self.vertex_octtree.last() {
             for i in 0..top_layer.len() {
-                num_solved += self.recursively_solve_qefs(&self.vertex_octtree.len() - 1, i);
+                self.recursively_solve_qefs(&self.vertex_octtree.len() - 1, i, &mut stats);
             }
         }
-        num_solved
+        stats
     }
 
-    fn recursively_solve_qefs(&self, layer: usize, index_in_layer: usize) -> usize {
+    fn recursively_solve_qefs(
+        &self,
+        layer: usize,
+        index_in_layer: usize,
+        stats: &mut TessellationStats,
+    ) {
         let vertex = &self.vertex_octtree[layer][index_in_layer];
         assert!(vertex.children.is_empty() || layer > 0);
         let error;
@@ -690,17 +2279,102 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
                 vertex.index,
                 vertex.parent
             );
-            qef.solve();
+            let clamped = qef.solve(self.sampler.config.clamp_strategy, || {
+                let mut planes = Vec::new();
+                self.collect_tangent_planes(layer, vertex, &mut planes);
+                planes.iter().map(|plane| plane.p).collect()
+            });
             error = qef.error;
+            stats.solved_qefs += 1;
+            if clamped {
+                stats.clamps += 1;
+            }
+            self.record_cell_debug_info(layer, vertex, &qef, clamped);
         }
-        let mut num_solved = 1;
         // If error exceed threshold, recurse into subvertices.
         if Float::abs(error) > self.error {
             for &child_index in &vertex.children {
-                num_solved += self.recursively_solve_qefs(layer - 1, child_index);
+                self.recursively_solve_qefs(layer - 1, child_index, stats);
+            }
+        }
+    }
+
+    // Append the solved qef of vertex in layer to cell_debug_info, if debug_cells is enabled.
+    fn record_cell_debug_info(
+        &self,
+        layer: usize,
+        vertex: &Vertex<S>,
+        qef: &qef::Qef<S>,
+        clamped: bool,
+    ) {
+        if self.debug_cells {
+            self.cell_debug_info.borrow_mut().push(CellDebugInfo {
+                layer,
+                index: vertex.index,
+                bitset: self.corner_signs(layer, vertex.index),
+                qef_residual: qef.error,
+                was_clamped: clamped,
+                vertex_pos: na::Point3::from(qef.solution),
+            });
+        }
+    }
+
+    // Select the vertex of the octtree used in the mesh for each leaf vertex, and solve the
+    // qefs of selected vertices, which solve_qefs skipped. This way all qefs are solved before
+    // generating quads, and quads only need to look up selected_vertices.
+    fn precompute_all_qef_solutions(&mut self) {
+        let mut stats = self.stats.get();
+        let mut selected_vertices = Vec::with_capacity(self.vertex_octtree[0].len());
+        for leaf_index in 0..self.vertex_octtree[0].len() {
+            let mut octtree_index = leaf_index;
+            let mut octtree_layer = 0;
+            // Walk up the chain of parents
+            loop {
+                let next_index = self.vertex_octtree[octtree_layer][octtree_index]
+                    .parent
+                    .get()
+                    .unwrap();
+                let next_vertex = &self.vertex_octtree[octtree_layer + 1][next_index];
+                let error = next_vertex.qef.borrow().error;
+                if (!error.is_nan() && error > (self.error))
+                    || (octtree_layer == self.vertex_octtree.len() - 2)
+                    || !next_vertex.is_2manifold()
+                {
+                    // Stop, if either the error is too large or we will reach the top.
+                    break;
+                }
+                octtree_layer += 1;
+                octtree_index = next_index;
+            }
+            let vertex = &self.vertex_octtree[octtree_layer][octtree_index];
+            if vertex.qef.borrow().error.is_nan() {
+                // Maybe the qef was not solved, since the error in the layer above was below the
+                // threshold. But it seems, manifold criterion has catched and we need to solve it
+                // now.
+                let mut qef = vertex.qef.borrow_mut();
+                let clamped = qef.solve(self.sampler.config.clamp_strategy, || {
+                    let mut planes = Vec::new();
+                    self.collect_tangent_planes(octtree_layer, vertex, &mut planes);
+                    planes.iter().map(|plane| plane.p).collect()
+                });
+                self.record_cell_debug_info(octtree_layer, vertex, &qef, clamped);
+                stats.solved_qefs += 1;
+                if clamped {
+                    stats.clamps += 1;
+                }
             }
+            selected_vertices.push((octtree_layer, octtree_index));
         }
-        num_solved
+        self.selected_vertices = selected_vertices;
+        self.stats.set(stats);
+    }
+
+    // The edges of edge_grid in a fixed order, so the order of vertices and faces does not depend
+    // on the iteration order of the HashMap and two tessellations yield identical meshes.
+    fn sorted_edges(&self) -> Vec<EdgeIndex> {
+        let mut edges: Vec<EdgeIndex> = self.edge_grid.borrow().keys().cloned().collect();
+        edges.sort_by_key(|e| (e.index, e.edge as usize));
+        edges
     }
 
     // Generates leaf vertices along with a map that points VertexIndices to the index in the leaf
@@ -708,7 +2382,7 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
     fn generate_leaf_vertices(&self) -> (Vec<Vertex<S>>, HashMap<VertexIndex, usize>) {
         let mut index_map = HashMap::new();
         let mut vertices = Vec::new();
-        for edge_index in self.edge_grid.borrow().keys() {
+        for edge_index in &self.sorted_edges() {
             self.add_vertices_for_minimal_egde(edge_index, &mut vertices, &mut index_map);
         }
         for vertex in &mut vertices {
@@ -756,8 +2430,12 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
     ) {
         debug_assert!((edge_index.edge as usize) < 4);
         let cell_size = na::Vector3::new(self.res, self.res, self.res);
-        for &quad_egde in &QUADS[edge_index.edge as usize] {
-            let idx = neg_offset(edge_index.index, EDGE_OFFSET[quad_egde as usize]);
+        let edge = edge_index.edge;
+        for &quad_egde in iter::once(&edge).chain(&edge.quad_neighbours()) {
+            let idx = wrap(
+                neg_offset(edge_index.index, EDGE_OFFSET[quad_egde as usize]),
+                self.period,
+            );
 
             let edge_set = get_connected_edges(quad_egde, self.bitset_for_cell(idx));
             let vertex_index = VertexIndex {
@@ -775,6 +2453,7 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
                 ];
                 for (i, neighbor) in neighbors.iter_mut().enumerate().take(6) {
                     if let Some(mut neighbor_index) = vertex_index.neighbor(i) {
+                        neighbor_index.index = wrap(neighbor_index.index, self.period);
                         for edges in get_connected_edges_from_edge_set(
                             neighbor_index.edges,
                             self.bitset_for_cell(neighbor_index.index),
@@ -823,8 +2502,19 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
     }
 
     fn get_edge_tangent_plane(&self, edge_index: &EdgeIndex) -> Plane<S> {
-        if let Some(ref plane) = self.edge_grid.borrow().get(&edge_index.base()) {
-            return **plane;
+        let base = edge_index.base();
+        let wrapped = EdgeIndex {
+            edge: base.edge,
+            index: wrap(base.index, self.period),
+        };
+        if let Some(plane) = self.edge_grid.borrow().get(&wrapped) {
+            let mut plane = *plane;
+            // Move planes of wrapped edges to the side of the period edge_index is on.
+            for i in 0..3 {
+                let delta: S = From::from(base.index[i] as f32 - wrapped.index[i] as f32);
+                plane.p[i] += delta * self.res;
+            }
+            return plane;
         }
         panic!(
             "could not find edge_point: {:?} -> {:?}",
@@ -833,157 +2523,408 @@ impl<'a, S: From<f32> + RealField + Float + AsUSize> ManifoldDualContouring<'a,
         );
     }
 
-    // Return the Point index (in self.mesh.vertices) the the point belonging to edge/idx.
-    fn lookup_cell_point(&self, edge: Edge, idx: Index) -> usize {
-        // Generate the proper vertex Index from a single edge and an Index.
-        let edge_set = get_connected_edges(edge, self.bitset_for_cell(idx));
-        let vertex_index = VertexIndex {
-            edges: edge_set,
-            index: idx,
-        };
-
-        // Convert the vertex index to index and layer in the Octtree.
-        let mut octtree_index = self.vertex_index_map[&vertex_index];
-        let mut octtree_layer = 0;
-        // Walk up the chain of parents
-        loop {
-            let next_index = self.vertex_octtree[octtree_layer][octtree_index]
-                .parent
-                .get()
-                .unwrap();
-            let next_vertex = &self.vertex_octtree[octtree_layer + 1][next_index];
-            let error = next_vertex.qef.borrow().error;
-            if (!error.is_nan() && error > (self.error))
-                || (octtree_layer == self.vertex_octtree.len() - 2)
-                || !next_vertex.is_2manifold()
-            {
-                // Stop, if either the error is too large or we will reach the top.
-                break;
-            }
-            octtree_layer += 1;
-            octtree_index = next_index;
-        }
+    // Return the index in sink of the vertex at octtree_layer and octtree_index in the Octtree.
+    fn mesh_vertex(
+        &self,
+        sink: &mut impl MeshSink<S>,
+        octtree_layer: usize,
+        octtree_index: usize,
+    ) -> usize {
         let vertex = &self.vertex_octtree[octtree_layer][octtree_index];
-        // If the vertex exists in mesh, return its index.
+        // If the vertex exists in sink, return its index.
         if let Some(mesh_index) = vertex.mesh_index.get() {
             return mesh_index;
         }
-        // If not, store it in mesh and return its index.
-        if vertex.qef.borrow().error.is_nan() {
-            // Maybe the qef was not solved, since the error in the layer above was below the
-            // threshold. But it seems, manifold criterion has catched and we need to solve it now.
-            vertex.qef.borrow_mut().solve()
-        }
-        let qef_solution = vertex.qef.borrow().solution;
-        let vertex_list = &mut self.mesh.borrow_mut().vertices;
-        let result = vertex_list.len();
+        // If not, add it to sink and return its index.
+        let mut planes = Vec::new();
+        self.collect_tangent_planes(octtree_layer, vertex, &mut planes);
+        let position = match self.vertex_placement {
+            Some(ref placement) => placement(&planes, vertex.index).coords,
+            None => vertex.qef.borrow().solution,
+        };
+        // The mean normal of all tangent planes. If they cancel out, use the normal of the
+        // function at the vertex.
+        let normal = planes
+            .iter()
+            .fold(na::Vector3::zeros(), |sum, plane| sum + plane.n)
+            .try_normalize(S::default_epsilon())
+            .unwrap_or_else(|| self.sampler.normal(&na::Point3::from(position)));
+        let result = sink.add_vertex(
+            [position.x, position.y, position.z],
+            [normal.x, normal.y, normal.z],
+        );
         vertex.mesh_index.set(Some(result));
-        vertex_list.push([qef_solution.x, qef_solution.y, qef_solution.z]);
         result
     }
 
-    fn bitset_for_cell(&self, idx: Index) -> BitSet {
-        let mut idx = idx;
-        let mut result = BitSet::zero();
-        for z in 0..2 {
-            for y in 0..2 {
-                for x in 0..2 {
-                    if let Some(&v) = self.value_grid.get(&idx) {
-                        if v < From::from(0f32) {
-                            result.set(z << 2 | y << 1 | x);
-                        }
-                    } else {
-                        panic!("did not find value_grid[{:?}]", idx);
-                    }
-                    idx[0] += 1;
+    // Collect the tangent planes of all leaf vertices below vertex in layer of the octtree.
+    fn collect_tangent_planes(&self, layer: usize, vertex: &Vertex<S>, planes: &mut Vec<Plane<S>>) {
+        if layer == 0 {
+            for (edge, &intersection) in vertex.edge_intersections.iter().enumerate() {
+                if intersection != 0 {
+                    planes.push(self.get_edge_tangent_plane(&EdgeIndex {
+                        edge: Edge::from_usize(edge),
+                        index: vertex.index,
+                    }));
                 }
-                idx[0] -= 2;
-                idx[1] += 1;
             }
-            idx[1] -= 2;
-            idx[2] += 1;
+            return;
+        }
+        for &child in &vertex.children {
+            self.collect_tangent_planes(layer - 1, &self.vertex_octtree[layer - 1][child], planes);
         }
-        result
     }
 
-    // Compute a quad for the given edge and append it to the list.
-    fn compute_quad(&self, edge_index: EdgeIndex) {
-        debug_assert!((edge_index.edge as usize) < 4);
-        debug_assert!(edge_index.index.iter().all(|&i| i > 0));
-
-        let mut p = Vec::with_capacity(4);
-        for &quad_egde in &QUADS[edge_index.edge as usize] {
-            let point_index = self.lookup_cell_point(
-                quad_egde,
-                neg_offset(edge_index.index, EDGE_OFFSET[quad_egde as usize]),
+    // Return the corner signs of the cell idx in layer of the octtree as in bitset_for_cell.
+    // Corners missing in value_grid are sampled.
+    fn corner_signs(&self, layer: usize, idx: Index) -> u32 {
+        let mut result = BitSet::zero();
+        for corner in 0..8 {
+            let grid_index = wrap(
+                [
+                    (idx[0] + (corner & 1)) << layer,
+                    (idx[1] + (corner >> 1 & 1)) << layer,
+                    (idx[2] + (corner >> 2)) << layer,
+                ],
+                self.period,
             );
-            // Dedup points before insertion (two minimal vertices might end up in the same parent
-            // vertex).
-            if !p.contains(&point_index) {
-                p.push(point_index)
+            let value = match self.value_grid.get(&grid_index) {
+                Some(&value) => value,
+                None => {
+                    let position = na::Vector3::new(
+                        From::from(grid_index[0] as f32),
+                        From::from(grid_index[1] as f32),
+                        From::from(grid_index[2] as f32),
+                    ) * self.res;
+                    self.sampler.value(&(self.origin + position))
+                }
+            };
+            if value < From::from(0f32) {
+                result.set(corner);
             }
         }
-        // Only try to generate meshes, if there are more then two points.
-        if p.len() < 3 {
-            return;
-        }
-        // Reverse order, if the edge is reversed.
-        if let Some(&v) = self.value_grid.get(&edge_index.index) {
-            if v < From::from(0f32) {
-                p.reverse();
-            }
+        result.as_u32()
+    }
+
+    fn bitset_for_cell(&self, idx: Index) -> BitSet {
+        match self.try_bitset_for_cell(idx) {
+            Some(bitset) => bitset,
+            None => panic!("did not find value_grid for cell {:?}", idx),
         }
-        let face_list = &mut self.mesh.borrow_mut().faces;
-        // TODO: Fix this to choose the proper split.
-        face_list.push([p[0], p[1], p[2]]);
-        if p.len() == 4 {
-            face_list.push([p[2], p[3], p[0]]);
+    }
+
+    // Returns None, if value_grid does not contain all corners of the cell.
+    fn try_bitset_for_cell(&self, idx: Index) -> Option<BitSet> {
+        cell_bitset(&self.value_grid, self.period, idx)
+    }
+
+    // Return the lookup of quad vertices, which can be shared between threads.
+    fn quad_lookup(&self) -> QuadLookup<'_, S> {
+        QuadLookup {
+            value_grid: &self.value_grid,
+            period: self.period,
+            vertex_index_map: &self.vertex_index_map,
+            selected_vertices: &self.selected_vertices,
         }
     }
 
-    // If a is inside the object and b outside - this method returns the point on the line between
-    // a and b where the object edge is. It also returns the normal on that point.
-    // av and bv represent the object values at a and b.
-    fn find_zero(&self, a: na::Point3<S>, av: S, b: na::Point3<S>, bv: S) -> Option<Plane<S>> {
-        assert!(a != b);
-        if Float::signum(av) == Float::signum(bv) {
-            return None;
+    /// Slice mesh with n_slices equally spaced planes perpendicular to axis, e.g. to preview the
+    /// layers of a 3d print. The planes lie at the centers of n_slices layers of equal height,
+    /// spanning the bounding box of the function. mesh is usually the result of tessellate, but
+    /// any mesh can be sliced.
+    pub fn slice_stack(&self, mesh: &Mesh<S>, axis: Axis, n_slices: usize) -> Vec<Contour<S>> {
+        let bbox = self.sampler.bbox();
+        let (min, max) = (bbox.min[axis.index()], bbox.max[axis.index()]);
+        let height = (max - min) / From::from(n_slices as f32);
+        let half: S = From::from(0.5f32);
+        (0..n_slices)
+            .map(|i| {
+                mesh.slice(
+                    axis,
+                    min + height * (<S as From<f32>>::from(i as f32) + half),
+                )
+            })
+            .collect()
+    }
+
+    /// Interpolate the value of the function at p trilinearly from the value grid of the last
+    /// tessellation, without evaluating the function. Returns None, if p is outside of the
+    /// sampling grid or if values around p were not kept. Values are only kept next to sign
+    /// changes, i.e. close to the surface.
+    pub fn recover_value_at(&self, p: na::Point3<S>) -> Option<S> {
+        let (zero, one) = (S::zero(), S::one());
+        let mut base = [0; 3];
+        let mut weights = [zero; 3];
+        for i in 0..3 {
+            let x = (p[i] - self.origin[i]) / self.res;
+            if x.is_nan() || x < zero || x > From::from(self.dim[i] as f32) {
+                return None;
+            }
+            let floor = Float::floor(x);
+            base[i] = floor.as_usize();
+            weights[i] = x - floor;
         }
-        let d = a - b;
-        let mut distance = Float::max(
-            Float::max(Float::abs(d.x), Float::abs(d.y)),
-            Float::abs(d.z),
-        );
-        distance = Float::min(Float::min(distance, Float::abs(av)), Float::abs(bv));
-        let precision: S = From::from(PRECISION);
-        if distance < precision * self.res {
-            let result = if Float::abs(bv) < Float::abs(av) {
-                &b
-            } else {
-                &a
-            };
-            return Some(Plane {
-                p: *result,
-                // We need a precise normal here.
-                n: self.function.normal(result),
-            });
+        let mut result = zero;
+        for corner in 0..8 {
+            let mut weight = one;
+            let mut idx = base;
+            for i in 0..3 {
+                if corner & (1 << i) == 0 {
+                    weight *= one - weights[i];
+                } else {
+                    weight *= weights[i];
+                    idx[i] += 1;
+                }
+            }
+            if weight != zero {
+                result += *self.value_grid.get(&wrap(idx, self.period))? * weight;
+            }
         }
-        // Linear interpolation of the zero crossing.
-        let n = a + (b - a) * (Float::abs(av) / Float::abs(bv - av));
-        let nv = self.function.value(&n);
+        Some(result)
+    }
 
-        if Float::signum(av) != Float::signum(nv) {
-            self.find_zero(a, av, n, nv)
-        } else {
-            self.find_zero(n, nv, b, bv)
+    /// Return the indexes of all cells of the last tessellation, that are intersected by the
+    /// surface, i.e. that contain a sign change. No mesh data is generated.
+    pub fn surface_cell_iterator<'b>(&'b self) -> impl Iterator<Item = Index> + 'b {
+        self.value_grid
+            .iter()
+            .map(|(idx, _)| idx)
+            .filter(
+                move |&idx| match self.try_bitset_for_cell(idx).map(BitSet::as_u32) {
+                    Some(bitset) => bitset != 0 && bitset != 255,
+                    None => false,
+                },
+            )
+    }
+
+    /// Write an OBJ file to path, containing a cube for every cell of the last tessellation, that
+    /// is intersected by the surface. The color of each cube encodes the configuration of the
+    /// cell corners. The colors are stored in a MTL file next to path.
+    /// This is useful to debug cell configurations that yield broken topology.
+    pub fn dump_debug_obj(&self, path: &Path) -> io::Result<()> {
+        let mut cells: Vec<(u32, Index)> = self
+            .surface_cell_iterator()
+            .map(|idx| (self.bitset_for_cell(idx).as_u32(), idx))
+            .collect();
+        cells.sort();
+
+        let mtl_path = path.with_extension("mtl");
+        let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+        let mut obj = BufWriter::new(File::create(path)?);
+        if let Some(mtl_name) = mtl_path.file_name() {
+            writeln!(obj, "mtllib {}", mtl_name.to_string_lossy())?;
+        }
+        let mut current_bitset = None;
+        for (i, &(bitset, idx)) in cells.iter().enumerate() {
+            if current_bitset != Some(bitset) {
+                current_bitset = Some(bitset);
+                writeln!(mtl, "newmtl cell_{}", bitset)?;
+                writeln!(
+                    mtl,
+                    "Kd {} {} {}",
+                    (bitset & 7) as f32 / 7.,
+                    (bitset >> 3 & 7) as f32 / 7.,
+                    (bitset >> 6 & 3) as f32 / 3.
+                )?;
+                writeln!(obj, "usemtl cell_{}", bitset)?;
+            }
+            for z in 0..2 {
+                for y in 0..2 {
+                    for x in 0..2 {
+                        let corner = self.origin
+                            + na::Vector3::new(
+                                From::from((idx[0] + x) as f32),
+                                From::from((idx[1] + y) as f32),
+                                From::from((idx[2] + z) as f32),
+                            ) * self.res;
+                        writeln!(obj, "v {} {} {}", corner.x, corner.y, corner.z)?;
+                    }
+                }
+            }
+            // OBJ indexes are 1-based. Corner c of cell i has index 8 * i + c + 1.
+            let base = 8 * i + 1;
+            for face in &[
+                [0, 2, 3, 1],
+                [4, 5, 7, 6],
+                [0, 1, 5, 4],
+                [2, 6, 7, 3],
+                [0, 4, 6, 2],
+                [1, 3, 7, 5],
+            ] {
+                writeln!(
+                    obj,
+                    "f {} {} {} {}",
+                    base + face[0],
+                    base + face[1],
+                    base + face[2],
+                    base + face[3]
+                )?;
+            }
+        }
+        obj.flush()?;
+        mtl.flush()
+    }
+
+    // Add the vertices of quad to sink, if they are not already, and its faces. Returns the
+    // vertex indexes of the quad, which are less than three, if no faces were added.
+    fn add_quad(&self, sink: &mut impl MeshSink<S>, quad: Quad) -> Vec<usize> {
+        let mut p = Vec::with_capacity(4);
+        for &(layer, index) in &quad.vertices {
+            let point_index = self.mesh_vertex(sink, layer, index);
+            // Dedup points before insertion (two minimal vertices might end up in the same parent
+            // vertex).
+            if !p.contains(&point_index) {
+                p.push(point_index)
+            }
+        }
+        // Only try to generate meshes, if there are more then two points.
+        if p.len() < 3 {
+            return p;
         }
+        if quad.reversed {
+            p.reverse();
+        }
+        for face in polygon_faces(&p) {
+            sink.add_face(face);
+        }
+        p
+    }
+}
+
+// Split polygon, a triangle or quad, into triangles. Returns no faces for less than three vertices.
+fn polygon_faces(polygon: &[usize]) -> Vec<[usize; 3]> {
+    let p = polygon;
+    // TODO: Fix this to choose the proper split.
+    match p.len() {
+        3 => vec![[p[0], p[1], p[2]]],
+        4 => vec![[p[0], p[1], p[2]], [p[2], p[3], p[0]]],
+        _ => Vec::new(),
+    }
+}
+
+/// Tessellation in progress, see ManifoldDualContouring::tessellate_incremental.
+/// Each call to next() adds the vertices and faces for one edge of the sampling grid to the
+/// sink and returns the faces. The faces index the vertices of the sink, e.g. of mesh().
+/// The order of the edges is reproducible, so an interrupted tessellation can be continued
+/// later from its position().
+pub struct IncrementalTessellator<'b, 'a: 'b, S: 'b + RealField, K = Mesh<S>> {
+    mdc: &'b mut ManifoldDualContouring<'a, S>,
+    edges: Vec<EdgeIndex>,
+    position: usize,
+    sink: K,
+}
+
+impl<'b, 'a, S, K> IncrementalTessellator<'b, 'a, S, K>
+where
+    S: From<f32> + RealField + Float + AsUSize,
+    K: MeshSink<S>,
+{
+    /// The number of edges processed so far. This is all state required to continue the
+    /// tessellation later.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    /// The total number of edges to process.
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+    /// Returns true, if there are no edges to process.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+    /// Continue a tessellation, that was interrupted at position. The faces of all edges before
+    /// position are regenerated and added to the sink without being returned, so vertex indexes
+    /// match those of the interrupted tessellation. See resume_from for sinks, that still hold
+    /// the vertices and faces of the interrupted tessellation.
+    pub fn skip_to(&mut self, position: usize) {
+        while self.position < position && self.next().is_some() {}
+    }
+    /// Continue a tessellation, that was interrupted at position, in a sink that already holds
+    /// the vertices and faces of the interrupted tessellation, e.g. a StreamingMeshWriter created
+    /// with StreamingMeshWriter::resume. Only the vertex numbering of the edges before position
+    /// is rebuilt, nothing is added to the sink. Returns the number of vertices of the
+    /// interrupted tessellation, so the next vertex added to the sink must get this index.
+    /// Panics, if the tessellation was already started.
+    pub fn resume_from(&mut self, position: usize) -> usize {
+        assert_eq!(self.position, 0, "tessellation was already started");
+        let mut numbering = VertexNumbering { vertex_count: 0 };
+        let end = cmp::min(position, self.edges.len());
+        for &edge_index in &self.edges[..end] {
+            let quad = self.mdc.quad_lookup().quad(edge_index);
+            self.mdc.add_quad(&mut numbering, quad);
+        }
+        self.position = end;
+        numbering.vertex_count
+    }
+    /// The sink the vertices and faces are added to.
+    pub fn sink(&self) -> &K {
+        &self.sink
+    }
+    /// Return the sink, e.g. to flush a StreamingMeshWriter after the last call to next().
+    pub fn into_sink(self) -> K {
+        self.sink
+    }
+}
+
+impl<'b, 'a, S: From<f32> + RealField + Float + AsUSize> IncrementalTessellator<'b, 'a, S> {
+    /// The mesh generated so far.
+    pub fn mesh(&self) -> Mesh<S> {
+        self.sink.clone()
+    }
+    /// Return the vertices of mesh() starting at index start, e.g. the ones added by the last
+    /// call to next(), without copying the whole mesh.
+    pub fn vertices_since(&self, start: usize) -> Vec<[S; 3]> {
+        self.sink.vertices[start..].to_vec()
+    }
+}
+
+impl<'b, 'a, S, K> Iterator for IncrementalTessellator<'b, 'a, S, K>
+where
+    S: From<f32> + RealField + Float + AsUSize,
+    K: MeshSink<S>,
+{
+    type Item = Vec<[usize; 3]>;
+    fn next(&mut self) -> Option<Vec<[usize; 3]>> {
+        let edge_index = *self.edges.get(self.position)?;
+        self.position += 1;
+        let quad = self.mdc.quad_lookup().quad(edge_index);
+        Some(polygon_faces(&self.mdc.add_quad(&mut self.sink, quad)))
+    }
+}
+
+// MeshSink, that only numbers the vertices, e.g. to rebuild the vertex indexes of an interrupted
+// tessellation.
+struct VertexNumbering {
+    vertex_count: usize,
+}
+
+impl<S> MeshSink<S> for VertexNumbering {
+    fn add_vertex(&mut self, _: [S; 3], _: [S; 3]) -> usize {
+        self.vertex_count += 1;
+        self.vertex_count - 1
     }
+    fn add_face(&mut self, _: [usize; 3]) {}
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::bitset::BitSet;
-    use super::get_connected_edges_from_edge_set;
+    use super::super::ImplicitFunction;
+    use super::{estimate_surface_cells, get_connected_edges_from_edge_set, sampling_grid};
+    use super::{
+        ClampStrategy, Edge, GridCacheError, GridStats, ManifoldDualContouring, TessellationConfig,
+        VerboseEvent, ZeroFindingMethod, QUADS,
+    };
+    use bbox::BoundingBox;
+    use contour::Axis;
+    use finite_difference;
+    use mesh::Mesh;
+    use na;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use streaming_mesh_writer::StreamingMeshWriter;
+    use test_util::Sphere;
     //  Corner indexes
     //
     //      6---------------7
@@ -1021,4 +2962,1098 @@ mod tests {
         assert!(connected_edges.contains(&BitSet::from_4bits(5, 5, 6, 10)));
         assert!(connected_edges.contains(&BitSet::from_4bits(3, 3, 4, 11)));
     }
+
+    #[test]
+    fn quad_neighbours() {
+        assert_eq!(Edge::A.quad_neighbours(), [Edge::G, Edge::J, Edge::D]);
+        assert_eq!(Edge::J.quad_neighbours(), [Edge::D, Edge::A, Edge::G]);
+        assert_eq!(Edge::F.quad_neighbours(), [Edge::C, Edge::I, Edge::L]);
+        for e in 0..12 {
+            let edge = Edge::from_usize(e);
+            let neighbours = edge.quad_neighbours();
+            let quad = &QUADS[edge.base() as usize];
+            assert!(!neighbours.contains(&edge));
+            assert!(neighbours.iter().all(|n| quad.contains(n)));
+        }
+    }
+
+    #[test]
+    fn dump_debug_obj() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        mdc.tessellate().unwrap();
+        let dir = ::std::env::temp_dir().join("tessellation_dump_debug_obj");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cells.obj");
+        mdc.dump_debug_obj(&path).unwrap();
+        let obj = fs::read_to_string(&path).unwrap();
+        let mtl = fs::read_to_string(dir.join("cells.mtl")).unwrap();
+        assert!(obj.starts_with("mtllib cells.mtl\n"));
+        let num_vertices = obj.lines().filter(|l| l.starts_with("v ")).count();
+        let num_faces = obj.lines().filter(|l| l.starts_with("f ")).count();
+        assert!(num_vertices > 0);
+        assert_eq!(num_vertices % 8, 0);
+        assert_eq!(num_faces, num_vertices / 8 * 6);
+        assert_eq!(num_vertices / 8, mdc.surface_cell_iterator().count());
+        let num_materials = mtl.lines().filter(|l| l.starts_with("newmtl ")).count();
+        let num_uses = obj.lines().filter(|l| l.starts_with("usemtl ")).count();
+        assert!(num_materials > 1);
+        assert_eq!(num_materials, num_uses);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn object_transform() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        // Stretch the sphere along x and move it to (3, 0, -2).
+        let transform = na::Matrix4::new_translation(&na::Vector3::new(3., 0., -2.))
+            * na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(2., 1., 1.));
+        mdc.set_object_transform(transform);
+        let mesh = mdc.tessellate().unwrap();
+        let mut bbox = BoundingBox::neg_infinity();
+        for v in &mesh.vertices {
+            let p = na::Point3::new((v[0] - 3.) / 2., v[1], v[2] + 2.);
+            assert!(sphere.value(&p).abs() < 0.05, "{:?}", v);
+            bbox.insert(&na::Point3::new(v[0], v[1], v[2]));
+        }
+        assert!((bbox.min.x - 1.).abs() < 0.1 && (bbox.max.x - 5.).abs() < 0.1);
+        assert!((bbox.min.z + 3.).abs() < 0.1 && (bbox.max.z + 1.).abs() < 0.1);
+    }
+
+    // Small sphere with values, that overestimate the distance to the surface twentyfold.
+    struct SteepSphere {
+        bbox: BoundingBox<f64>,
+    }
+
+    impl ImplicitFunction<f64> for SteepSphere {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            &self.bbox
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            20. * (p.coords.norm() - 0.3)
+        }
+        fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+            p.coords.normalize()
+        }
+        fn lipschitz_constant(&self) -> Option<f64> {
+            None
+        }
+    }
+
+    #[test]
+    fn unknown_lipschitz_constant() {
+        let sphere = SteepSphere {
+            bbox: BoundingBox::new(
+                &na::Point3::new(-3., -3., -3.),
+                &na::Point3::new(3., 3., 3.),
+            ),
+        };
+        assert_relative_eq!(
+            sphere.approximate_gradient_magnitude(&na::Point3::new(1., 1., 1.), 0.01),
+            20.,
+            epsilon = 1e-3
+        );
+        assert_relative_eq!(
+            sphere.locally_lipschitz_constant(&na::Point3::new(1., 0., 0.), 0.1),
+            20.,
+            epsilon = 1e-9
+        );
+        // Along the diagonal each axis only sees a part of the gradient.
+        assert_relative_eq!(
+            sphere.locally_lipschitz_constant(&na::Point3::new(1., 1., 1.), 0.01),
+            20. / 3f64.sqrt(),
+            epsilon = 1e-3
+        );
+        let mesh = ManifoldDualContouring::new(&sphere, 0.05, 0.1)
+            .tessellate()
+            .unwrap();
+        assert!(!mesh.faces.is_empty());
+        for v in &mesh.vertices {
+            let r = na::Vector3::new(v[0], v[1], v[2]).norm();
+            assert!((r - 0.3).abs() < 0.02, "{}", r);
+        }
+    }
+
+    #[test]
+    fn grid_fn() {
+        // Unit sphere given on a 31^3 grid around the origin.
+        let origin = na::Point3::new(-1.5, -1.5, -1.5);
+        let sphere = |idx: [usize; 3]| {
+            let p = origin + na::Vector3::new(idx[0] as f64, idx[1] as f64, idx[2] as f64) * 0.1;
+            p.coords.norm() - 1.
+        };
+        let mesh = ManifoldDualContouring::with_grid_fn([31, 31, 31], origin, 0.1, sphere, 0.1)
+            .tessellate()
+            .unwrap();
+        assert!(!mesh.faces.is_empty());
+        for v in &mesh.vertices {
+            let r = na::Vector3::new(v[0], v[1], v[2]).norm();
+            assert!((r - 1.).abs() < 0.02, "{}", r);
+        }
+        // Values, that are negative everywhere, yield the closed box of the grid.
+        let mesh = ManifoldDualContouring::with_grid_fn([5, 5, 5], origin, 0.1, |_| -1., 0.1)
+            .tessellate()
+            .unwrap();
+        assert_eq!(mesh.euler_characteristic(), 2);
+        for v in &mesh.vertices {
+            assert!(v.iter().all(|&x| x > -1.55 && x < -1.05), "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn vertex_normals() {
+        let sphere = Sphere::unit();
+        let mesh = ManifoldDualContouring::new(&sphere, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        assert_eq!(mesh.normals.len(), mesh.vertices.len());
+        for (v, n) in mesh.vertices.iter().zip(&mesh.normals) {
+            let v = na::Vector3::new(v[0], v[1], v[2]).normalize();
+            let n = na::Vector3::new(n[0], n[1], n[2]);
+            assert_relative_eq!(n.norm(), 1., epsilon = 1e-9);
+            assert!(v.dot(&n) > 0.99, "{:?} {:?}", v, n);
+        }
+    }
+
+    #[test]
+    fn surface_cell_iterator() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        assert_eq!(mdc.surface_cell_iterator().count(), 0);
+        mdc.tessellate().unwrap();
+        let mut num_cells = 0;
+        for idx in mdc.surface_cell_iterator() {
+            num_cells += 1;
+            let bitset = mdc.bitset_for_cell(idx).as_u32();
+            assert!(bitset != 0 && bitset != 255);
+            // Surface cells must be close to the surface.
+            let center = mdc.origin
+                + na::Vector3::new(
+                    idx[0] as f64 + 0.5,
+                    idx[1] as f64 + 0.5,
+                    idx[2] as f64 + 0.5,
+                ) * mdc.res;
+            assert!(sphere.value(&center).abs() < mdc.res);
+        }
+        assert!(num_cells > 0);
+    }
+
+    #[test]
+    fn recover_value_at() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        let p = na::Point3::new(0.3, 0.8, 0.6);
+        assert_eq!(mdc.recover_value_at(p), None);
+        mdc.tessellate().unwrap();
+        // All values around cells with a sign change are kept.
+        let cells: Vec<_> = mdc.surface_cell_iterator().collect();
+        assert!(!cells.is_empty());
+        for idx in cells {
+            let p = mdc.origin
+                + na::Vector3::new(
+                    idx[0] as f64 + 0.5,
+                    idx[1] as f64 + 0.25,
+                    idx[2] as f64 + 0.75,
+                ) * mdc.res;
+            let recovered = mdc.recover_value_at(p).unwrap();
+            assert_relative_eq!(recovered, sphere.value(&p), epsilon = 0.01);
+        }
+        // Values away from the surface are not kept.
+        assert_eq!(mdc.recover_value_at(na::Point3::new(0., 0., 0.)), None);
+        assert_eq!(mdc.recover_value_at(na::Point3::new(5., 0., 0.)), None);
+    }
+
+    #[test]
+    fn slice_stack() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        let mesh = mdc.tessellate().unwrap();
+        let stack = mdc.slice_stack(&mesh, Axis::Y, 4);
+        assert_eq!(stack.len(), 4);
+        for (contour, &offset) in stack.iter().zip(&[-0.75, -0.25, 0.25, 0.75]) {
+            assert_eq!(contour.axis, Axis::Y);
+            assert_relative_eq!(contour.offset, offset, epsilon = 1e-6);
+            assert_eq!(contour.polylines.len(), 1);
+            let radius = (1f64 - offset * offset).sqrt();
+            let circumference = 2. * ::std::f64::consts::PI * radius;
+            assert!((contour.length() - circumference).abs() < 0.1 * circumference);
+        }
+        // Meshes of the other tessellation methods can be sliced as well.
+        let half_edge = mdc.tessellate_half_edge().unwrap().to_mesh();
+        assert_eq!(mdc.slice_stack(&half_edge, Axis::Y, 4), stack);
+    }
+
+    // Half space x < x0, whose samples at x0 are zero. If inside_on_zero, is_inside counts them
+    // as inside.
+    struct HalfSpace {
+        bbox: BoundingBox<f64>,
+        x0: f64,
+        inside_on_zero: bool,
+    }
+
+    impl ImplicitFunction<f64> for HalfSpace {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            &self.bbox
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            p.x - self.x0
+        }
+        fn normal(&self, _: &na::Point3<f64>) -> na::Vector3<f64> {
+            na::Vector3::x()
+        }
+        fn is_inside(&self, p: &na::Point3<f64>) -> bool {
+            if self.inside_on_zero {
+                self.value(p) <= 0.
+            } else {
+                self.value(p) < 0.
+            }
+        }
+    }
+
+    #[test]
+    fn is_inside_classifies_zeros() {
+        let bbox = Sphere::unit().bbox;
+        // Samples on the side of the grid are at x0.
+        let (origin, _) = sampling_grid(&bbox, 0.1);
+        for &inside_on_zero in &[false, true] {
+            let half_space = HalfSpace {
+                bbox: bbox.clone(),
+                x0: origin.x,
+                inside_on_zero,
+            };
+            let mut mdc = ManifoldDualContouring::new(&half_space, 0.1, 0.1);
+            assert!(mdc.tessellation_step1().is_none());
+            let value = *mdc.value_grid.get(&[0, 0, 0]).unwrap();
+            assert_eq!(value < 0., inside_on_zero, "{}", value);
+        }
+    }
+
+    #[test]
+    fn report_grid_stats() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        mdc.tessellate().unwrap();
+        let stats = mdc.report_grid_stats();
+        assert_eq!(stats.total_cells, mdc.dim[0] * mdc.dim[1] * mdc.dim[2]);
+        // The cells crossed by the surface cover more than the surface area of the sphere
+        // divided by the area of a cell.
+        let area_cells = 4. * ::std::f64::consts::PI / 0.01;
+        let surface_cells = stats.surface_cells as f64;
+        assert!(surface_cells > area_cells && surface_cells < 2. * area_cells);
+        assert_relative_eq!(
+            stats.surface_fraction,
+            stats.surface_cells as f64 / stats.total_cells as f64,
+            epsilon = 1e-6
+        );
+        assert!(stats.estimated_triangle_count > stats.surface_cells);
+        assert!(!stats.is_suspicious());
+        let dense = GridStats {
+            surface_fraction: 0.6,
+            ..stats
+        };
+        assert!(dense.is_suspicious());
+        // In a much larger bounding box the surface passes through few cells.
+        let large_sphere = Sphere {
+            bbox: BoundingBox::new(
+                &na::Point3::new(-10., -10., -10.),
+                &na::Point3::new(10., 10., 10.),
+            ),
+            ..Sphere::unit()
+        };
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let mut mdc = ManifoldDualContouring::new(&large_sphere, 0.1, 0.1);
+        mdc.set_verbose_sink(Box::new(move |event| sink.lock().unwrap().push(event)));
+        mdc.tessellate().unwrap();
+        let stats = mdc.report_grid_stats();
+        assert!(stats.is_suspicious(), "{:?}", stats);
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&VerboseEvent::GridStatsWarning { stats }));
+    }
+
+    #[test]
+    fn verbose_sink() {
+        let sphere = Sphere::unit();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        mdc.set_verbose_sink(Box::new(move |event| sink.lock().unwrap().push(event)));
+        let mesh = mdc.tessellate().unwrap();
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            VerboseEvent::Start {
+                res: 0.2,
+                bbox: sphere.bbox.clone()
+            }
+        );
+        assert_eq!(
+            events.last(),
+            Some(&VerboseEvent::MeshStats {
+                face_count: mesh.faces.len()
+            })
+        );
+        let edge_count = events
+            .iter()
+            .filter_map(|event| match *event {
+                VerboseEvent::EdgeGridStats { edge_count, .. } => Some(edge_count),
+                _ => None,
+            })
+            .next();
+        let quad_count = events
+            .iter()
+            .filter_map(|event| match *event {
+                VerboseEvent::QuadStats { quad_count, .. } => Some(quad_count),
+                _ => None,
+            })
+            .next();
+        assert_eq!(edge_count, quad_count);
+        assert!(quad_count.unwrap() > 0);
+        assert!(events[1..]
+            .iter()
+            .any(|event| event.to_string().starts_with("generated value_grid with")));
+    }
+
+    #[test]
+    fn vertex_placement() {
+        let sphere = Sphere::unit();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mesh = ManifoldDualContouring::new(&sphere, 0.2, 0.1)
+            .vertex_placement(move |planes, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let sum = planes
+                    .iter()
+                    .fold(na::Vector3::zeros(), |sum, plane| sum + plane.p.coords);
+                na::Point3::from(sum / planes.len() as f64)
+            })
+            .tessellate()
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), mesh.vertices.len());
+        // Centroids of points on a convex surface lie slightly inside. The edge intersections
+        // are found with a precision of 0.05 * res.
+        for v in &mesh.vertices {
+            let r = na::Vector3::new(v[0], v[1], v[2]).norm();
+            assert!(r <= 1.01 && r > 0.8, "{}", r);
+        }
+    }
+
+    #[test]
+    fn tessellate_incremental() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let expected = mdc.clone().tessellate().unwrap();
+        let mut tessellator = mdc.tessellate_incremental();
+        let num_edges = tessellator.len();
+        let mut faces: Vec<[usize; 3]> =
+            tessellator.by_ref().take(num_edges / 2).flatten().collect();
+        let position = tessellator.position();
+        assert_eq!(position, num_edges / 2);
+        // Continue from position with a fresh tessellation.
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let mut tessellator = mdc.tessellate_incremental();
+        tessellator.skip_to(position);
+        assert_eq!(tessellator.mesh().faces, faces);
+        faces.extend(tessellator.by_ref().flatten());
+        assert_eq!(tessellator.position(), num_edges);
+        let mesh = tessellator.mesh();
+        assert_eq!(mesh.faces, faces);
+        assert_eq!(mesh.faces.len(), expected.faces.len());
+        assert_eq!(mesh.vertices.len(), expected.vertices.len());
+    }
+
+    #[test]
+    fn resume_streaming() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let mut tessellator = mdc.tessellate_incremental_into(StreamingMeshWriter::new(Vec::new()));
+        for _ in tessellator.by_ref() {}
+        let expected = tessellator.into_sink().into_inner();
+        // Interrupt a tessellation half way.
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let mut tessellator = mdc.tessellate_incremental_into(StreamingMeshWriter::new(Vec::new()));
+        let position = tessellator.len() / 2;
+        for _ in tessellator.by_ref().take(position) {}
+        let interrupted = tessellator.into_sink();
+        let vertex_count = interrupted.vertex_count();
+        let (normal_count, face_count) = (interrupted.normal_count(), interrupted.face_count());
+        let prefix = interrupted.into_inner();
+        assert!(face_count > 0 && prefix.len() < expected.len());
+        // Continue with a fresh tessellation, appending to the interrupted output.
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let writer = StreamingMeshWriter::resume(prefix, vertex_count, normal_count, face_count);
+        let mut tessellator = mdc.tessellate_incremental_into(writer);
+        assert_eq!(tessellator.resume_from(position), vertex_count);
+        assert_eq!(tessellator.position(), position);
+        for _ in tessellator.by_ref() {}
+        let mut writer = tessellator.into_sink();
+        writer.flush().unwrap();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn reproducible_output() {
+        let sphere = Sphere::unit();
+        let write = |mesh: &Mesh<f64>| {
+            let mut out = Vec::new();
+            mesh.write_obj(&mut out).unwrap();
+            out
+        };
+        let mesh = ManifoldDualContouring::new(&sphere, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        let again = ManifoldDualContouring::new(&sphere, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        assert_eq!(write(&mesh), write(&again));
+        // Incremental tessellation visits the edges in the same order.
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        let mut tessellator = mdc.tessellate_incremental();
+        while tessellator.next().is_some() {}
+        assert_eq!(write(&tessellator.mesh()), write(&mesh));
+    }
+
+    #[test]
+    fn tessellate_half_edge() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let expected = mdc.clone().tessellate().unwrap();
+        let mesh = mdc.tessellate_half_edge().unwrap();
+        assert!(!expected.faces.is_empty());
+        let converted = mesh.to_mesh();
+        assert_eq!(converted.faces, expected.faces);
+        assert_eq!(converted.vertices.len(), expected.vertices.len());
+        for (v, w) in converted.vertices.iter().zip(&expected.vertices) {
+            assert_relative_eq!(v[..], w[..], epsilon = 1e-9);
+        }
+        assert_eq!(mesh.normals().len(), expected.normals.len());
+        for (n, m) in mesh.normals().iter().zip(&expected.normals) {
+            assert_relative_eq!(n[..], m[..], epsilon = 1e-9);
+        }
+        for f in 0..mesh.num_faces() {
+            for &g in &mesh.adjacent_faces(f) {
+                assert!(mesh.adjacent_faces(g).contains(&f));
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_obj() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let expected = mdc.clone().tessellate().unwrap();
+        let mut tessellator = mdc.tessellate_incremental_into(StreamingMeshWriter::new(Vec::new()));
+        let faces: Vec<[usize; 3]> = tessellator.by_ref().flatten().collect();
+        let mut writer = tessellator.into_sink();
+        writer.flush().unwrap();
+        let obj = writer.into_inner();
+        let read = Mesh::<f64>::read_obj(&mut &obj[..]).unwrap();
+        assert_eq!(read.faces, faces);
+        assert_eq!(read.faces, expected.faces);
+        assert_eq!(read.vertices.len(), expected.vertices.len());
+        assert_eq!(read.normals.len(), expected.normals.len());
+        for (n, m) in read.normals.iter().zip(&expected.normals) {
+            assert_relative_eq!(n[..], m[..], epsilon = 1e-9);
+        }
+    }
+
+    // Schwarz P minimal surface, which is periodic with period 2 in each dimension. Scaled to be
+    // Lipschitz continuous with constant 1.
+    struct SchwarzP {
+        bbox: BoundingBox<f64>,
+    }
+
+    impl ImplicitFunction<f64> for SchwarzP {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            &self.bbox
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            let pi = ::std::f64::consts::PI;
+            p.coords.iter().map(|&c| (pi * c).cos()).sum::<f64>() / (pi * 3f64.sqrt())
+        }
+        fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+            let pi = ::std::f64::consts::PI;
+            -p.coords.map(|c| (pi * c).sin()).normalize()
+        }
+    }
+
+    #[test]
+    fn periodic() {
+        let surface = SchwarzP {
+            bbox: BoundingBox::new(
+                &na::Point3::new(-1., -1., -1.),
+                &na::Point3::new(1., 1., 1.),
+            ),
+        };
+        let mesh = ManifoldDualContouring::new_periodic(&surface, [2., 2., 2.], 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        // The surface is closed, i.e. each edge is shared by exactly two faces.
+        let mut edges = ::std::collections::HashMap::new();
+        for f in &mesh.faces {
+            for i in 0..3 {
+                let e = (f[i].min(f[(i + 1) % 3]), f[i].max(f[(i + 1) % 3]));
+                *edges.entry(e).or_insert(0) += 1;
+            }
+        }
+        assert!(edges.values().all(|&n| n == 2));
+        // The Schwarz P surface has genus 3 in the 3-torus.
+        let euler = mesh.vertices.len() as i64 - edges.len() as i64 + mesh.faces.len() as i64;
+        assert_eq!(euler, -4);
+        for v in &mesh.vertices {
+            assert!(v.iter().all(|c| (-1. ..=1.).contains(c)));
+            assert!(surface.value(&na::Point3::new(v[0], v[1], v[2])).abs() < 0.05);
+        }
+    }
+
+    // Sphere, that counts how often it was evaluated.
+    struct CountingSphere {
+        sphere: Sphere,
+        evaluations: AtomicUsize,
+    }
+
+    impl CountingSphere {
+        fn new(sphere: Sphere) -> CountingSphere {
+            CountingSphere {
+                sphere,
+                evaluations: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl ImplicitFunction<f64> for CountingSphere {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            self.sphere.bbox()
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            self.evaluations.fetch_add(1, Ordering::SeqCst);
+            self.sphere.value(p)
+        }
+        fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+            self.sphere.normal(p)
+        }
+    }
+
+    #[test]
+    fn zero_finding_methods() {
+        let sphere = CountingSphere::new(Sphere::unit());
+        // The sphere is convex along this edge, which makes plain regula falsi converge slowly.
+        let a = na::Point3::new(0., 0.9, 0.);
+        let b = na::Point3::new(1., 0.9, 0.);
+        let mut evaluations = Vec::new();
+        for &method in &[
+            ZeroFindingMethod::Bisection,
+            ZeroFindingMethod::RegulaFalsi,
+            ZeroFindingMethod::Illinois,
+        ] {
+            let mdc = ManifoldDualContouring::new(&sphere, 0.01, 0.1).zero_finding_method(method);
+            sphere.evaluations.store(0, Ordering::SeqCst);
+            let plane = mdc
+                .sampler
+                .find_zero(a, sphere.value(&a), b, sphere.value(&b))
+                .unwrap();
+            evaluations.push(sphere.evaluations.load(Ordering::SeqCst) - 2);
+            assert!((plane.p.coords.norm() - 1.).abs() < 0.05 * 0.01);
+        }
+        // Evaluations of bisection, regula falsi and illinois.
+        assert!(evaluations[2] < evaluations[0], "{:?}", evaluations);
+        assert!(evaluations[2] < evaluations[1], "{:?}", evaluations);
+    }
+
+    // Axis aligned cube, that counts how often it was evaluated.
+    struct CountingCube {
+        bbox: BoundingBox<f64>,
+        evaluations: AtomicUsize,
+    }
+
+    impl CountingCube {
+        fn new() -> CountingCube {
+            CountingCube {
+                bbox: BoundingBox::new(
+                    &na::Point3::new(-1., -1., -1.),
+                    &na::Point3::new(1., 1., 1.),
+                ),
+                evaluations: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl ImplicitFunction<f64> for CountingCube {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            &self.bbox
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            self.evaluations.fetch_add(1, Ordering::SeqCst);
+            let q = p.coords.map(|c| c.abs() - 1.);
+            q.map(|c| c.max(0.)).norm() + q.x.max(q.y.max(q.z)).min(0.)
+        }
+        fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+            finite_difference::normal(|p| self.value(p), p, 1e-6)
+        }
+    }
+
+    // Unit sphere, that counts calls of evaluate_batch.
+    struct BatchSphere {
+        sphere: Sphere,
+        batches: AtomicUsize,
+    }
+
+    impl ImplicitFunction<f64> for BatchSphere {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            self.sphere.bbox()
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            self.sphere.value(p)
+        }
+        fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+            self.sphere.normal(p)
+        }
+        fn evaluate_batch(&self, points: &[na::Point3<f64>]) -> Vec<f64> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            points.iter().map(|p| self.value(p)).collect()
+        }
+    }
+
+    #[test]
+    fn evaluate_batch() {
+        let batch_sphere = BatchSphere {
+            sphere: Sphere::unit(),
+            batches: AtomicUsize::new(0),
+        };
+        let points = [na::Point3::new(0., 0., 0.), na::Point3::new(2., 0., 0.)];
+        assert_eq!(batch_sphere.sphere.evaluate_batch(&points), vec![-1., 1.]);
+        let batched = ManifoldDualContouring::new(&batch_sphere, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        assert!(batch_sphere.batches.load(Ordering::SeqCst) > 0);
+        let sphere = Sphere::unit();
+        let mesh = ManifoldDualContouring::new(&sphere, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        assert_eq!(batched.vertices, mesh.vertices);
+        assert_eq!(batched.faces, mesh.faces);
+    }
+
+    #[test]
+    fn tessellate_with_feature_lines() {
+        let cube = CountingCube::new();
+        let mut mdc = ManifoldDualContouring::new(&cube, 0.1, 0.1);
+        let (mesh, features) = mdc.tessellate_with_feature_lines().unwrap();
+        // The cube simplifies to 8 vertices, so each of its 12 edges is one feature line.
+        assert_eq!(features.len(), 12);
+        let mut edges = Vec::new();
+        for f in &mesh.faces {
+            for i in 0..3 {
+                let (a, b) = (f[i], f[(i + 1) % 3]);
+                edges.push((a.min(b), a.max(b)));
+            }
+        }
+        for &(a, b) in &features {
+            assert!(edges.contains(&(a, b)));
+            // Both vertices are on the edges of the cube.
+            for &v in &[a, b] {
+                let on_side = mesh.vertices[v]
+                    .iter()
+                    .filter(|c| (c.abs() - 1.).abs() < 0.02)
+                    .count();
+                assert!(on_side >= 2, "{:?}", mesh.vertices[v]);
+            }
+        }
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        let (mesh, features) = mdc.tessellate_with_feature_lines().unwrap();
+        assert!(!mesh.faces.is_empty());
+        assert!(features.is_empty());
+    }
+
+    #[test]
+    fn curvature_adaptive() {
+        let uniform_cube = CountingCube::new();
+        let uniform_mesh = ManifoldDualContouring::new(&uniform_cube, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        let adaptive_cube = CountingCube::new();
+        let adaptive_mesh =
+            ManifoldDualContouring::new_curvature_adaptive(&adaptive_cube, 0.1, 0.8, 0.1)
+                .tessellate()
+                .unwrap();
+        assert!(
+            adaptive_cube.evaluations.load(Ordering::SeqCst)
+                < uniform_cube.evaluations.load(Ordering::SeqCst),
+            "adaptive: {} uniform: {}",
+            adaptive_cube.evaluations.load(Ordering::SeqCst),
+            uniform_cube.evaluations.load(Ordering::SeqCst)
+        );
+        assert!(!adaptive_mesh.faces.is_empty());
+        for v in &adaptive_mesh.vertices {
+            let distance = adaptive_cube.value(&na::Point3::new(v[0], v[1], v[2]));
+            assert!(distance.abs() < 0.05, "{:?} is {} off", v, distance);
+        }
+        assert_eq!(adaptive_mesh.faces.len(), uniform_mesh.faces.len());
+    }
+
+    #[test]
+    fn tessellation_config() {
+        let cube = CountingCube::new();
+        for &strategy in &[
+            ClampStrategy::BinarySearch,
+            ClampStrategy::MeanOfPlanes,
+            ClampStrategy::ClampToBounds,
+            ClampStrategy::NearestEdgeCrossing,
+        ] {
+            let config = TessellationConfig::new().clamp_strategy(strategy);
+            let (mesh, stats) = ManifoldDualContouring::with_config(&cube, 0.1, 0.1, config)
+                .tessellate_with_stats()
+                .unwrap();
+            assert!(!mesh.faces.is_empty());
+            assert!(stats.solved_qefs >= mesh.vertices.len());
+            assert!(stats.clamps <= stats.solved_qefs);
+            for v in &mesh.vertices {
+                let distance = cube.value(&na::Point3::new(v[0], v[1], v[2]));
+                assert!(
+                    distance.abs() < 0.1,
+                    "{:?}: {:?} is {} off",
+                    strategy,
+                    v,
+                    distance
+                );
+            }
+        }
+        // Without any steps, find_zero returns the end of the edge closer to the surface.
+        let sphere = Sphere::unit();
+        let config = TessellationConfig::new().max_find_zero_depth(0);
+        let mdc = ManifoldDualContouring::with_config(&sphere, 0.1, 0.1, config);
+        let a = na::Point3::new(0., 0.9, 0.);
+        let b = na::Point3::new(1., 0.9, 0.);
+        let plane = mdc
+            .sampler
+            .find_zero(a, sphere.value(&a), b, sphere.value(&b))
+            .unwrap();
+        assert_eq!(plane.p, a);
+    }
+
+    #[test]
+    fn precompute_all_qef_solutions() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        let mesh = mdc.tessellate().unwrap();
+        assert_eq!(mdc.selected_vertices.len(), mdc.vertex_octtree[0].len());
+        for &(layer, index) in &mdc.selected_vertices {
+            let vertex = &mdc.vertex_octtree[layer][index];
+            assert!(!vertex.qef.borrow().error.is_nan());
+            assert!(vertex.mesh_index.get().unwrap() < mesh.vertices.len());
+        }
+    }
+
+    #[test]
+    fn export_cell_debug_info() {
+        let sphere = Sphere::unit();
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        mdc.tessellate().unwrap();
+        assert!(mdc.export_cell_debug_info().is_empty());
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.2, 0.1).debug_cells(true);
+        let (_, stats) = mdc.tessellate_with_stats().unwrap();
+        let cells = mdc.export_cell_debug_info();
+        assert_eq!(cells.len(), stats.solved_qefs);
+        assert_eq!(
+            cells.iter().filter(|cell| cell.was_clamped).count(),
+            stats.clamps
+        );
+        for cell in cells.iter().filter(|cell| cell.layer == 0) {
+            // Leaf cells contain the surface.
+            assert_eq!(cell.bitset, mdc.bitset_for_cell(cell.index).as_u32());
+            assert_ne!(cell.bitset, 0);
+            assert_ne!(cell.bitset, 0xff);
+            assert!(sphere.value(&cell.vertex_pos).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn near_surface_refinement() {
+        let sphere = Sphere::unit();
+        let max_error = |mesh: &Mesh<f64>| {
+            mesh.vertices
+                .iter()
+                .map(|v| sphere.value(&na::Point3::new(v[0], v[1], v[2])).abs())
+                .fold(0., f64::max)
+        };
+        let coarse = ManifoldDualContouring::new(&sphere, 0.2, 0.1)
+            .tessellate()
+            .unwrap();
+        let config = TessellationConfig::new().near_surface_refinement_factor(2);
+        let refined = ManifoldDualContouring::with_config(&sphere, 0.2, 0.1, config)
+            .tessellate()
+            .unwrap();
+        let fine = ManifoldDualContouring::new(&sphere, 0.1, 0.1)
+            .tessellate()
+            .unwrap();
+        // Refined vertices are more accurate than coarse ones, but simplified more than fine
+        // ones, since the error threshold stays relative to the coarse resolution.
+        assert!(max_error(&refined) < max_error(&coarse));
+        assert!(coarse.vertices.len() < refined.vertices.len());
+        assert!(refined.vertices.len() < fine.vertices.len());
+        // Compared to sampling the fine grid everywhere with the same error threshold, cells of
+        // the coarse grid away from the surface are interpolated from their corners.
+        let counting = CountingSphere::new(Sphere::unit());
+        let uniform = ManifoldDualContouring::new(&counting, 0.1, 0.2)
+            .tessellate()
+            .unwrap();
+        let uniform_evaluations = counting.evaluations.swap(0, Ordering::SeqCst);
+        let config = TessellationConfig::new().near_surface_refinement_factor(4);
+        let mut refined = ManifoldDualContouring::with_config(&counting, 0.4, 0.05, config);
+        let refined_mesh = refined.tessellate().unwrap();
+        let refined_evaluations = counting.evaluations.load(Ordering::SeqCst);
+        assert!(refined.adaptive_grid.as_ref().unwrap().len() > 0);
+        assert!(
+            refined_evaluations < uniform_evaluations,
+            "refined: {} uniform: {}",
+            refined_evaluations,
+            uniform_evaluations
+        );
+        assert!(max_error(&refined_mesh) < 0.05);
+        let ratio = refined_mesh.vertices.len() as f64 / uniform.vertices.len() as f64;
+        assert!((ratio - 1.).abs() < 0.1, "{}", ratio);
+    }
+
+    #[test]
+    fn near_surface_refinement_far_corner() {
+        // A small sphere near the far corner of a coarse cell, whose first corner is more than a
+        // coarse cell away from the surface.
+        let (res, factor) = (0.4, 8);
+        let fine_res = res / factor as f64;
+        let bbox = Sphere::new(na::Point3::origin(), 0.5).bbox;
+        let (origin, _) = sampling_grid(&bbox, fine_res);
+        let offset = (-origin.x / res).floor() * res;
+        let cell = origin + na::Vector3::new(offset, offset, offset);
+        let center = cell + na::Vector3::new(0.8, 0.8, 0.8) * res;
+        let radius = 0.15 * res;
+        assert!(na::distance(&cell, &center) - radius > res);
+        let counting = CountingSphere::new(Sphere {
+            center,
+            radius,
+            bbox,
+        });
+        let config = TessellationConfig::new().near_surface_refinement_factor(factor);
+        let refined = ManifoldDualContouring::with_config(&counting, res, 0.1, config)
+            .tessellate()
+            .unwrap();
+        let refined_evaluations = counting.evaluations.swap(0, Ordering::SeqCst);
+        let uniform = ManifoldDualContouring::new(&counting, fine_res, 0.1 * factor as f64)
+            .tessellate()
+            .unwrap();
+        let uniform_evaluations = counting.evaluations.load(Ordering::SeqCst);
+        // The cell containing the sphere is subdivided, all others are interpolated.
+        assert!(!refined.faces.is_empty());
+        assert_eq!(refined.vertices.len(), uniform.vertices.len());
+        for v in &refined.vertices {
+            assert!(
+                counting
+                    .sphere
+                    .value(&na::Point3::new(v[0], v[1], v[2]))
+                    .abs()
+                    < fine_res
+            );
+        }
+        assert!(
+            refined_evaluations < uniform_evaluations,
+            "refined: {} uniform: {}",
+            refined_evaluations,
+            uniform_evaluations
+        );
+    }
+
+    #[test]
+    fn narrow_band() {
+        let sphere = Sphere::unit();
+        let mut full = ManifoldDualContouring::new(&sphere, 0.05, 0.1);
+        assert!(full.tessellation_step1().is_none());
+        let mut banded = ManifoldDualContouring::new(&sphere, 0.05, 0.1).with_narrow_band(0.);
+        assert!(banded.tessellation_step1().is_none());
+        assert!(banded.value_grid.len() < full.value_grid.len() / 2);
+        // All values, that survive compaction, are within the band. Except for values on the
+        // sides of the grid, which compaction always keeps.
+        full.compact_value_grid();
+        banded.compact_value_grid();
+        let inner = |mdc: &ManifoldDualContouring<f64>| {
+            let mut keys: Vec<[usize; 3]> = mdc
+                .value_grid
+                .iter()
+                .map(|(idx, _)| idx)
+                .filter(|idx| !idx.contains(&0))
+                .collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(inner(&banded), inner(&full));
+        let mesh = ManifoldDualContouring::new(&sphere, 0.05, 0.1)
+            .tessellate()
+            .unwrap();
+        let banded_mesh = ManifoldDualContouring::new(&sphere, 0.05, 0.1)
+            .with_narrow_band(0.)
+            .tessellate()
+            .unwrap();
+        assert_eq!(banded_mesh.faces.len(), mesh.faces.len());
+        assert_eq!(banded_mesh.vertices.len(), mesh.vertices.len());
+    }
+
+    #[test]
+    fn value_grid_cache() {
+        let sphere = CountingSphere::new(Sphere::unit());
+        let mut mdc = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        let mesh = mdc.tessellate().unwrap();
+        let sampled_evaluations = sphere.evaluations.swap(0, Ordering::SeqCst);
+        let mut cache = Vec::new();
+        mdc.save_value_grid(&mut cache).unwrap();
+
+        let mut cached = ManifoldDualContouring::new(&sphere, 0.1, 0.1);
+        cached.load_value_grid(&mut &cache[..]).unwrap();
+        let cached_mesh = cached.tessellate().unwrap();
+        // Only zero crossings and normals are evaluated.
+        assert!(sphere.evaluations.load(Ordering::SeqCst) < sampled_evaluations);
+        assert_eq!(cached_mesh.vertices.len(), mesh.vertices.len());
+        assert_eq!(cached_mesh.faces.len(), mesh.faces.len());
+        let mut saved_again = Vec::new();
+        cached.save_value_grid(&mut saved_again).unwrap();
+        assert_eq!(saved_again, cache);
+
+        let mut coarse = ManifoldDualContouring::new(&sphere, 0.2, 0.1);
+        match coarse.load_value_grid(&mut &cache[..]) {
+            Err(GridCacheError::DimensionMismatch(_)) => {}
+            r => panic!("expected DimensionMismatch, got {:?}", r),
+        }
+        cache[4] += 1;
+        match cached.load_value_grid(&mut &cache[..]) {
+            Err(GridCacheError::InvalidHeader(_)) => {}
+            r => panic!("expected InvalidHeader, got {:?}", r),
+        }
+        cache[4] -= 1;
+        cache.pop();
+        match cached.load_value_grid(&mut &cache[..]) {
+            Err(GridCacheError::Io(_)) => {}
+            r => panic!("expected Io, got {:?}", r),
+        }
+        // Values outside of the sampled grid are rejected.
+        let header = 5 + 8 * 8;
+        let outside_x = cached.dim[0].next_power_of_two() as u32 + 1;
+        let mut outside = cache.clone();
+        outside[header..header + 4].copy_from_slice(&outside_x.to_le_bytes());
+        match cached.load_value_grid(&mut &outside[..]) {
+            Err(GridCacheError::DimensionMismatch(_)) => {}
+            r => panic!("expected DimensionMismatch, got {:?}", r),
+        }
+        // Grids with indexes beyond u32 are rejected before anything is written.
+        cached.dim[0] = u32::MAX as usize + 1;
+        let mut oversized = Vec::new();
+        assert!(cached.save_value_grid(&mut oversized).is_err());
+        assert!(oversized.is_empty());
+    }
+
+    #[test]
+    fn adaptive() {
+        let cube = CountingCube::new();
+        let uniform = ManifoldDualContouring::new(&cube, 0.05, 0.1)
+            .tessellate()
+            .unwrap();
+        let uniform_evaluations = cube.evaluations.swap(0, Ordering::SeqCst);
+        let adaptive = ManifoldDualContouring::new_adaptive(&cube, 0.05, 0.2, 0.1)
+            .tessellate()
+            .unwrap();
+        let adaptive_evaluations = cube.evaluations.load(Ordering::SeqCst);
+        // Flat regions are interpolated from coarse samples.
+        assert!(
+            adaptive_evaluations < uniform_evaluations,
+            "adaptive: {} uniform: {}",
+            adaptive_evaluations,
+            uniform_evaluations
+        );
+        // Bounding the cluster size only adds faces.
+        assert!(adaptive.faces.len() >= uniform.faces.len());
+        assert_eq!(adaptive.euler_characteristic(), 2);
+        for v in &adaptive.vertices {
+            let distance = cube.value(&na::Point3::new(v[0], v[1], v[2]));
+            assert!(distance.abs() < 0.05, "{:?} is {} off", v, distance);
+        }
+        // Faces span at most two of the coarsest cells, while unbounded clustering collapses the
+        // sides of the cube.
+        let (_, _, max) = adaptive.edge_length_statistics();
+        assert!(max < 2. * 0.2 * 3f64.sqrt(), "{}", max);
+        assert!(uniform.edge_length_statistics().2 > 1.);
+    }
+
+    #[test]
+    fn parallel_tessellate() {
+        let a = Sphere::unit();
+        let b = Sphere::unit().translate(na::Vector3::new(5., 0., 0.));
+        let c = Sphere::unit().scale_uniform(0.5);
+        let functions: Vec<&(dyn ImplicitFunction<f64> + Sync)> = vec![&a, &b, &c];
+        let meshes = ManifoldDualContouring::parallel_tessellate(&functions, 0.1, 0.1);
+        assert_eq!(meshes.len(), 3);
+        for (mesh, &(center, radius)) in meshes.iter().zip(&[(0., 1.), (5., 1.), (0., 0.5)]) {
+            let mesh = mesh.as_ref().unwrap();
+            assert!(!mesh.faces.is_empty());
+            for v in &mesh.vertices {
+                let r = na::Vector3::new(v[0] - center, v[1], v[2]).norm();
+                assert!((r - radius).abs() < 0.05, "{}", r);
+            }
+        }
+    }
+
+    #[test]
+    fn measure_sdf_lipschitz() {
+        // Return the measured Lipschitz constant of f and whether a warning was emitted.
+        fn measure(f: &(dyn ImplicitFunction<f64> + Sync)) -> (f64, bool) {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink = events.clone();
+            let mut mdc = ManifoldDualContouring::new(f, 0.1, 0.1);
+            mdc.set_verbose_sink(Box::new(move |event| sink.lock().unwrap().push(event)));
+            let lipschitz = mdc.measure_sdf_lipschitz();
+            let warned = events.lock().unwrap().iter().any(|event| match *event {
+                VerboseEvent::LipschitzWarning { measured, expected } => {
+                    assert_eq!((measured, expected), (lipschitz, 1.));
+                    true
+                }
+                _ => false,
+            });
+            (lipschitz, warned)
+        }
+        let sphere = Sphere::unit();
+        let (lipschitz, warned) = measure(&sphere);
+        assert!(lipschitz <= 1. + 1e-9 && lipschitz > 0.95, "{}", lipschitz);
+        assert!(!warned);
+        let steep = SteepSphere {
+            bbox: sphere.bbox.clone(),
+        };
+        let (lipschitz, warned) = measure(&steep);
+        assert!(lipschitz <= 20. + 1e-9 && lipschitz > 19., "{}", lipschitz);
+        assert!(warned);
+    }
+
+    #[test]
+    fn estimate_memory_for_resolution() {
+        let sphere = Sphere::unit();
+        for &res in &[0.1, 0.05] {
+            let mut mdc = ManifoldDualContouring::new(&sphere, res, 0.1);
+            mdc.tessellate().unwrap();
+            let estimate = estimate_surface_cells(&sphere, res) as f64;
+            let actual = mdc.vertex_index_map.len() as f64;
+            assert!(
+                estimate > 0.5 * actual && estimate < 2. * actual,
+                "{} {}",
+                estimate,
+                actual
+            );
+        }
+        let coarse = ManifoldDualContouring::estimate_memory_for_resolution(&sphere, 0.1);
+        let fine = ManifoldDualContouring::estimate_memory_for_resolution(&sphere, 0.05);
+        assert!(coarse > 0);
+        assert!(
+            fine > 3 * coarse && fine < 5 * coarse,
+            "{} {}",
+            coarse,
+            fine
+        );
+    }
 }
@@ -0,0 +1,111 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use lru::LruCache;
+use na;
+use num_traits::{Float, ToPrimitive};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+/// Wrapper caching the values of an expensive implicit function.
+/// Points are rounded to a grid with a spacing of a quarter of the resolution. The function is
+/// evaluated on the grid points, so all points rounded to the same grid point share one value.
+pub struct MemoizedObject<S: Debug + RealField, O: ImplicitFunction<S>> {
+    inner: O,
+    spacing: S,
+    cache: Arc<Mutex<LruCache<[i64; 3], S>>>,
+}
+
+impl<S: Debug + RealField + Float + From<f32>, O: ImplicitFunction<S>> MemoizedObject<S, O> {
+    /// Create a new MemoizedObject.
+    /// inner: the function to cache
+    /// res: the resolution the function will be tessellated with
+    /// capacity: the maximum number of cached values
+    pub fn new(inner: O, res: S, capacity: usize) -> MemoizedObject<S, O> {
+        MemoizedObject {
+            inner,
+            spacing: res / From::from(4f32),
+            cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+    /// The wrapped function.
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>, O: ImplicitFunction<S>> ImplicitFunction<S>
+    for MemoizedObject<S, O>
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        self.inner.bbox()
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        let grid = p.coords.map(|c| Float::round(c / self.spacing));
+        let key = [
+            ToPrimitive::to_i64(&grid.x).unwrap(),
+            ToPrimitive::to_i64(&grid.y).unwrap(),
+            ToPrimitive::to_i64(&grid.z).unwrap(),
+        ];
+        if let Some(&value) = self.cache.lock().unwrap().get(&key) {
+            return value;
+        }
+        // Do not hold the lock while evaluating inner.
+        let value = self.inner.value(&na::Point3::from(grid * self.spacing));
+        self.cache.lock().unwrap().put(key, value);
+        value
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        self.inner.normal(p)
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        self.inner.lipschitz_constant()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Plane x = 0, that counts how often it was evaluated.
+    struct CountingPlane {
+        bbox: BoundingBox<f64>,
+        evaluations: Cell<usize>,
+    }
+
+    impl ImplicitFunction<f64> for CountingPlane {
+        fn bbox(&self) -> &BoundingBox<f64> {
+            &self.bbox
+        }
+        fn value(&self, p: &na::Point3<f64>) -> f64 {
+            self.evaluations.set(self.evaluations.get() + 1);
+            p.x
+        }
+        fn normal(&self, _: &na::Point3<f64>) -> na::Vector3<f64> {
+            na::Vector3::new(1., 0., 0.)
+        }
+    }
+
+    #[test]
+    fn memoize() {
+        let one = na::Point3::new(1., 1., 1.);
+        let plane = CountingPlane {
+            bbox: BoundingBox::new(&-one, &one),
+            evaluations: Cell::new(0),
+        };
+        let memoized = MemoizedObject::new(plane, 0.4, 2);
+        // Both points round to the grid point (0.3, 0, 0).
+        assert_relative_eq!(memoized.value(&na::Point3::new(0.32, 0., 0.)), 0.3);
+        assert_relative_eq!(memoized.value(&na::Point3::new(0.28, 0.01, 0.)), 0.3);
+        assert_eq!(memoized.inner().evaluations.get(), 1);
+        memoized.value(&na::Point3::new(0.5, 0., 0.));
+        memoized.value(&na::Point3::new(0.7, 0., 0.));
+        assert_eq!(memoized.inner().evaluations.get(), 3);
+        // The first value was evicted.
+        memoized.value(&na::Point3::new(0.3, 0., 0.));
+        assert_eq!(memoized.inner().evaluations.get(), 4);
+        assert_eq!(memoized.normal(&one), na::Vector3::new(1., 0., 0.));
+        assert_eq!(memoized.bbox(), &BoundingBox::new(&-one, &one));
+    }
+}
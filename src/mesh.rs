@@ -1,6 +1,23 @@
 use alga::general::RealField;
+use bbox::BoundingBox;
+use bvh::Bvh;
+use contour::{Axis, Contour};
+use convex_decomposition;
+use decimate;
 use na;
-use std::fmt::Debug;
+use num_traits::{Float, NumCast};
+use plane::Plane;
+use rand;
+use rand::Rng;
+use rayon::prelude::*;
+use std::cmp;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+use std::error;
+use std::fmt::{self, Debug};
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::path::Path;
+use ImplicitFunction;
 
 /// Mesh that will be returned from tessellate.
 #[derive(Clone, Debug, PartialEq)]
@@ -9,6 +26,165 @@ pub struct Mesh<S> {
     pub vertices: Vec<[S; 3]>,
     /// The list of triangles as indexes into vertices.
     pub faces: Vec<[usize; 3]>,
+    /// The normal of each vertex, if known (e.g. from the tessellated function). Empty
+    /// otherwise, see compute_vertex_normals.
+    pub normals: Vec<[S; 3]>,
+}
+
+/// Receiver of the vertices and faces of a tessellation, while they are generated, e.g. a Mesh,
+/// a HalfEdgeMesh or a StreamingMeshWriter.
+pub trait MeshSink<S> {
+    /// Add a vertex at position with normal and return its index. Vertices are numbered in the
+    /// order they are added.
+    fn add_vertex(&mut self, position: [S; 3], normal: [S; 3]) -> usize;
+    /// Add the triangle face of vertex indexes.
+    fn add_face(&mut self, face: [usize; 3]);
+}
+
+impl<S> MeshSink<S> for Mesh<S> {
+    fn add_vertex(&mut self, position: [S; 3], normal: [S; 3]) -> usize {
+        self.vertices.push(position);
+        self.normals.push(normal);
+        self.vertices.len() - 1
+    }
+    fn add_face(&mut self, face: [usize; 3]) {
+        self.faces.push(face);
+    }
+}
+
+// Maximum number of steps to shrink a ball in medial_axis_approximation.
+const MAX_SHRINKING_BALL_ITERATIONS: usize = 30;
+// Minimal angle (in radians) between the touching points of a ball in
+// medial_axis_approximation.
+const MIN_SEPARATION_ANGLE: f32 = ::std::f32::consts::PI / 6.;
+
+// Half the opening angle (in radians) of the cone of rays in compute_shape_diameter_function.
+const SDF_CONE_HALF_ANGLE: f32 = ::std::f32::consts::PI / 3.;
+
+// Number of points sampled from each mesh to approximate the Hausdorff distance.
+const HAUSDORFF_SAMPLES: usize = 10000;
+
+// Number of bisection steps to clip vertices of offset_mesh.
+const OFFSET_CLIP_ITERATIONS: usize = 32;
+
+// Distance of the points in front of and behind faces, that check_and_fix_flipped_normals
+// evaluates, relative to the mean edge length of the face.
+const FLIP_CHECK_OFFSET: f32 = 0.25;
+
+// compute_thickness_field bisects the radius of the inscribed spheres in this many steps, with a
+// tolerance relative to half the diagonal of the bounding box.
+const THICKNESS_BISECTION_STEPS: usize = 40;
+const THICKNESS_TOLERANCE: f32 = 1e-4;
+
+// uv_from_boundary_parameterization iterates, until no vertex moves more than the tolerance, but
+// at most the maximum number of iterations.
+const PARAMETERIZATION_TOLERANCE: f32 = 1e-6;
+const MAX_PARAMETERIZATION_ITERATIONS: usize = 10000;
+
+// Phong material written by export_wavefront_mtl: the ambient color relative to the diffuse
+// color, the gray specular color and the specular exponent.
+const MTL_AMBIENT: f32 = 0.2;
+const MTL_SPECULAR: f32 = 0.5;
+const MTL_SHININESS: f32 = 32.;
+
+// Vertex reached by compute_geodesic_voronoi from the seed with index label. Ordered by
+// reversed distance, so a BinaryHeap pops the closest vertex first.
+struct Reached<S> {
+    distance: S,
+    vertex: usize,
+    label: usize,
+}
+
+impl<S: PartialOrd> PartialEq for Reached<S> {
+    fn eq(&self, other: &Reached<S>) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl<S: PartialOrd> Eq for Reached<S> {}
+
+impl<S: PartialOrd> PartialOrd for Reached<S> {
+    fn partial_cmp(&self, other: &Reached<S>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: PartialOrd> Ord for Reached<S> {
+    fn cmp(&self, other: &Reached<S>) -> cmp::Ordering {
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(cmp::Ordering::Equal)
+            .then_with(|| (other.label, other.vertex).cmp(&(self.label, self.vertex)))
+    }
+}
+
+/// Difference between two meshes, as returned by Mesh::compare.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshDiff<S> {
+    /// The largest distance a vertex moved.
+    pub max_vertex_displacement: S,
+    /// The mean distance the vertices moved.
+    pub mean_vertex_displacement: S,
+    /// Number of faces in the other mesh minus the number of faces in this mesh.
+    pub face_count_delta: i64,
+    /// True, if vertex count or faces differ between both meshes.
+    pub topology_changed: bool,
+}
+
+/// Difference in topology between two meshes, as returned by Mesh::diff_topology. All deltas
+/// are the value of the other mesh minus the value of this mesh.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TopologyDiff {
+    /// Difference in the number of vertices.
+    pub vertex_count_delta: i64,
+    /// Difference in the number of faces.
+    pub face_count_delta: i64,
+    /// Difference in genus (see Mesh::genus), or None, if either mesh has no genus.
+    pub genus_delta: Option<i64>,
+    /// Difference in the number of edges, that are adjacent to only one face.
+    pub boundary_edge_count_delta: i64,
+}
+
+/// Error returned by Mesh::check_genus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopologyError {
+    /// The mesh has edges, that are not shared by exactly two faces, so it has no genus.
+    NotClosed,
+    /// The Euler characteristic of the closed mesh is odd, e.g. because the mesh is not
+    /// orientable, so it has no genus.
+    OddEulerCharacteristic(i64),
+    /// The genus of the mesh differs from the expected one.
+    UnexpectedGenus {
+        /// The genus of the mesh, which is negative for meshes with several components.
+        actual: i64,
+        /// The expected genus.
+        expected: usize,
+    },
+}
+
+impl error::Error for TopologyError {
+    fn description(&self) -> &str {
+        match *self {
+            TopologyError::NotClosed => "Mesh is not closed.",
+            TopologyError::OddEulerCharacteristic(_) => "Odd Euler characteristic.",
+            TopologyError::UnexpectedGenus { .. } => "Unexpected genus.",
+        }
+    }
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TopologyError::NotClosed => write!(f, "Mesh is not closed"),
+            TopologyError::OddEulerCharacteristic(chi) => {
+                write!(f, "Odd Euler characteristic: {}", chi)
+            }
+            TopologyError::UnexpectedGenus { actual, expected } => {
+                write!(f, "Unexpected genus: {} instead of {}", actual, expected)
+            }
+        }
+    }
 }
 
 impl<S: 'static + RealField + Debug> Mesh<S> {
@@ -42,21 +218,3693 @@ impl<S: 'static + RealField + Debug> Mesh<S> {
         );
         [v.0 as f32, v.1 as f32, v.2 as f32]
     }
+    /// Return the faces as general polygons of vertex indexes, as expected by polygon mesh
+    /// libraries like OpenMesh or CGAL.
+    pub fn to_polygon_list(&self) -> Vec<Vec<usize>> {
+        self.faces.iter().map(|f| f.to_vec()).collect()
+    }
+    /// Return the vertices as points along with the faces as polygon list (see to_polygon_list).
+    pub fn to_open_mesh(&self) -> (Vec<na::Point3<S>>, Vec<Vec<usize>>) {
+        let points = self
+            .vertices
+            .iter()
+            .map(|v| na::Point3::new(v[0], v[1], v[2]))
+            .collect();
+        (points, self.to_polygon_list())
+    }
+    // Return the number of faces adjacent to each undirected edge.
+    fn edge_face_counts(&self) -> HashMap<(usize, usize), usize> {
+        let mut result = HashMap::new();
+        for face in &self.faces {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                *result.entry((cmp::min(a, b), cmp::max(a, b))).or_insert(0) += 1;
+            }
+        }
+        result
+    }
+    /// Split the mesh into one mesh per material, e.g. for renderers, that require a single
+    /// material per mesh. face_materials contains the material index of each face. The meshes
+    /// are ordered by material index and only contain the vertices of their faces.
+    /// Panics, if face_materials does not have one entry per face.
+    pub fn separate_by_material(&self, face_materials: &[usize]) -> Vec<Mesh<S>> {
+        assert_eq!(face_materials.len(), self.faces.len());
+        let mut parts: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (f, &material) in face_materials.iter().enumerate() {
+            parts.entry(material).or_default().push(f);
+        }
+        parts
+            .values()
+            .map(|part| convex_decomposition::part_mesh(self, part))
+            .collect()
+    }
+    /// Merge meshes with their material index into one mesh, along with the material index of
+    /// each face. This reverses separate_by_material. Normals are only kept, if all meshes have
+    /// normals.
+    pub fn merge_by_material(meshes: &[(Mesh<S>, usize)]) -> (Mesh<S>, Vec<usize>) {
+        let keep_normals = meshes.iter().all(|(mesh, _)| !mesh.normals.is_empty());
+        let mut result = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        let mut face_materials = Vec::new();
+        for &(ref mesh, material) in meshes {
+            let offset = result.vertices.len();
+            result.vertices.extend_from_slice(&mesh.vertices);
+            if keep_normals {
+                result.normals.extend_from_slice(&mesh.normals);
+            }
+            result.faces.extend(
+                mesh.faces
+                    .iter()
+                    .map(|f| [f[0] + offset, f[1] + offset, f[2] + offset]),
+            );
+            face_materials.resize(face_materials.len() + mesh.faces.len(), material);
+        }
+        (result, face_materials)
+    }
+    /// Return the Euler characteristic V - E + F of the mesh.
+    pub fn euler_characteristic(&self) -> i64 {
+        self.vertices.len() as i64 - self.edge_face_counts().len() as i64 + self.faces.len() as i64
+    }
+    /// Return the genus (2 - euler_characteristic) / 2, i.e. the number of handles of a closed,
+    /// connected mesh. Returns None for meshes without genus, i.e. if the mesh is not closed or
+    /// its Euler characteristic is odd.
+    pub fn genus(&self) -> Option<i64> {
+        self.try_genus().ok()
+    }
+    // Return the genus or why the mesh has none.
+    fn try_genus(&self) -> Result<i64, TopologyError> {
+        if !self.is_closed() {
+            return Err(TopologyError::NotClosed);
+        }
+        let chi = self.euler_characteristic();
+        if chi % 2 != 0 {
+            return Err(TopologyError::OddEulerCharacteristic(chi));
+        }
+        Ok((2 - chi) / 2)
+    }
+    /// Check that the genus of the mesh is expected_genus, e.g. 0 for a sphere or 1 for a torus.
+    /// Fails for meshes without genus, i.e. if the mesh is not closed or its Euler
+    /// characteristic is odd.
+    pub fn check_genus(&self, expected_genus: usize) -> Result<(), TopologyError> {
+        let actual = self.try_genus()?;
+        if actual == expected_genus as i64 {
+            Ok(())
+        } else {
+            Err(TopologyError::UnexpectedGenus {
+                actual,
+                expected: expected_genus,
+            })
+        }
+    }
+    /// Returns true, if every edge is shared by exactly two faces, i.e. if the mesh is
+    /// watertight and has no non-manifold edges.
+    pub fn is_closed(&self) -> bool {
+        self.edge_face_counts().values().all(|&n| n == 2)
+    }
+    /// Return the loops of boundary edges, i.e. edges with only one face, as lists of vertex
+    /// indexes. Each loop starts and ends at the same vertex and follows the orientation of its
+    /// faces. Only if faces are oriented inconsistently, open chains of boundary edges may be
+    /// returned. Returns no loops for closed meshes.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let counts = self.edge_face_counts();
+        // Boundary edges in the direction of their face, sorted for a deterministic result.
+        let mut next: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut starts = Vec::new();
+        for face in &self.faces {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                if counts[&(cmp::min(a, b), cmp::max(a, b))] == 1 {
+                    next.entry(a).or_default().push(b);
+                    starts.push(a);
+                }
+            }
+        }
+        for targets in next.values_mut() {
+            targets.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        starts.sort_unstable();
+        let mut loops = Vec::new();
+        for start in starts {
+            let mut boundary_loop = vec![start];
+            let mut v = start;
+            // Vertices may have several outgoing boundary edges, which are followed by later
+            // loops.
+            while let Some(w) = next.get_mut(&v).and_then(|targets| targets.pop()) {
+                boundary_loop.push(w);
+                v = w;
+                if v == start {
+                    break;
+                }
+            }
+            if boundary_loop.len() > 1 {
+                loops.push(boundary_loop);
+            }
+        }
+        loops
+    }
+    /// Compare the topology of this mesh to other, e.g. to detect unintended changes of the
+    /// tessellation in regression tests.
+    pub fn diff_topology(&self, other: &Mesh<S>) -> TopologyDiff {
+        let boundary_edges =
+            |m: &Mesh<S>| m.edge_face_counts().values().filter(|&&n| n == 1).count() as i64;
+        TopologyDiff {
+            vertex_count_delta: other.vertices.len() as i64 - self.vertices.len() as i64,
+            face_count_delta: other.faces.len() as i64 - self.faces.len() as i64,
+            genus_delta: other.genus().and_then(|g| self.genus().map(|h| g - h)),
+            boundary_edge_count_delta: boundary_edges(other) - boundary_edges(self),
+        }
+    }
+    /// Split edges shared by more than two faces: Faces are grouped into components, that are
+    /// connected via manifold edges, and vertices of non-manifold edges are duplicated for each
+    /// component but the first. Returns the repaired mesh and the number of non-manifold edges.
+    pub fn repair_non_manifold_edges(&self) -> (Mesh<S>, usize) {
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                edge_faces
+                    .entry((cmp::min(a, b), cmp::max(a, b)))
+                    .or_default()
+                    .push(f);
+            }
+        }
+        let mut split = vec![false; self.vertices.len()];
+        let mut repaired = 0;
+        for (&(a, b), faces) in &edge_faces {
+            if faces.len() > 2 {
+                split[a] = true;
+                split[b] = true;
+                repaired += 1;
+            }
+        }
+        let mut result = self.clone();
+        if repaired == 0 {
+            return (result, 0);
+        }
+        // Flood fill the faces across manifold edges.
+        let mut component = vec![usize::MAX; self.faces.len()];
+        let mut num_components = 0;
+        for start in 0..self.faces.len() {
+            if component[start] != usize::MAX {
+                continue;
+            }
+            component[start] = num_components;
+            let mut queue = VecDeque::new();
+            queue.push_back(start);
+            while let Some(f) = queue.pop_front() {
+                let face = self.faces[f];
+                for i in 0..3 {
+                    let (a, b) = (face[i], face[(i + 1) % 3]);
+                    let neighbors = &edge_faces[&(cmp::min(a, b), cmp::max(a, b))];
+                    if neighbors.len() != 2 {
+                        continue;
+                    }
+                    for &g in neighbors {
+                        if component[g] == usize::MAX {
+                            component[g] = num_components;
+                            queue.push_back(g);
+                        }
+                    }
+                }
+            }
+            num_components += 1;
+        }
+        // The component keeping the original of each split vertex.
+        let mut owner = HashMap::new();
+        let mut copies = HashMap::new();
+        for (f, face) in result.faces.iter_mut().enumerate() {
+            for v in face.iter_mut() {
+                if !split[*v] || *owner.entry(*v).or_insert(component[f]) == component[f] {
+                    continue;
+                }
+                let vertices = &mut result.vertices;
+                let normals = &mut result.normals;
+                let original = *v;
+                *v = *copies.entry((original, component[f])).or_insert_with(|| {
+                    vertices.push(self.vertices[original]);
+                    if !self.normals.is_empty() {
+                        normals.push(self.normals[original]);
+                    }
+                    vertices.len() - 1
+                });
+            }
+        }
+        (result, repaired)
+    }
+    /// Split vertices, whose adjacent faces form several fans, that are not connected via edges
+    /// of the vertex (pinch points). Each fan but the first gets its own copy of the vertex.
+    /// Returns the repaired mesh and the number of split vertices.
+    pub fn repair_non_manifold_vertices(&self) -> (Mesh<S>, usize) {
+        let mut vertex_faces = vec![Vec::new(); self.vertices.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in face {
+                vertex_faces[v].push(f);
+            }
+        }
+        let mut result = self.clone();
+        let mut split = 0;
+        for (v, faces) in vertex_faces.iter().enumerate() {
+            // Flood fill the faces around v across edges of v.
+            let mut fan = vec![usize::MAX; faces.len()];
+            let mut num_fans = 0;
+            for start in 0..faces.len() {
+                if fan[start] != usize::MAX {
+                    continue;
+                }
+                fan[start] = num_fans;
+                let mut queue = VecDeque::new();
+                queue.push_back(start);
+                while let Some(i) = queue.pop_front() {
+                    let face = self.faces[faces[i]];
+                    for j in 0..faces.len() {
+                        let shares_edge = self.faces[faces[j]]
+                            .iter()
+                            .any(|&w| w != v && face.contains(&w));
+                        if fan[j] == usize::MAX && shares_edge {
+                            fan[j] = num_fans;
+                            queue.push_back(j);
+                        }
+                    }
+                }
+                num_fans += 1;
+            }
+            if num_fans < 2 {
+                continue;
+            }
+            split += 1;
+            let first_copy = result.vertices.len();
+            for _ in 1..num_fans {
+                result.vertices.push(self.vertices[v]);
+                if !self.normals.is_empty() {
+                    result.normals.push(self.normals[v]);
+                }
+            }
+            for (&f, &fan) in faces.iter().zip(&fan) {
+                if fan > 0 {
+                    for w in result.faces[f].iter_mut().filter(|w| **w == v) {
+                        *w = first_copy + fan - 1;
+                    }
+                }
+            }
+        }
+        (result, split)
+    }
+    /// Panic, if two faces traverse a shared edge in the same direction, i.e. if adjacent faces
+    /// have inconsistent winding order. This is meant to be used in tests.
+    pub fn assert_consistent_winding(&self) {
+        let mut directed_edges = HashMap::new();
+        for (face_index, face) in self.faces.iter().enumerate() {
+            for i in 0..3 {
+                let edge = (face[i], face[(i + 1) % 3]);
+                if let Some(other) = directed_edges.insert(edge, face_index) {
+                    panic!(
+                        "inconsistent winding: faces {} {:?} and {} {:?} both traverse edge {} -> {}",
+                        other, self.faces[other], face_index, face, edge.0, edge.1
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<S: 'static + RealField + Float + From<f32> + Debug> Mesh<S> {
+    // Return the vertex at index i as point.
+    fn point(&self, i: usize) -> na::Point3<S> {
+        let v = &self.vertices[i];
+        na::Point3::new(v[0], v[1], v[2])
+    }
+    // Return the three corners of the face at index face.
+    fn face_points(&self, face: usize) -> [na::Point3<S>; 3] {
+        let f = &self.faces[face];
+        [self.point(f[0]), self.point(f[1]), self.point(f[2])]
+    }
+    /// Return the area of the face at index face.
+    pub fn face_area(&self, face: usize) -> S {
+        let [a, b, c] = self.face_points(face);
+        let half: S = From::from(0.5f32);
+        (b - a).cross(&(c - a)).norm() * half
+    }
+    /// Sample n points uniformly distributed over the surface of the mesh.
+    /// Returns each point along with the index of the face it lies on.
+    /// Returns an empty Vec if the mesh has no area.
+    pub fn random_sample_points(&self, n: usize) -> Vec<(na::Point3<S>, usize)> {
+        let mut total = From::from(0f32);
+        let cumulative_areas: Vec<S> = (0..self.faces.len())
+            .map(|face| {
+                total += self.face_area(face);
+                total
+            })
+            .collect();
+        let zero: S = From::from(0f32);
+        if total <= zero {
+            return Vec::new();
+        }
+        let one: S = From::from(1f32);
+        let mut rng = rand::thread_rng();
+        (0..n)
+            .map(|_| {
+                let r: S = From::from(rng.gen::<f32>());
+                let target = r * total;
+                // Find the first face whose cumulative area exceeds target.
+                let face =
+                    match cumulative_areas.binary_search_by(|a| a.partial_cmp(&target).unwrap()) {
+                        Ok(i) => i + 1,
+                        Err(i) => i,
+                    };
+                let face = cmp::min(face, self.faces.len() - 1);
+                let [a, b, c] = self.face_points(face);
+                let r1 = Float::sqrt(From::from(rng.gen::<f32>()));
+                let r2: S = From::from(rng.gen::<f32>());
+                let p = a.coords * (one - r1) + b.coords * (r1 * (one - r2)) + c.coords * (r1 * r2);
+                (na::Point3::from(p), face)
+            })
+            .collect()
+    }
+    /// Return the signed volume enclosed by the mesh, computed with the divergence theorem
+    /// relative to the origin. It is positive for closed meshes with outward facing faces.
+    pub fn volume(&self) -> S {
+        self.signed_volume_relative_to(&na::Point3::origin())
+    }
+    /// Return the same as volume, but computed relative to the centroid of the vertices. This
+    /// avoids cancellation for meshes far from the origin.
+    pub fn signed_volume_from_centroid(&self) -> S {
+        if self.vertices.is_empty() {
+            return From::from(0f32);
+        }
+        self.signed_volume_relative_to(&self.vertex_centroid())
+    }
+    /// Returns true, if the mesh is closed and its volume is within tolerance of
+    /// expected_volume.
+    pub fn check_watertight_volume(&self, expected_volume: S, tolerance: S) -> bool {
+        self.is_closed() && Float::abs(self.volume() - expected_volume) <= tolerance
+    }
+    /// Panic, if check_watertight_volume fails. The message includes the actual volume. This is
+    /// meant to be used in tests.
+    pub fn assert_watertight_volume(&self, expected: S, tol: S) {
+        assert!(self.is_closed(), "mesh is not closed");
+        let volume = self.volume();
+        assert!(
+            Float::abs(volume - expected) <= tol,
+            "volume {:?} differs from expected volume {:?} by more than {:?}",
+            volume,
+            expected,
+            tol
+        );
+    }
+    /// Return the center of mass of the volume enclosed by the mesh, assuming uniform density.
+    /// Returns the centroid of the vertices for meshes without volume.
+    pub fn compute_center_of_mass(&self) -> na::Point3<S> {
+        if self.vertices.is_empty() {
+            return na::Point3::origin();
+        }
+        let center = self.vertex_centroid();
+        let (volume, first_moment, _) = self.volume_moments(&center);
+        if volume == S::zero() {
+            return center;
+        }
+        center + first_moment / volume
+    }
+    /// Return the inertia tensor of the volume enclosed by the mesh with uniform density, relative
+    /// to its center of mass (see compute_center_of_mass) and along the axes of the mesh, e.g. for
+    /// rigid body simulation.
+    pub fn compute_inertia_tensor(&self, density: S) -> na::Matrix3<S> {
+        if self.vertices.is_empty() {
+            return na::Matrix3::zeros();
+        }
+        let center = self.vertex_centroid();
+        let (volume, first_moment, second_moment) = self.volume_moments(&center);
+        // Move the second moment to the center of mass (parallel axis theorem).
+        let covariance = if volume == S::zero() {
+            second_moment
+        } else {
+            second_moment - first_moment * first_moment.transpose() / volume
+        };
+        (na::Matrix3::identity() * covariance.trace() - covariance) * density
+    }
+    // Return volume, first moment (the integral of p - center) and second moment (the integral of
+    // (p - center) * (p - center)^T) of the volume enclosed by the mesh, computed with the
+    // divergence theorem from the tetrahedra spanned by each face and center.
+    fn volume_moments(&self, center: &na::Point3<S>) -> (S, na::Vector3<S>, na::Matrix3<S>) {
+        let (six, twenty_four, hundred_twenty): (S, S, S) =
+            (From::from(6f32), From::from(24f32), From::from(120f32));
+        let mut volume = S::zero();
+        let mut first_moment = na::Vector3::zeros();
+        let mut second_moment = na::Matrix3::zeros();
+        for f in 0..self.faces.len() {
+            let [a, b, c] = self.face_points(f);
+            let (a, b, c) = (a - center, b - center, c - center);
+            let det = a.dot(&b.cross(&c));
+            let sum = a + b + c;
+            volume += det / six;
+            first_moment += sum * (det / twenty_four);
+            second_moment +=
+                (sum * sum.transpose() + a * a.transpose() + b * b.transpose() + c * c.transpose())
+                    * (det / hundred_twenty);
+        }
+        (volume, first_moment, second_moment)
+    }
+    // Return the mean of all vertices. Panics for meshes without vertices.
+    fn vertex_centroid(&self) -> na::Point3<S> {
+        let sum = (0..self.vertices.len())
+            .fold(na::Vector3::zeros(), |sum, i| sum + self.point(i).coords);
+        let n: S = From::from(self.vertices.len() as f32);
+        na::Point3::from(sum / n)
+    }
+    /// Return the principal axes of the vertices, i.e. the eigenvectors of the covariance matrix
+    /// of their positions ordered by decreasing eigenvalue, and their centroid. The axes form a
+    /// right handed orthonormal basis, e.g. for oriented bounding boxes or to align meshes.
+    /// Returns the coordinate axes and the origin for meshes without vertices.
+    pub fn compute_principal_axes(
+        &self,
+    ) -> (
+        na::Vector3<S>,
+        na::Vector3<S>,
+        na::Vector3<S>,
+        na::Point3<S>,
+    ) {
+        if self.vertices.is_empty() {
+            return (
+                na::Vector3::x(),
+                na::Vector3::y(),
+                na::Vector3::z(),
+                na::Point3::origin(),
+            );
+        }
+        let centroid = self.vertex_centroid();
+        let covariance = (0..self.vertices.len()).fold(na::Matrix3::zeros(), |sum, i| {
+            let d = self.point(i) - centroid;
+            sum + d * d.transpose()
+        });
+        let eigen = na::SymmetricEigen::new(covariance);
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| {
+            eigen.eigenvalues[b]
+                .partial_cmp(&eigen.eigenvalues[a])
+                .unwrap_or(cmp::Ordering::Equal)
+        });
+        let first: na::Vector3<S> = eigen.eigenvectors.column(order[0]).normalize();
+        let second: na::Vector3<S> = eigen.eigenvectors.column(order[1]).normalize();
+        (first, second, first.cross(&second), centroid)
+    }
+    /// Fit a plane to the vertices: The plane passes through their centroid, its normal is the
+    /// principal axis of least variance (see compute_principal_axes). Also returns the root mean
+    /// square distance of the vertices to the plane.
+    pub fn fit_plane(&self) -> (Plane<S>, S) {
+        let (_, _, n, p) = self.compute_principal_axes();
+        let sum = (0..self.vertices.len()).fold(S::zero(), |sum, i| {
+            let d = (self.point(i) - p).dot(&n);
+            sum + d * d
+        });
+        let rms = if self.vertices.is_empty() {
+            S::zero()
+        } else {
+            Float::sqrt(sum / From::from(self.vertices.len() as f32))
+        };
+        (Plane { p, n }, rms)
+    }
+    /// Project the vertices onto plane and return their 2d coordinates relative to plane.p, e.g.
+    /// to draw cross sections. The y axis of the 2d frame is the projection of the coordinate
+    /// axis least parallel to plane.n, the x axis is perpendicular to it, so x, y and plane.n
+    /// form a right-handed frame.
+    pub fn project_onto_plane(&self, plane: Plane<S>) -> Vec<[S; 2]> {
+        let n = plane.n.normalize();
+        let threshold: S = From::from(0.5f32);
+        let axis = if Float::abs(n.x) < threshold {
+            na::Vector3::x()
+        } else if Float::abs(n.y) < threshold {
+            na::Vector3::y()
+        } else {
+            na::Vector3::z()
+        };
+        let up = (axis - n * n.dot(&axis)).normalize();
+        let right = up.cross(&n);
+        (0..self.vertices.len())
+            .map(|i| {
+                let d = self.point(i) - plane.p;
+                [d.dot(&right), d.dot(&up)]
+            })
+            .collect()
+    }
+    /// Return a copy of the mesh rotated, so its principal axes (see compute_principal_axes)
+    /// align with the x, y and z axes, and moved, so its centroid is at the origin.
+    pub fn align_to_axes(&self) -> Mesh<S> {
+        let (a, b, c, centroid) = self.compute_principal_axes();
+        let rotate = |v: na::Vector3<S>| [a.dot(&v), b.dot(&v), c.dot(&v)];
+        Mesh {
+            vertices: (0..self.vertices.len())
+                .map(|i| rotate(self.point(i) - centroid))
+                .collect(),
+            faces: self.faces.clone(),
+            normals: self
+                .normals
+                .iter()
+                .map(|n| rotate(na::Vector3::new(n[0], n[1], n[2])))
+                .collect(),
+        }
+    }
+    // Sum of the signed volumes of the tetrahedra spanned by each face and center.
+    fn signed_volume_relative_to(&self, center: &na::Point3<S>) -> S {
+        let six: S = From::from(6f32);
+        (0..self.faces.len()).fold(S::zero(), |sum, f| {
+            let [a, b, c] = self.face_points(f);
+            let (a, b, c) = (a - center, b - center, c - center);
+            sum + a.dot(&b.cross(&c)) / six
+        })
+    }
+    // Return all edges as sorted vertex pairs along with their lengths.
+    fn edge_lengths(&self) -> Vec<((usize, usize), S)> {
+        let mut edges: Vec<(usize, usize)> = self.edge_face_counts().keys().cloned().collect();
+        edges.sort();
+        edges
+            .into_iter()
+            .map(|(a, b)| ((a, b), na::distance(&self.point(a), &self.point(b))))
+            .collect()
+    }
+    /// Partition the vertices into geodesic Voronoi regions: Each vertex is labeled with the index
+    /// (into seeds) of the seed closest to it along the edges of the mesh. Vertices, that are not
+    /// connected to any seed, are labeled usize::MAX.
+    pub fn compute_geodesic_voronoi(&self, seeds: &[usize]) -> Vec<usize> {
+        let mut neighbors = vec![Vec::new(); self.vertices.len()];
+        for ((a, b), length) in self.edge_lengths() {
+            neighbors[a].push((b, length));
+            neighbors[b].push((a, length));
+        }
+        let mut labels = vec![usize::MAX; self.vertices.len()];
+        // Dijkstra's algorithm starting from all seeds at once.
+        let mut heap: BinaryHeap<Reached<S>> = seeds
+            .iter()
+            .enumerate()
+            .map(|(label, &vertex)| Reached {
+                distance: S::zero(),
+                vertex,
+                label,
+            })
+            .collect();
+        let mut distances = vec![S::infinity(); self.vertices.len()];
+        while let Some(Reached {
+            distance,
+            vertex,
+            label,
+        }) = heap.pop()
+        {
+            if labels[vertex] != usize::MAX {
+                continue;
+            }
+            labels[vertex] = label;
+            for &(neighbor, length) in &neighbors[vertex] {
+                let d = distance + length;
+                if labels[neighbor] == usize::MAX && d < distances[neighbor] {
+                    distances[neighbor] = d;
+                    heap.push(Reached {
+                        distance: d,
+                        vertex: neighbor,
+                        label,
+                    });
+                }
+            }
+        }
+        labels
+    }
+    /// Return all edges (v0, v1) with v0 < v1, that are shorter than min_len.
+    pub fn check_min_edge_length(&self, min_len: S) -> Vec<(usize, usize)> {
+        self.edge_lengths()
+            .into_iter()
+            .filter(|&(_, l)| l < min_len)
+            .map(|(e, _)| e)
+            .collect()
+    }
+    /// Return all edges (v0, v1) with v0 < v1, that are longer than max_len.
+    pub fn check_max_edge_length(&self, max_len: S) -> Vec<(usize, usize)> {
+        self.edge_lengths()
+            .into_iter()
+            .filter(|&(_, l)| l > max_len)
+            .map(|(e, _)| e)
+            .collect()
+    }
+    /// Return minimum, mean and maximum length of all edges, or zeros if there are no edges.
+    pub fn edge_length_statistics(&self) -> (S, S, S) {
+        let lengths = self.edge_lengths();
+        let zero: S = From::from(0f32);
+        if lengths.is_empty() {
+            return (zero, zero, zero);
+        }
+        let (min, sum, max) = lengths.iter().fold(
+            (S::infinity(), zero, S::neg_infinity()),
+            |(min, sum, max), &(_, l)| (Float::min(min, l), sum + l, Float::max(max, l)),
+        );
+        (min, sum / From::from(lengths.len() as f32), max)
+    }
+    /// Return the aspect ratio of each face, i.e. the length of its longest edge divided by the
+    /// length of its shortest edge. This is infinite for faces with coinciding vertices.
+    pub fn compute_face_aspect_ratios(&self) -> Vec<S> {
+        (0..self.faces.len())
+            .map(|f| {
+                let [a, b, c] = self.face_points(f);
+                let lengths = [
+                    na::distance(&a, &b),
+                    na::distance(&b, &c),
+                    na::distance(&c, &a),
+                ];
+                let longest = lengths.iter().cloned().fold(S::zero(), Float::max);
+                let shortest = lengths.iter().cloned().fold(S::infinity(), Float::min);
+                longest / shortest
+            })
+            .collect()
+    }
+    /// Return minimum, maximum, mean and standard deviation of the aspect ratios of all faces
+    /// (see compute_face_aspect_ratios), or zeros if there are no faces.
+    pub fn aspect_ratio_statistics(&self) -> (S, S, S, S) {
+        let ratios = self.compute_face_aspect_ratios();
+        let zero: S = From::from(0f32);
+        if ratios.is_empty() {
+            return (zero, zero, zero, zero);
+        }
+        let n: S = From::from(ratios.len() as f32);
+        let (min, max, sum) = ratios.iter().fold(
+            (S::infinity(), S::neg_infinity(), zero),
+            |(min, max, sum), &r| (Float::min(min, r), Float::max(max, r), sum + r),
+        );
+        let mean = sum / n;
+        let variance = ratios
+            .iter()
+            .fold(zero, |sum, &r| sum + (r - mean) * (r - mean))
+            / n;
+        (min, max, mean, Float::sqrt(variance))
+    }
+    // Return the normal of the face at index face, scaled by twice the face area.
+    fn scaled_face_normal(&self, face: usize) -> na::Vector3<S> {
+        let [a, b, c] = self.face_points(face);
+        (b - a).cross(&(c - a))
+    }
+    /// Return a copy of the mesh, in which the winding of faces is flipped, where needed, so
+    /// their normals point into the hemisphere of reference_normal. If reference_normal is None,
+    /// the normal of each face should point away from the centroid of the vertices instead, as
+    /// for closed meshes. Degenerate faces and faces perpendicular to the reference are kept.
+    pub fn transform_faces_ccw(&self, reference_normal: Option<na::Vector3<S>>) -> Mesh<S> {
+        let mut result = self.clone();
+        if self.vertices.is_empty() {
+            return result;
+        }
+        let centroid = self.vertex_centroid();
+        let third: S = From::from(1f32 / 3f32);
+        for (i, face) in result.faces.iter_mut().enumerate() {
+            let reference = match reference_normal {
+                Some(n) => n,
+                None => {
+                    let [a, b, c] = self.face_points(i);
+                    (a.coords + b.coords + c.coords) * third - centroid.coords
+                }
+            };
+            if self.scaled_face_normal(i).dot(&reference) < S::zero() {
+                face.swap(1, 2);
+            }
+        }
+        result
+    }
+    /// Orient the faces of a tessellation of f outwards, e.g. where ambiguous cell
+    /// configurations produced inverted faces: Each face is flipped, if the point a bit in front of
+    /// its centroid is inside f and the point behind it is outside. The offset is a quarter of the
+    /// mean edge length of the face. Returns the number of flipped faces. Vertex normals are not
+    /// changed.
+    pub fn check_and_fix_flipped_normals(&mut self, f: &dyn ImplicitFunction<S>) -> usize {
+        let third: S = From::from(1f32 / 3f32);
+        let offset: S = From::from(FLIP_CHECK_OFFSET);
+        let mut flipped = 0;
+        for i in 0..self.faces.len() {
+            let n = match self.scaled_face_normal(i).try_normalize(S::zero()) {
+                Some(n) => n,
+                None => continue,
+            };
+            let [a, b, c] = self.face_points(i);
+            let centroid = na::Point3::from((a.coords + b.coords + c.coords) * third);
+            let mean_edge_length =
+                (na::distance(&a, &b) + na::distance(&b, &c) + na::distance(&c, &a)) * third;
+            let d = n * (mean_edge_length * offset);
+            if f.is_inside(&(centroid + d)) && !f.is_inside(&(centroid - d)) {
+                self.faces[i].swap(1, 2);
+                flipped += 1;
+            }
+        }
+        flipped
+    }
+    /// Compute the local thickness at each vertex, i.e. the diameter of the largest sphere inside
+    /// f touching the surface at the vertex, e.g. to find walls too thin to be 3d printed. f must
+    /// be the signed distance function the mesh was tessellated from. Since -f is the radius of
+    /// the largest sphere inside f around a point, the center of the touching sphere is the point
+    /// farthest along the inward normal, whose distance to the surface equals its distance to
+    /// the vertex. It is found by bisection.
+    pub fn compute_thickness_field(&self, f: &dyn ImplicitFunction<S>) -> Vec<S> {
+        let two: S = From::from(2f32);
+        let max_radius = f.bbox().dim().norm() / two;
+        let tolerance = max_radius * From::from(THICKNESS_TOLERANCE);
+        (0..self.vertices.len())
+            .map(|i| {
+                let p = self.point(i);
+                let n = f.normal(&p);
+                // Vertices are only close to the surface, so compare to their own value.
+                let offset = f.value(&p);
+                let fits = |r: S| -f.value(&(p - n * r)) >= r - offset - tolerance;
+                let (mut inside, mut outside) = (S::zero(), max_radius);
+                for _ in 0..THICKNESS_BISECTION_STEPS {
+                    let r = (inside + outside) / two;
+                    if fits(r) {
+                        inside = r;
+                    } else {
+                        outside = r;
+                    }
+                }
+                inside * two
+            })
+            .collect()
+    }
+    /// Compute a normal for each vertex, see compute_angle_weighted_vertex_normals.
+    pub fn compute_vertex_normals(&self) -> Vec<[S; 3]> {
+        self.compute_angle_weighted_vertex_normals()
+    }
+    /// Compute a normal for each vertex, as the average of the normals of all adjacent faces,
+    /// weighted by the angle of each face at the vertex. Unlike area weighting, this does not
+    /// depend on how the faces around a vertex are triangulated.
+    pub fn compute_angle_weighted_vertex_normals(&self) -> Vec<[S; 3]> {
+        let mut normals = vec![na::Vector3::zeros(); self.vertices.len()];
+        for (i, face) in self.faces.iter().enumerate() {
+            let n = match self.scaled_face_normal(i).try_normalize(S::zero()) {
+                Some(n) => n,
+                None => continue,
+            };
+            for j in 0..3 {
+                let p = self.point(face[j]);
+                let a = self.point(face[(j + 1) % 3]) - p;
+                let b = self.point(face[(j + 2) % 3]) - p;
+                let angle = Float::atan2(a.cross(&b).norm(), a.dot(&b));
+                normals[face[j]] += n * angle;
+            }
+        }
+        Mesh::normalized(&normals)
+    }
+    /// Compute a normal for each vertex, as the area weighted average of the normals of all
+    /// adjacent faces.
+    pub fn compute_area_weighted_vertex_normals(&self) -> Vec<[S; 3]> {
+        let mut normals = vec![na::Vector3::zeros(); self.vertices.len()];
+        for (i, face) in self.faces.iter().enumerate() {
+            let n = self.scaled_face_normal(i);
+            for &v in face {
+                normals[v] += n;
+            }
+        }
+        Mesh::normalized(&normals)
+    }
+    // Normalize the accumulated normals and convert them to arrays.
+    fn normalized(normals: &[na::Vector3<S>]) -> Vec<[S; 3]> {
+        normals
+            .iter()
+            .map(|n| {
+                let n = n.normalize();
+                [n.x, n.y, n.z]
+            })
+            .collect()
+    }
+    // Return the mean curvature normal of each vertex, approximated with the cotangent Laplacian
+    // L x / 2A, where A is a third of the area of the faces around the vertex. This is zero for
+    // vertices without area.
+    fn mean_curvature_normals(&self) -> Vec<na::Vector3<S>> {
+        let mut laplacian = vec![na::Vector3::zeros(); self.vertices.len()];
+        let mut areas = vec![S::zero(); self.vertices.len()];
+        let three: S = From::from(3f32);
+        for (f, face) in self.faces.iter().enumerate() {
+            let area = self.face_area(f) / three;
+            for j in 0..3 {
+                let (a, b) = (face[(j + 1) % 3], face[(j + 2) % 3]);
+                areas[face[j]] += area;
+                // The cotangent of the angle at face[j] weighs the opposite edge.
+                let p = self.point(face[j]);
+                let (u, v) = (self.point(a) - p, self.point(b) - p);
+                let sin = u.cross(&v).norm();
+                if sin == S::zero() {
+                    continue;
+                }
+                let d = (self.point(b) - self.point(a)) * (u.dot(&v) / sin);
+                laplacian[a] += d;
+                laplacian[b] -= d;
+            }
+        }
+        let two: S = From::from(2f32);
+        laplacian
+            .into_iter()
+            .zip(areas)
+            .map(|(l, area)| {
+                if area > S::zero() {
+                    l / (two * area)
+                } else {
+                    na::Vector3::zeros()
+                }
+            })
+            .collect()
+    }
+    /// Return a copy of the mesh after one step of mean curvature flow: Each vertex moves by time
+    /// times the mean curvature normal, which is approximated with the cotangent Laplacian
+    /// L x / 2A, where A is a third of the area of the faces around the vertex. For small time
+    /// this smooths the mesh, while keeping its volume better than plain Laplacian smoothing.
+    /// Normals are recomputed, if the mesh has any.
+    pub fn compute_curvature_flow(&self, time: S) -> Mesh<S> {
+        let mut result = Mesh {
+            vertices: self
+                .mean_curvature_normals()
+                .iter()
+                .enumerate()
+                .map(|(i, n)| {
+                    let p = self.point(i) + n * time;
+                    [p.x, p.y, p.z]
+                })
+                .collect(),
+            faces: self.faces.clone(),
+            normals: Vec::new(),
+        };
+        if !self.normals.is_empty() {
+            result.normals = result.compute_vertex_normals();
+        }
+        result
+    }
+    /// Perform one step of Lloyd relaxation: Each interior vertex moves towards the centroid of
+    /// its neighbors, which approximates the centroid of its Voronoi cell, by
+    /// v = (1 - strength) * v + strength * centroid. This evens out the distribution of vertices
+    /// and reduces sliver triangles. Vertices on boundary edges stay in place. Normals are
+    /// recomputed, if the mesh has any.
+    pub fn relax_vertices(&mut self, strength: S) {
+        let edges = self.edge_face_counts();
+        let mut boundary = vec![false; self.vertices.len()];
+        let mut sums = vec![(na::Vector3::zeros(), 0); self.vertices.len()];
+        for (&(a, b), &count) in &edges {
+            if count == 1 {
+                boundary[a] = true;
+                boundary[b] = true;
+            }
+            sums[a].0 += self.point(b).coords;
+            sums[a].1 += 1;
+            sums[b].0 += self.point(a).coords;
+            sums[b].1 += 1;
+        }
+        for (i, (sum, count)) in sums.into_iter().enumerate() {
+            if boundary[i] || count == 0 {
+                continue;
+            }
+            let count: S = From::from(count as f32);
+            let centroid = sum / count;
+            let p = self.point(i).coords * (S::one() - strength) + centroid * strength;
+            self.vertices[i] = [p.x, p.y, p.z];
+        }
+        if !self.normals.is_empty() {
+            self.normals = self.compute_vertex_normals();
+        }
+    }
+    /// Compute the mean curvature of each vertex as half the magnitude of the mean curvature
+    /// normal L x / 2A (see compute_curvature_flow). The sign is positive, where the surface is
+    /// convex with respect to normals (e.g. from compute_vertex_normals), and negative, where it
+    /// is concave.
+    pub fn compute_mean_curvature(&self, normals: &[[S; 3]]) -> Vec<S> {
+        assert_eq!(normals.len(), self.vertices.len());
+        let half: S = From::from(0.5f32);
+        self.mean_curvature_normals()
+            .iter()
+            .zip(normals)
+            .map(|(h, n)| {
+                let curvature = h.norm() * half;
+                // The mean curvature normal points inwards at convex vertices.
+                if h.dot(&na::Vector3::new(n[0], n[1], n[2])) > S::zero() {
+                    -curvature
+                } else {
+                    curvature
+                }
+            })
+            .collect()
+    }
+    /// Compute the discrete Gaussian curvature of each vertex as its angle defect, i.e. 2 pi minus
+    /// the sum of the angles of the faces at the vertex. For closed meshes the sum over all
+    /// vertices is 2 pi times the euler_characteristic (Gauss-Bonnet).
+    pub fn compute_discrete_gaussian_curvature(&self) -> Vec<S> {
+        let mut curvatures = vec![S::two_pi(); self.vertices.len()];
+        for face in &self.faces {
+            for j in 0..3 {
+                let p = self.point(face[j]);
+                let a = self.point(face[(j + 1) % 3]) - p;
+                let b = self.point(face[(j + 2) % 3]) - p;
+                curvatures[face[j]] -= Float::atan2(a.cross(&b).norm(), a.dot(&b));
+            }
+        }
+        curvatures
+    }
+    /// Approximate the medial axis (the centers of maximal inscribed spheres) of a closed mesh.
+    /// For each vertex this approximates the inner pole of its Voronoi cell with the shrinking ball
+    /// algorithm: Starting from a large ball touching the vertex along the inverse vertex normal,
+    /// the ball is shrunk until it contains no other vertex.
+    pub fn medial_axis_approximation(&self) -> Vec<na::Point3<S>> {
+        let normals = self.compute_vertex_normals();
+        let points: Vec<_> = (0..self.vertices.len()).map(|i| self.point(i)).collect();
+        let mut bbox = BoundingBox::neg_infinity();
+        for p in &points {
+            bbox.insert(p);
+        }
+        let zero: S = From::from(0f32);
+        let two: S = From::from(2f32);
+        let max_radius = bbox.dim().norm();
+        let epsilon = max_radius * From::from(1e-6f32);
+        let min_separation_angle: S = From::from(MIN_SEPARATION_ANGLE);
+        (0..points.len())
+            .into_par_iter()
+            .filter_map(|i| {
+                let p = points[i];
+                let n = na::Vector3::new(normals[i][0], normals[i][1], normals[i][2]);
+                if !n.iter().all(|c| Float::is_finite(*c)) {
+                    return None;
+                }
+                let mut radius = max_radius;
+                for _ in 0..MAX_SHRINKING_BALL_ITERATIONS {
+                    let center = p - n * radius;
+                    let nearest = points
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, q)| q)
+                        .min_by(|&a, &b| {
+                            na::distance_squared(a, &center)
+                                .partial_cmp(&na::distance_squared(b, &center))
+                                .unwrap()
+                        })?;
+                    if na::distance(nearest, &center) >= radius - epsilon {
+                        break;
+                    }
+                    // Radius of the ball touching p along n that has nearest on its surface.
+                    let d = p - nearest;
+                    let denominator = two * d.dot(&n);
+                    if denominator <= zero {
+                        return None;
+                    }
+                    let next_radius = d.norm_squared() / denominator;
+                    // Denoising: If p and nearest are close as seen from the next center, nearest
+                    // is most likely a neighbor of p on the same sheet of the surface, that only
+                    // intersects the ball due to an inaccurate normal.
+                    let next_center = p - n * next_radius;
+                    let separation = (p - next_center).angle(&(nearest - next_center));
+                    if separation < min_separation_angle {
+                        break;
+                    }
+                    radius = next_radius;
+                }
+                Some(p - n * radius)
+            })
+            .collect()
+    }
+    /// Return a mesh visualizing the edges of this mesh: Each edge is replaced by a six sided
+    /// prism with the given radius, and each vertex where three or more edges meet gets a small
+    /// sphere.
+    pub fn wireframe_mesh(&self, tube_radius: S) -> Mesh<S> {
+        let mut edges: Vec<(usize, usize)> = self
+            .faces
+            .iter()
+            .flat_map(|f| (0..3).map(move |i| (f[i], f[(i + 1) % 3])))
+            .map(|(a, b)| (cmp::min(a, b), cmp::max(a, b)))
+            .collect();
+        edges.sort();
+        edges.dedup();
+        let mut degrees = vec![0; self.vertices.len()];
+        let mut result = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for &(a, b) in &edges {
+            degrees[a] += 1;
+            degrees[b] += 1;
+            result.append_prism(&self.point(a), &self.point(b), tube_radius);
+        }
+        let sphere_radius = tube_radius * From::from(1.5f32);
+        for (i, &degree) in degrees.iter().enumerate() {
+            if degree >= 3 {
+                result.append_sphere(&self.point(i), sphere_radius);
+            }
+        }
+        result
+    }
+    // Unit vectors of the directions of the vertices of a regular hexagon.
+    fn hexagon() -> Vec<(S, S)> {
+        (0..6)
+            .map(|k| {
+                let angle = k as f32 * ::std::f32::consts::PI / 3.;
+                (From::from(angle.cos()), From::from(angle.sin()))
+            })
+            .collect()
+    }
+    // Append a closed six sided prism with radius r around the line segment from a to b.
+    fn append_prism(&mut self, a: &na::Point3<S>, b: &na::Point3<S>, r: S) {
+        let d = b - a;
+        if d.norm() == From::from(0f32) {
+            return;
+        }
+        // Build a right handed orthonormal basis (u, v, d).
+        let d = d.normalize();
+        let abs = d.map(Float::abs);
+        let axis = if abs.x <= abs.y && abs.x <= abs.z {
+            na::Vector3::x()
+        } else if abs.y <= abs.z {
+            na::Vector3::y()
+        } else {
+            na::Vector3::z()
+        };
+        let u = d.cross(&axis).normalize();
+        let v = d.cross(&u);
+        let start = self.vertices.len();
+        for end in &[a, b] {
+            for &(cos, sin) in &Self::hexagon() {
+                let p = *end + (u * cos + v * sin) * r;
+                self.vertices.push([p.x, p.y, p.z]);
+            }
+        }
+        let ring_a = |k: usize| start + k % 6;
+        let ring_b = |k: usize| start + 6 + k % 6;
+        for k in 0..6 {
+            self.faces.push([ring_a(k), ring_a(k + 1), ring_b(k)]);
+            self.faces.push([ring_a(k + 1), ring_b(k + 1), ring_b(k)]);
+        }
+        for k in 1..5 {
+            self.faces.push([ring_a(0), ring_a(k + 1), ring_a(k)]);
+            self.faces.push([ring_b(0), ring_b(k), ring_b(k + 1)]);
+        }
+    }
+    // Append a coarse sphere with radius r around center.
+    fn append_sphere(&mut self, center: &na::Point3<S>, r: S) {
+        let start = self.vertices.len();
+        let half: S = From::from(0.5f32);
+        let ring_radius = r * From::from((3f32).sqrt() / 2.);
+        self.vertices.push([center.x, center.y, center.z + r]);
+        for &z in &[r * half, -r * half] {
+            for &(cos, sin) in &Self::hexagon() {
+                self.vertices.push([
+                    center.x + cos * ring_radius,
+                    center.y + sin * ring_radius,
+                    center.z + z,
+                ]);
+            }
+        }
+        self.vertices.push([center.x, center.y, center.z - r]);
+        let (top, bottom) = (start, start + 13);
+        let upper = |k: usize| start + 1 + k % 6;
+        let lower = |k: usize| start + 7 + k % 6;
+        for k in 0..6 {
+            self.faces.push([top, upper(k), upper(k + 1)]);
+            self.faces.push([upper(k), lower(k), lower(k + 1)]);
+            self.faces.push([upper(k), lower(k + 1), upper(k + 1)]);
+            self.faces.push([bottom, lower(k + 1), lower(k)]);
+        }
+    }
+    /// Intersect the mesh with the plane perpendicular to axis at offset.
+    /// Vertices lying exactly on the plane are treated as lying above it.
+    pub fn slice(&self, axis: Axis, offset: S) -> Contour<S> {
+        let a = axis.index();
+        let above = |v: usize| self.vertices[v][a] >= offset;
+        // Each face crossing the plane contributes a segment connecting two of its edges.
+        let mut segments: Vec<[(usize, usize); 2]> = Vec::new();
+        for face in &self.faces {
+            let crossing: Vec<(usize, usize)> = (0..3)
+                .map(|i| (face[i], face[(i + 1) % 3]))
+                .filter(|&(u, v)| above(u) != above(v))
+                .map(|(u, v)| (cmp::min(u, v), cmp::max(u, v)))
+                .collect();
+            if crossing.len() == 2 {
+                segments.push([crossing[0], crossing[1]]);
+            }
+        }
+        let mut adjacent: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, segment) in segments.iter().enumerate() {
+            for &edge in segment {
+                adjacent.entry(edge).or_default().push(i);
+            }
+        }
+        let crossing_point = |(u, v): (usize, usize)| {
+            let (p, q) = (self.point(u), self.point(v));
+            let mut result = p + (q - p) * ((offset - p[a]) / (q[a] - p[a]));
+            result[a] = offset;
+            result
+        };
+        // Start at the ends of open polylines first, so they are not split.
+        let mut open_ends: Vec<(usize, usize)> = adjacent
+            .iter()
+            .filter(|&(_, s)| s.len() == 1)
+            .map(|(&edge, _)| edge)
+            .collect();
+        open_ends.sort();
+        let mut used = vec![false; segments.len()];
+        let mut polylines = Vec::new();
+        for start in open_ends.into_iter().chain(segments.iter().map(|s| s[0])) {
+            let mut edge = start;
+            let mut polyline = vec![crossing_point(edge)];
+            while let Some(&s) = adjacent[&edge].iter().find(|&&s| !used[s]) {
+                used[s] = true;
+                edge = if segments[s][0] == edge {
+                    segments[s][1]
+                } else {
+                    segments[s][0]
+                };
+                polyline.push(crossing_point(edge));
+            }
+            if polyline.len() > 1 {
+                polylines.push(polyline);
+            }
+        }
+        Contour {
+            axis,
+            offset,
+            polylines,
+        }
+    }
+    /// Reduce the mesh to at most target faces using quadric error metrics, while keeping sharp
+    /// features intact: Edges where the normals of the adjacent faces differ by more than angle
+    /// (in radians) are never collapsed, and vertices on them are constrained to the feature
+    /// lines. The same applies to boundary edges. Fewer faces than requested may remain, if no
+    /// more edges can be collapsed without changing the topology.
+    pub fn decimate_preserving_sharp_features(&self, target: usize, angle: S) -> Mesh<S> {
+        decimate::decimate(self, target, angle)
+    }
+    /// Reduce the mesh using quadric error metrics, where should_collapse decides which edges to
+    /// collapse: Edges are visited in order of increasing error and each edge (a, b) with error e,
+    /// whose collapse keeps the mesh manifold, is collapsed, if should_collapse(a, b, e) returns
+    /// true. a and b are vertex indexes into this mesh. E.g. |_, _, e| e < threshold
+    /// collapses all edges below the error threshold. Boundary edges are never collapsed. Edges
+    /// may be visited again with a new error, after a neighboring edge was collapsed.
+    pub fn decimate_with_callback(
+        &self,
+        should_collapse: impl Fn(usize, usize, S) -> bool,
+    ) -> Mesh<S> {
+        decimate::decimate_with_callback(self, should_collapse)
+    }
+    /// Reduce the mesh to target randomly selected vertices, keeping only the faces between
+    /// them, e.g. as fast preview. Unlike decimate_preserving_sharp_features this leaves holes
+    /// and ignores the geometry. Vertices keep their order and normals.
+    pub fn downsample_vertex_count(&self, target: usize) -> Mesh<S> {
+        let mut selected = vec![false; self.vertices.len()];
+        let count = target.min(self.vertices.len());
+        let sample = rand::seq::index::sample(&mut rand::thread_rng(), self.vertices.len(), count);
+        for i in sample.into_iter() {
+            selected[i] = true;
+        }
+        let mut index_map = vec![usize::MAX; self.vertices.len()];
+        let mut result = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for (i, &v) in self.vertices.iter().enumerate() {
+            if selected[i] {
+                index_map[i] = result.vertices.len();
+                result.vertices.push(v);
+                if !self.normals.is_empty() {
+                    result.normals.push(self.normals[i]);
+                }
+            }
+        }
+        result.faces = self
+            .faces
+            .iter()
+            .filter(|face| face.iter().all(|&v| selected[v]))
+            .map(|face| [index_map[face[0]], index_map[face[1]], index_map[face[2]]])
+            .collect();
+        result
+    }
+    /// Split the mesh into at most max_parts parts, which are convex, i.e. have no concave
+    /// edges between their faces, e.g. to build collision shapes. Parts are split recursively with
+    /// normalized cuts of the dual graph of faces, which is weighted, so cuts follow concave
+    /// edges. Splitting stops early, once all parts are convex. Parts are open meshes made of the
+    /// faces of this mesh.
+    pub fn approximate_convex_decomposition(&self, max_parts: usize) -> Vec<Mesh<S>> {
+        convex_decomposition::decompose(self, max_parts)
+    }
+    /// Approximate the symmetric Hausdorff distance between this mesh and other, i.e. the
+    /// largest distance of a point on one mesh to the nearest point on the other mesh.
+    /// The distance is evaluated for the vertices and random points on the surface of each mesh,
+    /// so the result may slightly underestimate the true distance.
+    pub fn compute_hausdorff_distance(&self, other: &Mesh<S>) -> S {
+        Float::max(
+            self.directed_hausdorff_distance(other),
+            other.directed_hausdorff_distance(self),
+        )
+    }
+    // Largest distance of a point on this mesh to the nearest point on other.
+    fn directed_hausdorff_distance(&self, other: &Mesh<S>) -> S {
+        let bvh = Bvh::new(other);
+        let mut points: Vec<na::Point3<S>> = self
+            .random_sample_points(HAUSDORFF_SAMPLES)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        points.extend((0..self.vertices.len()).map(|i| self.point(i)));
+        points
+            .par_iter()
+            .map(|p| bvh.distance(p).unwrap_or_else(S::infinity))
+            .reduce(S::zero, Float::max)
+    }
+    /// Return the signed distance of each query point to the surface of the mesh. Points inside
+    /// the mesh, as determined by the winding number, have negative distances.
+    pub fn compute_sdf_at(&self, query_points: &[na::Point3<S>]) -> Vec<S> {
+        let bvh = Bvh::new(self);
+        let half: S = From::from(0.5f32);
+        query_points
+            .par_iter()
+            .map(|p| {
+                let distance = bvh.distance(p).unwrap_or_else(S::infinity);
+                if self.compute_winding_number(*p) > half {
+                    -distance
+                } else {
+                    distance
+                }
+            })
+            .collect()
+    }
+    /// Return the generalized winding number (Jacobson et al.) of the mesh around p, i.e. the sum
+    /// of the solid angles of all faces as seen from p divided by 4 pi. This is close to 1 (or -1
+    /// for inverted faces) inside and close to 0 outside, even for meshes with holes or
+    /// non-manifold parts, which makes it a more robust inside test than ray casting.
+    pub fn compute_winding_number(&self, p: na::Point3<S>) -> S {
+        let two: S = From::from(2f32);
+        let total = (0..self.faces.len()).fold(S::zero(), |sum, face| {
+            let [a, b, c] = self.face_points(face);
+            let (a, b, c) = (a - p, b - p, c - p);
+            let (la, lb, lc) = (a.norm(), b.norm(), c.norm());
+            let numerator = a.dot(&b.cross(&c));
+            let denominator = la * lb * lc + a.dot(&b) * lc + a.dot(&c) * lb + b.dot(&c) * la;
+            sum + two * Float::atan2(numerator, denominator)
+        });
+        total / (two * two * S::pi())
+    }
+    /// Return the shape diameter function (Shapira et al.) at each vertex, i.e. the mean length
+    /// of rays_per_vertex random rays in a cone of 120 degrees around the inward normal, cast to
+    /// where they leave the mesh. Vertices without any ray leaving the mesh get zero.
+    pub fn compute_shape_diameter_function(&self, rays_per_vertex: usize) -> Vec<S> {
+        let bvh = Bvh::new(self);
+        let normals = self.compute_vertex_normals();
+        let face_normals: Vec<na::Vector3<S>> = (0..self.faces.len())
+            .map(|f| self.scaled_face_normal(f))
+            .collect();
+        let zero: S = From::from(0f32);
+        let one: S = From::from(1f32);
+        let two_pi: S = From::from(2. * ::std::f32::consts::PI);
+        let min_cos = Float::cos(<S as From<f32>>::from(SDF_CONE_HALF_ANGLE));
+        // Ignore hits within this distance, which are the faces adjacent to the vertex.
+        let min_t = self.bbox_diagonal() * From::from(1e-6f32);
+        (0..self.vertices.len())
+            .into_par_iter()
+            .map(|i| {
+                let n = -na::Vector3::new(normals[i][0], normals[i][1], normals[i][2]);
+                // Orthonormal basis (u, v, n) around the inward normal.
+                let helper = if Float::abs(n.x) < From::from(0.9f32) {
+                    na::Vector3::x()
+                } else {
+                    na::Vector3::y()
+                };
+                let u = n.cross(&helper).normalize();
+                let v = n.cross(&u);
+                let origin = self.point(i);
+                let mut rng = rand::thread_rng();
+                let (mut sum, mut count) = (zero, 0);
+                for _ in 0..rays_per_vertex {
+                    // Uniformly distributed direction within the cone.
+                    let r: S = From::from(rng.gen::<f32>());
+                    let cos_theta = one - r * (one - min_cos);
+                    let sin_theta = Float::sqrt(one - cos_theta * cos_theta);
+                    let phi = two_pi * From::from(rng.gen::<f32>());
+                    let direction = n * cos_theta
+                        + u * (sin_theta * Float::cos(phi))
+                        + v * (sin_theta * Float::sin(phi));
+                    if let Some((t, face)) = bvh.ray_intersect(&origin, &direction, min_t) {
+                        // Only count rays leaving the mesh through the back of a face.
+                        if face_normals[face].dot(&direction) > zero {
+                            sum += t;
+                            count += 1;
+                        }
+                    }
+                }
+                if count == 0 {
+                    zero
+                } else {
+                    sum / From::from(count as f32)
+                }
+            })
+            .collect()
+    }
+    /// Return the mesh offset by distance along normals (one per vertex), which approximates
+    /// the Minkowski sum with a ball for positive distances along outward normals. Where the
+    /// offset surface intersects itself, e.g. in concave regions, vertices are clipped: Each
+    /// vertex only moves as far as it stays distance away from the rest of the mesh, i.e. as far
+    /// as no other face gets closer than the faces adjacent to the vertex.
+    pub fn offset_mesh(&self, normals: &[[S; 3]], distance: S) -> Mesh<S> {
+        assert_eq!(
+            normals.len(),
+            self.vertices.len(),
+            "need one normal per vertex"
+        );
+        let bvh = Bvh::new(self);
+        let face_normals: Vec<na::Vector3<S>> = (0..self.faces.len())
+            .map(|f| {
+                self.scaled_face_normal(f)
+                    .try_normalize(S::zero())
+                    .unwrap_or_else(na::Vector3::zeros)
+            })
+            .collect();
+        // Minimum of n dot face normal over the adjacent faces of each vertex.
+        let mut alignment = vec![S::one(); self.vertices.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in face {
+                let n = na::Vector3::new(normals[v][0], normals[v][1], normals[v][2]);
+                alignment[v] =
+                    Float::min(alignment[v], Float::max(n.dot(&face_normals[f]), S::zero()));
+            }
+        }
+        let length = Float::abs(distance);
+        let epsilon = self.bbox_diagonal() * From::from(1e-6f32);
+        let half: S = From::from(0.5f32);
+        let vertices = (0..self.vertices.len())
+            .into_par_iter()
+            .map(|i| {
+                let p = self.point(i);
+                let n = na::Vector3::new(normals[i][0], normals[i][1], normals[i][2]);
+                let direction = if distance < S::zero() { -n } else { n };
+                // The adjacent faces are at distance t * alignment from p + t * direction.
+                let is_free = |t: S| {
+                    bvh.distance(&(p + direction * t))
+                        .is_none_or(|d| d >= t * alignment[i] - epsilon)
+                };
+                let mut t = length;
+                if !is_free(t) {
+                    let (mut free, mut blocked) = (S::zero(), length);
+                    for _ in 0..OFFSET_CLIP_ITERATIONS {
+                        let middle = (free + blocked) * half;
+                        if is_free(middle) {
+                            free = middle;
+                        } else {
+                            blocked = middle;
+                        }
+                    }
+                    t = free;
+                }
+                let q = p + direction * t;
+                [q.x, q.y, q.z]
+            })
+            .collect();
+        Mesh {
+            vertices,
+            faces: self.faces.clone(),
+            normals: self.normals.clone(),
+        }
+    }
+    // Return the length of the diagonal of the bounding box of all vertices.
+    fn bbox_diagonal(&self) -> S {
+        let mut bbox = BoundingBox::neg_infinity();
+        for i in 0..self.vertices.len() {
+            bbox.insert(&self.point(i));
+        }
+        if self.vertices.is_empty() {
+            From::from(0f32)
+        } else {
+            bbox.dim().norm()
+        }
+    }
+    /// Unfold the mesh into the plane, e.g. as simple UV mapping, by rotating faces around the
+    /// edges they share with neighbouring faces (hinge unfolding) in breadth first order.
+    /// Returns the 2d position of each corner of each face along with its vertex index, i.e. entry
+    /// 3 * f + i is corner i of face f. Vertices may get different positions in different faces,
+    /// where the mesh is cut open. Connected components are laid out next to each other along x.
+    /// Overlaps are only avoided for developable surfaces.
+    pub fn unfold_to_2d(&self) -> Vec<([S; 2], usize)> {
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, face) in self.faces.iter().enumerate() {
+            for j in 0..3 {
+                let (a, b) = (face[j], face[(j + 1) % 3]);
+                edge_faces
+                    .entry((cmp::min(a, b), cmp::max(a, b)))
+                    .or_default()
+                    .push(i);
+            }
+        }
+        let zero: S = From::from(0f32);
+        let mut positions: Vec<Option<[na::Point2<S>; 3]>> = vec![None; self.faces.len()];
+        // Smallest x coordinate of the next connected component.
+        let mut next_x = zero;
+        for root in 0..self.faces.len() {
+            if positions[root].is_some() {
+                continue;
+            }
+            let [a, b, c] = self.face_points(root);
+            let ab = b - a;
+            let x = ab.normalize();
+            let y = ab.cross(&(c - a)).cross(&ab).normalize();
+            let local = |p: na::Point3<S>| {
+                let d = p - a;
+                let y_coord = if Float::is_finite(y.x) {
+                    d.dot(&y)
+                } else {
+                    zero
+                };
+                na::Point2::new(next_x + d.dot(&x), y_coord)
+            };
+            positions[root] = Some([local(a), local(b), local(c)]);
+            let mut max_x = next_x;
+            let mut queue = VecDeque::new();
+            queue.push_back(root);
+            while let Some(parent) = queue.pop_front() {
+                let parent_positions = positions[parent].unwrap();
+                for p in &parent_positions {
+                    max_x = Float::max(max_x, p.x);
+                }
+                let parent_face = self.faces[parent];
+                for j in 0..3 {
+                    let (u, v) = (parent_face[j], parent_face[(j + 1) % 3]);
+                    let opposite = parent_positions[(j + 2) % 3];
+                    for &f in &edge_faces[&(cmp::min(u, v), cmp::max(u, v))] {
+                        if positions[f].is_some() {
+                            continue;
+                        }
+                        positions[f] = Some(self.hinge(
+                            f,
+                            (u, parent_positions[j]),
+                            (v, parent_positions[(j + 1) % 3]),
+                            &opposite,
+                        ));
+                        queue.push_back(f);
+                    }
+                }
+            }
+            next_x = max_x + self.bbox_diagonal() * From::from(0.1f32);
+        }
+        positions
+            .iter()
+            .zip(&self.faces)
+            .flat_map(|(p, face)| {
+                let p = p.unwrap();
+                (0..3).map(move |i| ([p[i].x, p[i].y], face[i]))
+            })
+            .collect()
+    }
+    // Return the 2d positions of the corners of face, when it is unfolded around the edge between
+    // vertices u and v, which are already at the given positions, such that its third corner lies
+    // on the opposite side of that edge than the point opposite.
+    fn hinge(
+        &self,
+        face: usize,
+        (u, pu): (usize, na::Point2<S>),
+        (v, pv): (usize, na::Point2<S>),
+        opposite: &na::Point2<S>,
+    ) -> [na::Point2<S>; 3] {
+        let f = self.faces[face];
+        let w = f.iter().cloned().find(|&i| i != u && i != v).unwrap();
+        let (a, b, c) = (self.point(u), self.point(v), self.point(w));
+        let edge = (b - a).normalize();
+        let along = (c - a).dot(&edge);
+        let across = (c - a).cross(&edge).norm();
+        let edge2d = (pv - pu).normalize();
+        let mut normal2d = na::Vector2::new(-edge2d.y, edge2d.x);
+        if normal2d.dot(&(opposite - pu)) > From::from(0f32) {
+            normal2d = -normal2d;
+        }
+        let pw = pu + edge2d * along + normal2d * across;
+        let position = |i: usize| {
+            if i == u {
+                pu
+            } else if i == v {
+                pv
+            } else {
+                pw
+            }
+        };
+        [position(f[0]), position(f[1]), position(f[2])]
+    }
+    /// Parameterize a mesh with disk topology over the unit disk with Floater's mean value
+    /// coordinates, e.g. as UV mapping: The longest boundary loop (see boundary_loops) is mapped
+    /// to the unit circle by arc length, and each interior vertex is placed at the mean value
+    /// weighted average of its neighbors. The mapping is bijective for disk topology meshes.
+    /// Returns a UV coordinate for each vertex, or nothing, if the mesh has no boundary. Vertices
+    /// without faces are mapped to the origin.
+    pub fn uv_from_boundary_parameterization(&self) -> Vec<[S; 2]> {
+        let (length, boundary) = match self
+            .boundary_loops()
+            .into_iter()
+            .map(|l| {
+                let length = l.windows(2).fold(S::zero(), |sum, e| {
+                    sum + na::distance(&self.point(e[0]), &self.point(e[1]))
+                });
+                (length, l)
+            })
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(cmp::Ordering::Equal))
+        {
+            Some(boundary) => boundary,
+            None => return Vec::new(),
+        };
+        let mut uvs = vec![[S::zero(); 2]; self.vertices.len()];
+        let mut fixed = vec![false; self.vertices.len()];
+        let mut arc_length = S::zero();
+        for e in boundary.windows(2) {
+            let angle = S::two_pi() * arc_length / length;
+            uvs[e[0]] = [Float::cos(angle), Float::sin(angle)];
+            fixed[e[0]] = true;
+            arc_length += na::distance(&self.point(e[0]), &self.point(e[1]));
+        }
+        // Mean value weight of each directed edge (i, j): (tan(a / 2) + tan(b / 2)) / |x_j - x_i|,
+        // where a and b are the angles at i of the faces next to the edge.
+        let mut weights: Vec<Vec<(usize, S)>> = vec![Vec::new(); self.vertices.len()];
+        let half: S = From::from(0.5f32);
+        for face in &self.faces {
+            for j in 0..3 {
+                let (i, a, b) = (face[j], face[(j + 1) % 3], face[(j + 2) % 3]);
+                let p = self.point(i);
+                let (u, v) = (self.point(a) - p, self.point(b) - p);
+                let tan = Float::tan(Float::atan2(u.cross(&v).norm(), u.dot(&v)) * half);
+                weights[i].push((a, tan / u.norm()));
+                weights[i].push((b, tan / v.norm()));
+            }
+        }
+        // Solve the linear system with Gauss-Seidel iterations, which converge, since each row
+        // is a convex combination.
+        let tolerance: S = From::from(PARAMETERIZATION_TOLERANCE);
+        for _ in 0..MAX_PARAMETERIZATION_ITERATIONS {
+            let mut max_change = S::zero();
+            for i in 0..uvs.len() {
+                if fixed[i] || weights[i].is_empty() {
+                    continue;
+                }
+                let (mut sum, mut total) = ([S::zero(); 2], S::zero());
+                for &(j, w) in &weights[i] {
+                    sum[0] += uvs[j][0] * w;
+                    sum[1] += uvs[j][1] * w;
+                    total += w;
+                }
+                let uv = [sum[0] / total, sum[1] / total];
+                let change =
+                    Float::max(Float::abs(uv[0] - uvs[i][0]), Float::abs(uv[1] - uvs[i][1]));
+                max_change = Float::max(max_change, change);
+                uvs[i] = uv;
+            }
+            if max_change < tolerance {
+                break;
+            }
+        }
+        uvs
+    }
+    /// Write the vertices along with the given per vertex normals (e.g. from
+    /// compute_vertex_normals) as ASCII point cloud, with one line "x y z nx ny nz" per vertex.
+    pub fn write_asc(&self, normals: &[[S; 3]], writer: &mut impl Write) -> io::Result<()> {
+        if normals.len() != self.vertices.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "got {} normals for {} vertices",
+                    normals.len(),
+                    self.vertices.len()
+                ),
+            ));
+        }
+        for (v, n) in self.vertices.iter().zip(normals) {
+            writeln!(
+                writer,
+                "{} {} {} {} {} {}",
+                v[0], v[1], v[2], n[0], n[1], n[2]
+            )?;
+        }
+        Ok(())
+    }
+    /// Write the mesh in Wavefront OBJ format, i.e. "v x y z" lines followed by "f a b c" lines
+    /// with 1-based vertex indexes. If the mesh has normals, they are written as "vn" lines and
+    /// referenced as "f a//a b//b c//c".
+    pub fn write_obj(&self, writer: &mut impl Write) -> io::Result<()> {
+        self.write_obj_with_material(None, writer)
+    }
+    /// Write the mesh to path in Wavefront OBJ format (see write_obj), using a Phong material
+    /// of the given color, which is written to a MTL file next to path (see
+    /// export_wavefront_mtl).
+    pub fn write_obj_file(
+        &self,
+        path: &Path,
+        material_name: &str,
+        color: [f32; 3],
+    ) -> io::Result<()> {
+        let mtl_path = path.with_extension("mtl");
+        let mtl_name = mtl_path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+            .to_string_lossy()
+            .into_owned();
+        let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+        self.export_wavefront_mtl(material_name, color, &mut mtl)?;
+        let mut obj = BufWriter::new(File::create(path)?);
+        self.write_obj_with_material(Some((&mtl_name, material_name)), &mut obj)
+    }
+    /// Write a Wavefront MTL file with a basic Phong material of the given diffuse color, which
+    /// can be referenced from OBJ files, see write_obj_file.
+    pub fn export_wavefront_mtl(
+        &self,
+        material_name: &str,
+        color: [f32; 3],
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        writeln!(writer, "newmtl {}", material_name)?;
+        writeln!(
+            writer,
+            "Ka {} {} {}",
+            color[0] * MTL_AMBIENT,
+            color[1] * MTL_AMBIENT,
+            color[2] * MTL_AMBIENT
+        )?;
+        writeln!(writer, "Kd {} {} {}", color[0], color[1], color[2])?;
+        writeln!(
+            writer,
+            "Ks {} {} {}",
+            MTL_SPECULAR, MTL_SPECULAR, MTL_SPECULAR
+        )?;
+        writeln!(writer, "Ns {}", MTL_SHININESS)
+    }
+    // Write the mesh in Wavefront OBJ format, preceded by mtllib and usemtl statements, if
+    // material contains the name of a MTL file and a material in it.
+    fn write_obj_with_material(
+        &self,
+        material: Option<(&str, &str)>,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        if !self.normals.is_empty() && self.normals.len() != self.vertices.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "mesh has {} normals for {} vertices",
+                    self.normals.len(),
+                    self.vertices.len()
+                ),
+            ));
+        }
+        if let Some((mtl_file, material_name)) = material {
+            writeln!(writer, "mtllib {}", mtl_file)?;
+            writeln!(writer, "usemtl {}", material_name)?;
+        }
+        for v in &self.vertices {
+            writeln!(writer, "v {} {} {}", v[0], v[1], v[2])?;
+        }
+        for n in &self.normals {
+            writeln!(writer, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+        for f in &self.faces {
+            let (a, b, c) = (f[0] + 1, f[1] + 1, f[2] + 1);
+            if self.normals.is_empty() {
+                writeln!(writer, "f {} {} {}", a, b, c)?;
+            } else {
+                writeln!(writer, "f {}//{} {}//{} {}//{}", a, a, b, b, c, c)?;
+            }
+        }
+        Ok(())
+    }
+    /// Read a mesh in Wavefront OBJ format, as written by write_obj. Polygons are split into
+    /// triangle fans. Normals are only kept, if there is one for each vertex and every face uses
+    /// the normal with the index of each of its vertices, as in "f 1//1 2//2 3//3". Texture
+    /// coordinates and all other statements are ignored.
+    pub fn read_obj(reader: &mut impl BufRead) -> io::Result<Mesh<S>> {
+        let invalid = |line: usize, message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line + 1, message),
+            )
+        };
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        // Whether all faces use the normal with the index of each vertex.
+        let mut normals_match = true;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut tokens = line.split_whitespace();
+            let keyword = tokens.next();
+            if keyword == Some("v") || keyword == Some("vn") {
+                let mut coordinates = [S::zero(); 3];
+                for c in &mut coordinates {
+                    *c = tokens
+                        .next()
+                        .and_then(|t| t.parse::<f64>().ok())
+                        .and_then(<S as NumCast>::from)
+                        .ok_or_else(|| invalid(i, "expected three coordinates"))?;
+                }
+                if keyword == Some("v") {
+                    mesh.vertices.push(coordinates);
+                } else {
+                    mesh.normals.push(coordinates);
+                }
+            } else if keyword == Some("f") {
+                let mut polygon = Vec::new();
+                for t in tokens {
+                    // Tokens are "v", "v/vt", "v//vn" or "v/vt/vn".
+                    let mut indexes = t.split('/');
+                    let index = indexes
+                        .next()
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .filter(|&v| v >= 1 && v <= mesh.vertices.len())
+                        .ok_or_else(|| invalid(i, "invalid vertex index"))?;
+                    let normal = indexes.nth(1).filter(|n| !n.is_empty());
+                    normals_match &= normal.and_then(|n| n.parse::<usize>().ok()) == Some(index);
+                    polygon.push(index - 1);
+                }
+                if polygon.len() < 3 {
+                    return Err(invalid(i, "face with less than three vertices"));
+                }
+                for j in 1..polygon.len() - 1 {
+                    mesh.faces.push([polygon[0], polygon[j], polygon[j + 1]]);
+                }
+            }
+        }
+        if !normals_match || mesh.normals.len() != mesh.vertices.len() {
+            mesh.normals.clear();
+        }
+        Ok(mesh)
+    }
+    /// Write the vertices as CSV, with one line "x,y,z" per vertex. If the mesh has normals, the
+    /// lines are "x,y,z,nx,ny,nz".
+    pub fn write_csv_vertices(&self, writer: &mut impl Write) -> io::Result<()> {
+        if self.normals.is_empty() {
+            for v in &self.vertices {
+                writeln!(writer, "{},{},{}", v[0], v[1], v[2])?;
+            }
+            return Ok(());
+        }
+        if self.normals.len() != self.vertices.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "mesh has {} normals for {} vertices",
+                    self.normals.len(),
+                    self.vertices.len()
+                ),
+            ));
+        }
+        for (v, n) in self.vertices.iter().zip(&self.normals) {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                v[0], v[1], v[2], n[0], n[1], n[2]
+            )?;
+        }
+        Ok(())
+    }
+    /// Write the faces as CSV, with one line "a,b,c" of 0-based vertex indexes per face.
+    pub fn write_csv_faces(&self, writer: &mut impl Write) -> io::Result<()> {
+        for f in &self.faces {
+            writeln!(writer, "{},{},{}", f[0], f[1], f[2])?;
+        }
+        Ok(())
+    }
+    /// Read vertices as written by write_csv_vertices into a mesh without faces. Lines have to
+    /// contain either 3 values "x,y,z" or 6 values "x,y,z,nx,ny,nz", then normals are read as
+    /// well. Empty lines are ignored.
+    pub fn read_csv_vertices(reader: &mut impl BufRead) -> io::Result<Mesh<S>> {
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values = line
+                .split(',')
+                .map(|t| t.trim().parse::<f64>().ok().and_then(<S as NumCast>::from))
+                .collect::<Option<Vec<S>>>();
+            match values {
+                Some(ref v) if v.len() == 3 && mesh.normals.is_empty() => {
+                    mesh.vertices.push([v[0], v[1], v[2]]);
+                }
+                Some(ref v) if v.len() == 6 && mesh.normals.len() == mesh.vertices.len() => {
+                    mesh.vertices.push([v[0], v[1], v[2]]);
+                    mesh.normals.push([v[3], v[4], v[5]]);
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected 3 or 6 values like the first line", i + 1),
+                    ))
+                }
+            }
+        }
+        Ok(mesh)
+    }
+    /// Write the mesh in binary STL format: An 80 byte header, the number of faces as u32 and
+    /// for each face its normal, its three vertices and a zero u16 attribute byte count, all
+    /// little endian.
+    pub fn write_stl_binary(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut header = [0u8; 80];
+        let title = b"binary STL written by tessellation";
+        header[..title.len()].copy_from_slice(title);
+        writer.write_all(&header)?;
+        if self.faces.len() > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} faces exceed the limit of binary STL", self.faces.len()),
+            ));
+        }
+        writer.write_all(&(self.faces.len() as u32).to_le_bytes())?;
+        let write_vector = |writer: &mut dyn Write, v: [S; 3]| -> io::Result<()> {
+            for c in &v {
+                writer.write_all(&c.to_f32().unwrap_or(0.).to_le_bytes())?;
+            }
+            Ok(())
+        };
+        for (i, f) in self.faces.iter().enumerate() {
+            let n = self
+                .scaled_face_normal(i)
+                .try_normalize(S::zero())
+                .unwrap_or_else(na::Vector3::zeros);
+            write_vector(writer, [n.x, n.y, n.z])?;
+            for &v in f {
+                write_vector(writer, self.vertices[v])?;
+            }
+            writer.write_all(&[0, 0])?;
+        }
+        Ok(())
+    }
+    /// Write the mesh in ASCII PLY format, with the vertex properties "x y z" (and "nx ny nz", if
+    /// the mesh has normals) followed by faces as "3 a b c" with 0-based vertex indexes.
+    pub fn write_ply(&self, writer: &mut impl Write) -> io::Result<()> {
+        self.write_ply_with_optional_colors(None, writer)
+    }
+    /// Write the mesh to path like write_ply, with an RGBA color per vertex, that is written as
+    /// uchar properties "red green blue alpha".
+    pub fn write_ply_with_colors(&self, colors: &[[u8; 4]], path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_ply_with_optional_colors(Some(colors), &mut writer)
+    }
+    fn write_ply_with_optional_colors(
+        &self,
+        colors: Option<&[[u8; 4]]>,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        let invalid_length = |name: &str, len: usize| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} {} for {} vertices", len, name, self.vertices.len()),
+            )
+        };
+        if !self.normals.is_empty() && self.normals.len() != self.vertices.len() {
+            return Err(invalid_length("normals", self.normals.len()));
+        }
+        if let Some(colors) = colors {
+            if colors.len() != self.vertices.len() {
+                return Err(invalid_length("colors", colors.len()));
+            }
+        }
+        writeln!(writer, "ply\nformat ascii 1.0")?;
+        writeln!(writer, "element vertex {}", self.vertices.len())?;
+        writeln!(
+            writer,
+            "property float x\nproperty float y\nproperty float z"
+        )?;
+        if !self.normals.is_empty() {
+            writeln!(
+                writer,
+                "property float nx\nproperty float ny\nproperty float nz"
+            )?;
+        }
+        if colors.is_some() {
+            writeln!(
+                writer,
+                "property uchar red\nproperty uchar green\nproperty uchar blue\n\
+                 property uchar alpha"
+            )?;
+        }
+        writeln!(writer, "element face {}", self.faces.len())?;
+        writeln!(writer, "property list uchar int vertex_indices\nend_header")?;
+        for (i, v) in self.vertices.iter().enumerate() {
+            write!(writer, "{} {} {}", v[0], v[1], v[2])?;
+            if let Some(n) = self.normals.get(i) {
+                write!(writer, " {} {} {}", n[0], n[1], n[2])?;
+            }
+            if let Some(c) = colors.map(|colors| colors[i]) {
+                write!(writer, " {} {} {} {}", c[0], c[1], c[2], c[3])?;
+            }
+            writeln!(writer)?;
+        }
+        for f in &self.faces {
+            writeln!(writer, "3 {} {} {}", f[0], f[1], f[2])?;
+        }
+        Ok(())
+    }
+    /// Write the mesh in ASCII FBX 7.4 format, e.g. for game engines. The mesh is written as a
+    /// single Geometry node with Vertices and PolygonVertexIndex (and a LayerElementNormal, if the
+    /// mesh has normals), which is attached to the scene by a Model node.
+    #[cfg(feature = "fbx")]
+    pub fn write_fbx_ascii(&self, writer: &mut impl Write) -> io::Result<()> {
+        if !self.normals.is_empty() && self.normals.len() != self.vertices.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} normals for {} vertices",
+                    self.normals.len(),
+                    self.vertices.len()
+                ),
+            ));
+        }
+        // Write values as an FBX array property at the given indentation.
+        fn write_array<T: ::std::fmt::Display>(
+            writer: &mut impl Write,
+            indent: &str,
+            name: &str,
+            values: &[T],
+        ) -> io::Result<()> {
+            writeln!(writer, "{}{}: *{} {{", indent, name, values.len())?;
+            write!(writer, "{}\ta: ", indent)?;
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, ",")?;
+                }
+                write!(writer, "{}", v)?;
+            }
+            writeln!(writer, "\n{}}}", indent)
+        }
+        writeln!(writer, "; FBX 7.4.0 project file")?;
+        writeln!(
+            writer,
+            "FBXHeaderExtension:  {{\n\tFBXHeaderVersion: 1003\n\tFBXVersion: 7400\n}}"
+        )?;
+        writeln!(
+            writer,
+            "Definitions:  {{\n\tVersion: 100\n\tCount: 2\n\
+             \tObjectType: \"Model\" {{\n\t\tCount: 1\n\t}}\n\
+             \tObjectType: \"Geometry\" {{\n\t\tCount: 1\n\t}}\n}}"
+        )?;
+        writeln!(writer, "Objects:  {{")?;
+        writeln!(writer, "\tGeometry: 1000, \"Geometry::Mesh\", \"Mesh\" {{")?;
+        write_array(
+            writer,
+            "\t\t",
+            "Vertices",
+            &self
+                .vertices
+                .iter()
+                .flat_map(|v| v.iter())
+                .collect::<Vec<_>>(),
+        )?;
+        // The last index of each polygon is stored as its bitwise complement.
+        write_array(
+            writer,
+            "\t\t",
+            "PolygonVertexIndex",
+            &self
+                .faces
+                .iter()
+                .flat_map(|f| vec![f[0] as i64, f[1] as i64, !(f[2] as i64)])
+                .collect::<Vec<_>>(),
+        )?;
+        writeln!(writer, "\t\tGeometryVersion: 124")?;
+        if !self.normals.is_empty() {
+            writeln!(
+                writer,
+                "\t\tLayerElementNormal: 0 {{\n\t\t\tVersion: 101\n\t\t\tName: \"\"\n\
+                 \t\t\tMappingInformationType: \"ByVertice\"\n\
+                 \t\t\tReferenceInformationType: \"Direct\""
+            )?;
+            write_array(
+                writer,
+                "\t\t\t",
+                "Normals",
+                &self
+                    .normals
+                    .iter()
+                    .flat_map(|n| n.iter())
+                    .collect::<Vec<_>>(),
+            )?;
+            writeln!(writer, "\t\t}}")?;
+            writeln!(
+                writer,
+                "\t\tLayer: 0 {{\n\t\t\tVersion: 100\n\t\t\tLayerElement:  {{\n\
+                 \t\t\t\tType: \"LayerElementNormal\"\n\t\t\t\tTypedIndex: 0\n\t\t\t}}\n\t\t}}"
+            )?;
+        }
+        writeln!(writer, "\t}}")?;
+        writeln!(
+            writer,
+            "\tModel: 2000, \"Model::Mesh\", \"Mesh\" {{\n\t\tVersion: 232\n\t}}\n}}"
+        )?;
+        // Connect the geometry to the model and the model to the root of the scene.
+        writeln!(
+            writer,
+            "Connections:  {{\n\tC: \"OO\",1000,2000\n\tC: \"OO\",2000,0\n}}"
+        )
+    }
+    /// Read a mesh in binary STL format, as written by write_stl_binary. Identical vertices of
+    /// adjacent faces are merged. The normals stored in the file are ignored.
+    pub fn read_stl_binary(reader: &mut impl Read) -> io::Result<Mesh<S>> {
+        let mut header = [0u8; 80];
+        reader.read_exact(&mut header)?;
+        let mut count = [0u8; 4];
+        reader.read_exact(&mut count)?;
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        // Map from the bits of the coordinates to the vertex index.
+        let mut vertex_map = HashMap::new();
+        let mut face = [0u8; 50];
+        for _ in 0..u32::from_le_bytes(count) {
+            reader.read_exact(&mut face)?;
+            let coordinate = |i: usize| {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(&face[4 * i..4 * i + 4]);
+                f32::from_le_bytes(bytes)
+            };
+            let mut indexes = [0; 3];
+            for (j, index) in indexes.iter_mut().enumerate() {
+                // Skip the normal, i.e. the first three coordinates.
+                let c = [
+                    coordinate(3 + 3 * j),
+                    coordinate(4 + 3 * j),
+                    coordinate(5 + 3 * j),
+                ];
+                let vertices = &mut mesh.vertices;
+                *index = *vertex_map
+                    .entry([c[0].to_bits(), c[1].to_bits(), c[2].to_bits()])
+                    .or_insert_with(|| {
+                        vertices.push([From::from(c[0]), From::from(c[1]), From::from(c[2])]);
+                        vertices.len() - 1
+                    });
+            }
+            mesh.faces.push(indexes);
+        }
+        Ok(mesh)
+    }
+    /// Compare this mesh to other, e.g. before and after smoothing.
+    /// If both meshes share the same topology, vertices are compared 1:1. Otherwise each vertex
+    /// is compared to the nearest vertex in other.
+    pub fn compare(&self, other: &Mesh<S>) -> MeshDiff<S> {
+        let topology_changed =
+            self.vertices.len() != other.vertices.len() || self.faces != other.faces;
+        let zero: S = From::from(0f32);
+        let displacements: Vec<S> = if topology_changed {
+            (0..self.vertices.len())
+                .map(|i| {
+                    let p = self.point(i);
+                    (0..other.vertices.len())
+                        .map(|j| na::distance(&p, &other.point(j)))
+                        .fold(S::infinity(), Float::min)
+                })
+                .collect()
+        } else {
+            (0..self.vertices.len())
+                .map(|i| na::distance(&self.point(i), &other.point(i)))
+                .collect()
+        };
+        let max_vertex_displacement = displacements.iter().cloned().fold(zero, Float::max);
+        let mean_vertex_displacement = if displacements.is_empty() {
+            zero
+        } else {
+            displacements.iter().fold(zero, |sum, &d| sum + d)
+                / From::from(displacements.len() as f32)
+        };
+        MeshDiff {
+            max_vertex_displacement,
+            mean_vertex_displacement,
+            face_count_delta: other.faces.len() as i64 - self.faces.len() as i64,
+            topology_changed,
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::cell::RefCell;
+    use std::fs;
+    use test_util::Sphere;
+
+    // Tetrahedron with all faces pointing outwards.
+    fn tetrahedron() -> Mesh<f64> {
+        Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            faces: vec![[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]],
+            normals: Vec::new(),
+        }
+    }
 
     #[test]
     fn simple() {
         let m = Mesh {
             vertices: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]],
             faces: vec![[0, 1, 2]],
+            normals: Vec::new(),
         };
         assert_eq!(m.normal32(0), [0., 0., 1.]);
         assert_eq!(m.vertex32(0), [0., 0., 0.]);
         assert_eq!(m.vertex32(1), [1., 0., 0.]);
         assert_eq!(m.vertex32(2), [0., 1., 0.]);
     }
+
+    #[test]
+    fn random_sample_points() {
+        // Two triangles in the xy-plane, the second has three times the area of the first.
+        let m = Mesh {
+            vertices: vec![
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [0., 1., 0.],
+                [3., 0., 0.],
+                [3., 3., 0.],
+                [2., 0., 0.],
+            ],
+            faces: vec![[0, 1, 2], [3, 4, 5]],
+            normals: Vec::new(),
+        };
+        assert_relative_eq!(m.face_area(0), 0.5);
+        assert_relative_eq!(m.face_area(1), 1.5);
+        let samples = m.random_sample_points(4000);
+        assert_eq!(samples.len(), 4000);
+        let mut on_second = 0;
+        for &(p, face) in &samples {
+            assert_eq!(p.z, 0.);
+            if face == 0 {
+                assert!(p.x >= 0. && p.y >= 0. && p.x + p.y <= 1. + 1e-9);
+            } else {
+                on_second += 1;
+                assert!(p.x >= 2. && p.x <= 3. && p.y >= 0. && p.y <= 3. * (p.x - 2.) + 1e-9);
+            }
+        }
+        assert!((2800..3200).contains(&on_second), "{}", on_second);
+        let empty: Mesh<f64> = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        assert!(empty.random_sample_points(10).is_empty());
+    }
+
+    #[test]
+    fn compare() {
+        let m = Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]],
+            faces: vec![[0, 1, 2]],
+            normals: Vec::new(),
+        };
+        let mut moved = m.clone();
+        moved.vertices[1] = [1., 0., 2.];
+        let diff = m.compare(&moved);
+        assert!(!diff.topology_changed);
+        assert_eq!(diff.face_count_delta, 0);
+        assert_relative_eq!(diff.max_vertex_displacement, 2.);
+        assert_relative_eq!(diff.mean_vertex_displacement, 2. / 3.);
+
+        let mut split = m.clone();
+        split.vertices.push([0.5, 0.5, 0.]);
+        split.faces = vec![[0, 1, 3], [1, 2, 3], [2, 0, 3]];
+        let diff = m.compare(&split);
+        assert!(diff.topology_changed);
+        assert_eq!(diff.face_count_delta, 2);
+        assert_relative_eq!(diff.max_vertex_displacement, 0.);
+        assert_eq!(m.compare(&m).max_vertex_displacement, 0.);
+    }
+
+    #[test]
+    fn repair_non_manifold_edges() {
+        let t = tetrahedron();
+        assert_eq!(t.repair_non_manifold_edges(), (t.clone(), 0));
+        // Two tetrahedra sharing the edge 0 - 1.
+        let mut m = t.clone();
+        m.vertices.push([0., -1., 0.]);
+        m.vertices.push([0., 0., -1.]);
+        m.faces
+            .extend_from_slice(&[[0, 4, 1], [0, 1, 5], [1, 4, 5], [0, 5, 4]]);
+        m.normals = vec![[1., 0., 0.]; m.vertices.len()];
+        assert_eq!(m.edge_face_counts()[&(0, 1)], 4);
+        let (repaired, count) = m.repair_non_manifold_edges();
+        assert_eq!(count, 1);
+        assert_eq!(repaired.vertices.len(), 8);
+        assert_eq!(repaired.normals.len(), 8);
+        assert_eq!(repaired.vertices[6], repaired.vertices[0]);
+        assert_eq!(repaired.vertices[7], repaired.vertices[1]);
+        assert!(repaired.edge_face_counts().values().all(|&n| n == 2));
+        // Two separate spheres.
+        assert_eq!(repaired.euler_characteristic(), 4);
+        repaired.assert_consistent_winding();
+    }
+
+    #[test]
+    fn repair_non_manifold_vertices() {
+        let t = tetrahedron();
+        assert_eq!(t.repair_non_manifold_vertices(), (t.clone(), 0));
+        // Two tetrahedra touching at vertex 0.
+        let mut m = t.clone();
+        m.vertices
+            .extend_from_slice(&[[-1., 0., 0.], [0., -1., 0.], [0., 0., -1.]]);
+        m.faces
+            .extend_from_slice(&[[0, 4, 5], [0, 6, 4], [4, 6, 5], [0, 5, 6]]);
+        assert_relative_eq!(m.volume(), 2. * t.volume());
+        assert_eq!(m.euler_characteristic(), 3);
+        let (repaired, count) = m.repair_non_manifold_vertices();
+        assert_eq!(count, 1);
+        assert_eq!(repaired.vertices.len(), 8);
+        assert_eq!(repaired.vertices[7], repaired.vertices[0]);
+        assert_eq!(&repaired.faces[..4], &m.faces[..4]);
+        // Two separate spheres.
+        assert_eq!(repaired.euler_characteristic(), 4);
+        repaired.assert_consistent_winding();
+    }
+
+    #[test]
+    fn diff_topology() {
+        let t = tetrahedron();
+        assert_eq!(t.euler_characteristic(), 2);
+        assert_eq!(t.genus(), Some(0));
+        assert_eq!(
+            t.diff_topology(&t),
+            TopologyDiff {
+                vertex_count_delta: 0,
+                face_count_delta: 0,
+                genus_delta: Some(0),
+                boundary_edge_count_delta: 0,
+            }
+        );
+        // Removing a face opens three boundary edges, so the mesh has no genus anymore.
+        let mut open = t.clone();
+        open.faces.pop();
+        assert_eq!(open.genus(), None);
+        assert_eq!(
+            t.diff_topology(&open),
+            TopologyDiff {
+                vertex_count_delta: 0,
+                face_count_delta: -1,
+                genus_delta: None,
+                boundary_edge_count_delta: 3,
+            }
+        );
+        // Torus made of 4x4 quads.
+        let n = 4;
+        let mut torus = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..n {
+            for j in 0..n {
+                torus.vertices.push([i as f64, j as f64, 0.]);
+                let (a, b, c, d) = (
+                    i * n + j,
+                    ((i + 1) % n) * n + j,
+                    ((i + 1) % n) * n + (j + 1) % n,
+                    i * n + (j + 1) % n,
+                );
+                torus.faces.push([a, b, c]);
+                torus.faces.push([a, c, d]);
+            }
+        }
+        assert_eq!(torus.euler_characteristic(), 0);
+        assert_eq!(torus.genus(), Some(1));
+        assert_eq!(torus.check_genus(1), Ok(()));
+        assert_eq!(
+            t.check_genus(1),
+            Err(TopologyError::UnexpectedGenus {
+                actual: 0,
+                expected: 1
+            })
+        );
+        // A single triangle has Euler characteristic 1, which would truncate to genus 0.
+        let triangle = Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]],
+            faces: vec![[0, 1, 2]],
+            normals: Vec::new(),
+        };
+        assert_eq!(triangle.genus(), None);
+        assert_eq!(triangle.check_genus(0), Err(TopologyError::NotClosed));
+        // The closed projective plane with 6 vertices has Euler characteristic 1.
+        let projective_plane = Mesh {
+            vertices: vec![[0., 0., 0.]; 6],
+            faces: vec![
+                [0, 1, 3],
+                [0, 1, 5],
+                [0, 2, 4],
+                [0, 2, 5],
+                [0, 3, 4],
+                [1, 2, 3],
+                [1, 2, 4],
+                [1, 4, 5],
+                [2, 3, 5],
+                [3, 4, 5],
+            ],
+            normals: Vec::new(),
+        };
+        assert!(projective_plane.is_closed());
+        assert_eq!(projective_plane.genus(), None);
+        assert_eq!(
+            projective_plane.check_genus(0),
+            Err(TopologyError::OddEulerCharacteristic(1))
+        );
+        assert_eq!(t.diff_topology(&torus).genus_delta, Some(1));
+        assert_eq!(torus.diff_topology(&t).vertex_count_delta, -12);
+    }
+
+    // Sphere of radius 1 around the origin with n rings and 2 * n segments.
+    fn uv_sphere(n: usize) -> Mesh<f64> {
+        use std::f64::consts::PI;
+        let mut vertices = vec![[0., 0., 1.]];
+        for i in 1..n {
+            let theta = PI * i as f64 / n as f64;
+            for j in 0..2 * n {
+                let phi = PI * j as f64 / n as f64;
+                vertices.push([
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ]);
+            }
+        }
+        vertices.push([0., 0., -1.]);
+        let south = vertices.len() - 1;
+        let ring = |i: usize, j: usize| 1 + (i - 1) * 2 * n + j % (2 * n);
+        let mut faces = Vec::new();
+        for j in 0..2 * n {
+            faces.push([0, ring(1, j), ring(1, j + 1)]);
+            for i in 1..n - 1 {
+                faces.push([ring(i, j), ring(i + 1, j), ring(i + 1, j + 1)]);
+                faces.push([ring(i, j), ring(i + 1, j + 1), ring(i, j + 1)]);
+            }
+            faces.push([south, ring(n - 1, j + 1), ring(n - 1, j)]);
+        }
+        Mesh {
+            vertices,
+            faces,
+            normals: Vec::new(),
+        }
+    }
+
+    // Sphere of radius 1 around the origin, generated by subdividing an octahedron.
+    fn octahedron_sphere(subdivisions: usize) -> Mesh<f64> {
+        let mut m = Mesh {
+            vertices: vec![
+                [1., 0., 0.],
+                [-1., 0., 0.],
+                [0., 1., 0.],
+                [0., -1., 0.],
+                [0., 0., 1.],
+                [0., 0., -1.],
+            ],
+            faces: vec![
+                [0, 2, 4],
+                [2, 1, 4],
+                [1, 3, 4],
+                [3, 0, 4],
+                [2, 0, 5],
+                [1, 2, 5],
+                [3, 1, 5],
+                [0, 3, 5],
+            ],
+            normals: Vec::new(),
+        };
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut faces = Vec::new();
+            for f in m.faces.clone() {
+                let mut mid = [0; 3];
+                for i in 0..3 {
+                    let (a, b) = (f[i], f[(i + 1) % 3]);
+                    let vertices = &mut m.vertices;
+                    mid[i] = *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                        let (va, vb) = (vertices[a], vertices[b]);
+                        let v = na::Vector3::new(va[0] + vb[0], va[1] + vb[1], va[2] + vb[2])
+                            .normalize();
+                        vertices.push([v.x, v.y, v.z]);
+                        vertices.len() - 1
+                    });
+                }
+                faces.push([f[0], mid[0], mid[2]]);
+                faces.push([mid[0], f[1], mid[1]]);
+                faces.push([mid[2], mid[1], f[2]]);
+                faces.push([mid[0], mid[1], mid[2]]);
+            }
+            m.faces = faces;
+        }
+        m
+    }
+
+    #[test]
+    fn vertex_normals() {
+        uv_sphere(8).assert_consistent_winding();
+        let m = octahedron_sphere(2);
+        m.assert_consistent_winding();
+        for (v, n) in m.vertices.iter().zip(m.compute_vertex_normals()) {
+            let v = na::Vector3::new(v[0], v[1], v[2]);
+            assert!(na::Vector3::new(n[0], n[1], n[2]).dot(&v) > 0.99);
+        }
+    }
+
+    #[test]
+    fn principal_axes() {
+        let mut m = subdivided_cube(2);
+        // Stretch to 6 x 2 x 4 and rotate by 90 degrees around x, so y and z swap.
+        for v in &mut m.vertices {
+            *v = [3. * v[0] + 1., -2. * v[2] + 2., v[1] + 3.];
+        }
+        let (a, b, c, centroid) = m.compute_principal_axes();
+        assert_relative_eq!(centroid, na::Point3::new(1., 2., 3.), epsilon = 1e-12);
+        assert_relative_eq!(a.x.abs(), 1., epsilon = 1e-12);
+        assert_relative_eq!(b.y.abs(), 1., epsilon = 1e-12);
+        assert_relative_eq!(c.z.abs(), 1., epsilon = 1e-12);
+        assert_relative_eq!(a.cross(&b), c, epsilon = 1e-12);
+        let empty = Mesh::<f64> {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        assert_eq!(empty.compute_principal_axes().3, na::Point3::origin());
+    }
+
+    #[test]
+    fn fit_plane() {
+        // Grid in a tilted plane, with vertices alternately above and below it.
+        let n = na::Vector3::new(1., 2., 2.) / 3.;
+        let u = na::Vector3::new(2., -1., 0.).normalize();
+        let v = n.cross(&u);
+        let center = na::Point3::new(1., -2., 3.);
+        let mut m = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..10 {
+            for j in 0..10 {
+                let offset = if (i + j) % 2 == 0 { 0.01 } else { -0.01 };
+                let p = center + u * (i as f64 - 4.5) + v * (j as f64 - 4.5) + n * offset;
+                m.vertices.push([p.x, p.y, p.z]);
+            }
+        }
+        let (plane, rms) = m.fit_plane();
+        assert_relative_eq!(plane.p, center, epsilon = 1e-9);
+        assert_relative_eq!(plane.n.dot(&n).abs(), 1., epsilon = 1e-6);
+        assert_relative_eq!(rms, 0.01, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn project_onto_plane() {
+        let t = tetrahedron();
+        let xy = Plane {
+            p: na::Point3::new(0., 0., 5.),
+            n: na::Vector3::z() * 2.,
+        };
+        assert_eq!(
+            t.project_onto_plane(xy),
+            vec![[0., 0.], [0., 1.], [-1., 0.], [0., 0.]]
+        );
+        // Distances within the plane are preserved.
+        let n = na::Vector3::new(1., 2., 2.) / 3.;
+        let plane = Plane {
+            p: na::Point3::new(1., -2., 3.),
+            n,
+        };
+        let m = octahedron_sphere(1);
+        let projected = m.project_onto_plane(plane);
+        for i in 0..m.vertices.len() {
+            for j in 0..i {
+                let d = m.point(i) - m.point(j);
+                let in_plane = d - n * n.dot(&d);
+                let (a, b) = (projected[i], projected[j]);
+                let distance = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+                assert_relative_eq!(distance, in_plane.norm(), epsilon = 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn align_to_axes() {
+        let mut m = subdivided_cube(2);
+        m.normals = m.compute_vertex_normals();
+        let rotation = na::Rotation3::from_scaled_axis(na::Vector3::new(0.3, -0.5, 0.8));
+        let mut moved = m.clone();
+        for v in &mut moved.vertices {
+            // Stretch to 6 x 4 x 2, then rotate and move.
+            let p = rotation * na::Point3::new(3. * v[0], 2. * v[1], v[2]);
+            *v = [p.x + 1., p.y - 2., p.z + 5.];
+        }
+        moved.normals = moved.compute_vertex_normals();
+        let aligned = moved.align_to_axes();
+        assert_eq!(aligned.faces, moved.faces);
+        for (v, w) in aligned.vertices.iter().zip(&m.vertices) {
+            // The axes are only determined up to their sign.
+            assert_relative_eq!(v[0].abs(), 3. * w[0].abs(), epsilon = 1e-9);
+            assert_relative_eq!(v[1].abs(), 2. * w[1].abs(), epsilon = 1e-9);
+            assert_relative_eq!(v[2].abs(), w[2].abs(), epsilon = 1e-9);
+        }
+        assert_relative_eq!(aligned.volume(), moved.volume(), epsilon = 1e-9);
+        for (n, expected) in aligned.normals.iter().zip(aligned.compute_vertex_normals()) {
+            assert_relative_eq!(n[..], expected[..], epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn inertia_tensor() {
+        let mut m = subdivided_cube(2);
+        // Box of size 6 x 4 x 2 centered at (1, 2, 3).
+        for v in &mut m.vertices {
+            *v = [3. * v[0] + 1., 2. * v[1] + 2., v[2] + 3.];
+        }
+        assert_relative_eq!(
+            m.compute_center_of_mass(),
+            na::Point3::new(1., 2., 3.),
+            epsilon = 1e-12
+        );
+        let mass = 48. * 0.5;
+        let expected = na::Matrix3::from_diagonal(&na::Vector3::new(
+            mass * (16. + 4.) / 12.,
+            mass * (36. + 4.) / 12.,
+            mass * (36. + 16.) / 12.,
+        ));
+        assert_relative_eq!(m.compute_inertia_tensor(0.5), expected, epsilon = 1e-9);
+        // Rotating the box rotates the tensor.
+        let rotation = na::Rotation3::from_scaled_axis(na::Vector3::new(0.2, 0.7, -0.4));
+        for v in &mut m.vertices {
+            let p = rotation * na::Point3::new(v[0], v[1], v[2]);
+            *v = [p.x, p.y, p.z];
+        }
+        let r = rotation.matrix();
+        assert_relative_eq!(
+            m.compute_inertia_tensor(0.5),
+            r * expected * r.transpose(),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn angle_weighted_vertex_normals() {
+        let mut cube = subdivided_cube(1);
+        // Flip the diagonal of one side, so its triangles are distributed unevenly over corners.
+        let (a, b) = (cube.faces[0], cube.faces[1]);
+        cube.faces[0] = [a[0], a[1], b[2]];
+        cube.faces[1] = [a[1], a[2], b[2]];
+        cube.assert_consistent_winding();
+        let diagonal = |v: &[f64; 3]| na::Vector3::new(v[0], v[1], v[2]).normalize();
+        let area_weighted = cube.compute_area_weighted_vertex_normals();
+        let mut asymmetric = 0;
+        for ((v, n), a) in cube
+            .vertices
+            .iter()
+            .zip(cube.compute_vertex_normals())
+            .zip(&area_weighted)
+        {
+            // Each side contributes a right angle to its corners, regardless of triangulation.
+            assert_relative_eq!(na::Vector3::from(n), diagonal(v), epsilon = 1e-12);
+            if (na::Vector3::from(*a) - diagonal(v)).norm() > 1e-3 {
+                asymmetric += 1;
+            }
+        }
+        // Corners belonging to two triangles of a side are pulled towards its normal.
+        assert!(asymmetric > 0);
+    }
+
+    #[test]
+    fn relax_vertices() {
+        // A square with an off-center interior vertex, which forms sliver triangles.
+        let mut m = Mesh {
+            vertices: vec![
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 1., 0.],
+                [0., 1., 0.],
+                [0.9, 0.8, 0.],
+            ],
+            faces: vec![[0, 1, 4], [1, 2, 4], [2, 3, 4], [3, 0, 4]],
+            normals: vec![[0., 0., 1.]; 5],
+        };
+        let worst_aspect_ratio = |m: &Mesh<f64>| {
+            m.compute_face_aspect_ratios()
+                .into_iter()
+                .fold(0., f64::max)
+        };
+        let before = worst_aspect_ratio(&m);
+        m.relax_vertices(0.5);
+        assert_eq!(
+            m.vertices[..4],
+            [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]]
+        );
+        assert_relative_eq!(m.vertices[4][0], 0.7);
+        assert_relative_eq!(m.vertices[4][1], 0.65);
+        assert!(worst_aspect_ratio(&m) < before);
+        m.relax_vertices(1.);
+        assert_relative_eq!(m.vertices[4][0], 0.5);
+        assert_relative_eq!(m.vertices[4][1], 0.5);
+        assert_eq!(m.normals, vec![[0., 0., 1.]; 5]);
+        // On a closed mesh all vertices move.
+        let mut sphere = octahedron_sphere(2);
+        let original = sphere.clone();
+        sphere.relax_vertices(0.5);
+        assert!(sphere
+            .vertices
+            .iter()
+            .zip(&original.vertices)
+            .all(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn curvature_flow() {
+        // On a sphere of radius r the mean curvature normal is -2 / r times the unit normal.
+        let mut m = octahedron_sphere(4);
+        m.normals = m.compute_vertex_normals();
+        let flown = m.compute_curvature_flow(0.01);
+        assert_eq!(flown.faces, m.faces);
+        assert_eq!(flown.normals.len(), m.normals.len());
+        let radii: Vec<f64> = flown
+            .vertices
+            .iter()
+            .map(|v| na::Vector3::new(v[0], v[1], v[2]).norm())
+            .collect();
+        let mean = radii.iter().sum::<f64>() / radii.len() as f64;
+        assert_relative_eq!(mean, 0.98, epsilon = 1e-3);
+        // The six corners of the octahedron have only four neighbors and move somewhat further.
+        for r in radii {
+            assert_relative_eq!(r, 0.98, epsilon = 0.01);
+        }
+        // A bumpy vertex moves back towards its neighbors.
+        let mut bumpy = octahedron_sphere(4);
+        for x in &mut bumpy.vertices[0] {
+            *x *= 1.1;
+        }
+        let smoothed = bumpy.compute_curvature_flow(0.001);
+        let r = |m: &Mesh<f64>| {
+            na::Vector3::new(m.vertices[0][0], m.vertices[0][1], m.vertices[0][2]).norm()
+        };
+        assert!(
+            r(&smoothed) < r(&bumpy) - 0.01,
+            "{} {}",
+            r(&smoothed),
+            r(&bumpy)
+        );
+    }
+
+    #[test]
+    fn mean_curvature() {
+        // The mean curvature of a sphere is the inverse of its radius.
+        let mut m = octahedron_sphere(4);
+        for v in &mut m.vertices {
+            for x in v.iter_mut() {
+                *x *= 2.;
+            }
+        }
+        let normals = m.compute_vertex_normals();
+        let curvatures = m.compute_mean_curvature(&normals);
+        let mean = curvatures.iter().sum::<f64>() / curvatures.len() as f64;
+        assert_relative_eq!(mean, 0.5, epsilon = 1e-3);
+        // Except at the six corners of the octahedron, which have only four neighbors.
+        assert_eq!(
+            curvatures
+                .iter()
+                .filter(|&&h| (h - 0.5).abs() > 0.05)
+                .count(),
+            6
+        );
+        // Inverting the sphere makes it concave.
+        let inverted: Vec<_> = normals.iter().map(|n| [-n[0], -n[1], -n[2]]).collect();
+        for (h, inverted) in curvatures.iter().zip(m.compute_mean_curvature(&inverted)) {
+            assert_relative_eq!(-h, inverted);
+        }
+        // Flat regions have no curvature.
+        let flat = Mesh {
+            vertices: vec![
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 1., 0.],
+                [0., 1., 0.],
+                [0.5, 0.5, 0.],
+            ],
+            faces: vec![[0, 1, 4], [1, 2, 4], [2, 3, 4], [3, 0, 4]],
+            normals: Vec::new(),
+        };
+        let normals = vec![[0., 0., 1.]; 5];
+        assert_relative_eq!(flat.compute_mean_curvature(&normals)[4], 0.);
+    }
+
+    #[test]
+    fn discrete_gaussian_curvature() {
+        let two_pi = 2. * ::std::f64::consts::PI;
+        for m in &[tetrahedron(), octahedron_sphere(3)] {
+            let total: f64 = m.compute_discrete_gaussian_curvature().iter().sum();
+            assert_relative_eq!(
+                total,
+                two_pi * m.euler_characteristic() as f64,
+                epsilon = 1e-9
+            );
+        }
+        // Torus with radii 2 and 1 made of 8x6 quads.
+        let (n, k) = (8, 6);
+        let mut torus = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..n {
+            let phi = two_pi * i as f64 / n as f64;
+            for j in 0..k {
+                let theta = two_pi * j as f64 / k as f64;
+                let r = 2. + theta.cos();
+                torus
+                    .vertices
+                    .push([r * phi.cos(), r * phi.sin(), theta.sin()]);
+                let (a, b, c, d) = (
+                    i * k + j,
+                    ((i + 1) % n) * k + j,
+                    ((i + 1) % n) * k + (j + 1) % k,
+                    i * k + (j + 1) % k,
+                );
+                torus.faces.push([a, b, c]);
+                torus.faces.push([a, c, d]);
+            }
+        }
+        assert_eq!(torus.euler_characteristic(), 0);
+        let curvatures = torus.compute_discrete_gaussian_curvature();
+        assert_relative_eq!(curvatures.iter().sum::<f64>(), 0., epsilon = 1e-9);
+        // The outside of the torus is convex and the inside saddle shaped.
+        assert!(curvatures[0] > 0.);
+        assert!(curvatures[k / 2] < 0.);
+    }
+
+    #[test]
+    fn medial_axis_approximation() {
+        let m = octahedron_sphere(3);
+        let axis = m.medial_axis_approximation();
+        assert_eq!(axis.len(), m.vertices.len());
+        // The medial axis of a sphere is its center.
+        let mut distances: Vec<f64> = axis.iter().map(|p| p.coords.norm()).collect();
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!(distances[distances.len() / 2] < 0.1, "{:?}", distances);
+        assert!(distances[distances.len() - 1] < 0.25, "{:?}", distances);
+    }
+
+    #[test]
+    fn polygon_list() {
+        let m = tetrahedron();
+        let polygons = m.to_polygon_list();
+        assert_eq!(polygons.len(), 4);
+        assert_eq!(polygons[1], vec![0, 1, 3]);
+        let (points, open_mesh_polygons) = m.to_open_mesh();
+        assert_eq!(points[3], na::Point3::new(0., 0., 1.));
+        assert_eq!(open_mesh_polygons, polygons);
+    }
+
+    #[test]
+    fn consistent_winding() {
+        tetrahedron().assert_consistent_winding();
+    }
+
+    #[test]
+    #[should_panic(expected = "inconsistent winding")]
+    fn inconsistent_winding() {
+        let mut m = tetrahedron();
+        m.faces[2] = [3, 2, 1];
+        m.assert_consistent_winding();
+    }
+
+    #[test]
+    fn wireframe_mesh() {
+        let wireframe = tetrahedron().wireframe_mesh(0.01);
+        // 6 edges with 12 vertices and 20 faces each, 4 vertices of degree 3 with 14 vertices and
+        // 24 faces each.
+        assert_eq!(wireframe.vertices.len(), 6 * 12 + 4 * 14);
+        assert_eq!(wireframe.faces.len(), 6 * 20 + 4 * 24);
+        wireframe.assert_consistent_winding();
+        // All faces point outwards, so the signed volume is positive.
+        let volume: f64 = (0..wireframe.faces.len())
+            .map(|f| {
+                let [a, b, c] = wireframe.face_points(f);
+                a.coords.dot(&b.coords.cross(&c.coords)) / 6.
+            })
+            .sum();
+        assert!(volume > 0.);
+        // All vertices lie within the sphere radius of an edge of the tetrahedron.
+        let t = tetrahedron();
+        for i in 0..wireframe.vertices.len() {
+            let p = wireframe.point(i);
+            let nearest = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+                .iter()
+                .map(|&(a, b)| {
+                    let (a, b) = (t.point(a), t.point(b));
+                    let s = ((p - a).dot(&(b - a)) / (b - a).norm_squared()).clamp(0., 1.);
+                    na::distance(&p, &(a + (b - a) * s))
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert!(nearest <= 0.015 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn slice() {
+        let sphere = octahedron_sphere(3);
+        let contour = sphere.slice(Axis::Z, 0.3);
+        assert_eq!(contour.polylines.len(), 1);
+        assert!(contour.is_closed());
+        let radius = (1f64 - 0.3 * 0.3).sqrt();
+        for p in &contour.polylines[0] {
+            assert_relative_eq!(p.z, 0.3);
+            assert!((p.coords.xy().norm() - radius).abs() < 0.05);
+        }
+        assert!((contour.length() - 2. * ::std::f64::consts::PI * radius).abs() < 0.1);
+        // Slicing through the open side of a single triangle yields an open polyline.
+        let m = Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]],
+            faces: vec![[0, 1, 2]],
+            normals: Vec::new(),
+        };
+        let contour = m.slice(Axis::X, 0.5);
+        assert_eq!(contour.polylines.len(), 1);
+        assert!(!contour.is_closed());
+        assert_relative_eq!(contour.length(), 0.5);
+        assert!(m.slice(Axis::X, 2.).polylines.is_empty());
+    }
+
+    // Axis aligned cube spanning [-1, 1] in each dimension, with each side split into n x n
+    // quads.
+    fn subdivided_cube(n: usize) -> Mesh<f64> {
+        let mut index_map = HashMap::new();
+        let mut m = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        let mut vertex = |p: [i64; 3], m: &mut Mesh<f64>| {
+            *index_map.entry(p).or_insert_with(|| {
+                let s = |c: i64| c as f64 * 2. / n as f64 - 1.;
+                m.vertices.push([s(p[0]), s(p[1]), s(p[2])]);
+                m.vertices.len() - 1
+            })
+        };
+        let n = n as i64;
+        for axis in 0..3 {
+            for &side in &[0, n] {
+                let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                for i in 0..n {
+                    for j in 0..n {
+                        let mut corners = [[0; 3]; 4];
+                        for (k, &(di, dj)) in [(0, 0), (1, 0), (1, 1), (0, 1)].iter().enumerate() {
+                            corners[k][axis] = side;
+                            corners[k][u] = i + di;
+                            corners[k][v] = j + dj;
+                        }
+                        let mut c: Vec<usize> =
+                            corners.iter().map(|&p| vertex(p, &mut m)).collect();
+                        if side == 0 {
+                            c.reverse();
+                        }
+                        m.faces.push([c[0], c[1], c[2]]);
+                        m.faces.push([c[0], c[2], c[3]]);
+                    }
+                }
+            }
+        }
+        m
+    }
+
+    #[test]
+    fn decimate_preserving_sharp_features() {
+        let cube = subdivided_cube(4);
+        cube.assert_consistent_winding();
+        assert_eq!(cube.faces.len(), 6 * 32);
+        // Keeping all vertices on the edges of the cube requires at least 6 * 14 faces.
+        let decimated = cube.decimate_preserving_sharp_features(90, 0.5);
+        assert!(decimated.faces.len() <= 90);
+        decimated.assert_consistent_winding();
+        // All vertices remain on the surface of the cube and all corners are kept.
+        for v in &decimated.vertices {
+            let max = v.iter().fold(0., |m: f64, c| m.max(c.abs()));
+            assert_relative_eq!(max, 1., epsilon = 1e-6);
+        }
+        let num_corners = decimated
+            .vertices
+            .iter()
+            .filter(|v| v.iter().all(|c| (c.abs() - 1.).abs() < 1e-6))
+            .count();
+        assert_eq!(num_corners, 8);
+        // Without sharp features the cube is rounded off.
+        let rounded = cube.decimate_preserving_sharp_features(50, ::std::f64::consts::PI);
+        assert!(rounded.faces.len() <= 50);
+        rounded.assert_consistent_winding();
+    }
+
+    #[test]
+    fn boundary_loops() {
+        assert!(tetrahedron().boundary_loops().is_empty());
+        let mut open = tetrahedron();
+        open.faces.pop();
+        let loops = open.boundary_loops();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 4);
+        assert_eq!(loops[0].first(), loops[0].last());
+        // A 3x3 grid of quads without the center quad has an outer and an inner loop.
+        let mut grid = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..4 {
+            for j in 0..4 {
+                grid.vertices.push([i as f64, j as f64, 0.]);
+                if i < 3 && j < 3 && (i, j) != (1, 1) {
+                    let (a, b, c, d) = (
+                        i * 4 + j,
+                        (i + 1) * 4 + j,
+                        (i + 1) * 4 + j + 1,
+                        i * 4 + j + 1,
+                    );
+                    grid.faces.push([a, b, c]);
+                    grid.faces.push([a, c, d]);
+                }
+            }
+        }
+        let mut lengths: Vec<usize> = grid.boundary_loops().iter().map(|l| l.len() - 1).collect();
+        lengths.sort();
+        assert_eq!(lengths, vec![4, 12]);
+        assert_eq!(
+            grid.boundary_loops()[0],
+            vec![0, 4, 8, 12, 13, 14, 15, 11, 7, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn uv_from_boundary_parameterization() {
+        assert!(tetrahedron().uv_from_boundary_parameterization().is_empty());
+        // The upper half of a sphere.
+        let sphere = octahedron_sphere(3);
+        let mut hemisphere = sphere.clone();
+        hemisphere
+            .faces
+            .retain(|f| f.iter().all(|&v| sphere.vertices[v][2] >= 0.));
+        let uvs = hemisphere.uv_from_boundary_parameterization();
+        assert_eq!(uvs.len(), hemisphere.vertices.len());
+        let radius = |uv: [f64; 2]| (uv[0] * uv[0] + uv[1] * uv[1]).sqrt();
+        for (v, &uv) in hemisphere.vertices.iter().zip(&uvs) {
+            if v[2] == 0. {
+                assert_relative_eq!(radius(uv), 1., epsilon = 1e-9);
+            } else if v[2] > 0. {
+                assert!(radius(uv) < 1.);
+            }
+        }
+        // The pole is mapped to the center by symmetry.
+        let pole = hemisphere.vertices.iter().position(|v| v[2] == 1.).unwrap();
+        assert!(radius(uvs[pole]) < 1e-4);
+        // No face is flipped.
+        for face in &hemisphere.faces {
+            let [a, b, c] = [uvs[face[0]], uvs[face[1]], uvs[face[2]]];
+            let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            assert!(area > 0.);
+        }
+    }
+
+    #[test]
+    fn downsample_vertex_count() {
+        let mut sphere = octahedron_sphere(3);
+        sphere.normals = sphere.vertices.clone();
+        let downsampled = sphere.downsample_vertex_count(100);
+        assert_eq!(downsampled.vertices.len(), 100);
+        assert_eq!(downsampled.normals, downsampled.vertices);
+        assert!(downsampled.faces.len() < sphere.faces.len());
+        // All faces are faces of the original mesh.
+        let faces: Vec<Vec<[f64; 3]>> = sphere
+            .faces
+            .iter()
+            .map(|f| f.iter().map(|&v| sphere.vertices[v]).collect())
+            .collect();
+        for f in &downsampled.faces {
+            let face: Vec<[f64; 3]> = f.iter().map(|&v| downsampled.vertices[v]).collect();
+            assert!(faces.contains(&face));
+        }
+        let all = sphere.downsample_vertex_count(1000);
+        assert_eq!(all.vertices, sphere.vertices);
+        assert_eq!(all.faces, sphere.faces);
+    }
+
+    #[test]
+    fn decimate_with_callback() {
+        let sphere = octahedron_sphere(3);
+        assert_eq!(
+            sphere.decimate_with_callback(|_, _, _| false).faces.len(),
+            sphere.faces.len()
+        );
+        let errors = RefCell::new(Vec::new());
+        let decimated = sphere.decimate_with_callback(|_, _, e| {
+            errors.borrow_mut().push(e);
+            e < 1e-3
+        });
+        assert!(decimated.faces.len() < sphere.faces.len());
+        assert!(decimated.faces.len() > 8);
+        decimated.assert_consistent_winding();
+        assert!(decimated.is_closed());
+        assert!(errors.borrow().iter().any(|&e| e >= 1e-3));
+        // Keep the vertices at the poles of the sphere.
+        let poles: Vec<usize> = (0..sphere.vertices.len())
+            .filter(|&i| sphere.vertices[i][2].abs() == 1.)
+            .collect();
+        assert_eq!(poles.len(), 2);
+        let decimated =
+            sphere.decimate_with_callback(|a, b, _| !poles.contains(&a) && !poles.contains(&b));
+        for &p in &poles {
+            assert!(decimated.vertices.contains(&sphere.vertices[p]));
+        }
+        assert!(decimated.faces.len() < sphere.faces.len() / 4);
+    }
+
+    #[test]
+    fn approximate_convex_decomposition() {
+        // Two planes meeting at a concave valley along the y axis.
+        let n = 6;
+        let mut valley = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..=2 * n {
+            for j in 0..=n {
+                let x = i as f64 - n as f64;
+                valley.vertices.push([x, j as f64, 0.5 * x.abs()]);
+            }
+        }
+        for i in 0..2 * n {
+            for j in 0..n {
+                let (a, b) = (i * (n + 1) + j, (i + 1) * (n + 1) + j);
+                valley.faces.push([a, b, b + 1]);
+                valley.faces.push([a, b + 1, a + 1]);
+            }
+        }
+        let whole = valley.approximate_convex_decomposition(1);
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].faces.len(), valley.faces.len());
+        assert_eq!(whole[0].vertices.len(), valley.vertices.len());
+        let parts = valley.approximate_convex_decomposition(5);
+        assert_eq!(parts.len(), 2);
+        for part in &parts {
+            assert_eq!(part.faces.len(), valley.faces.len() / 2);
+            // Each part is one of the planes.
+            let sign = part.vertices.iter().map(|v| v[0]).sum::<f64>().signum();
+            assert!(part.vertices.iter().all(|v| v[0] * sign >= 0.));
+        }
+        // Convex meshes are not split.
+        assert_eq!(
+            octahedron_sphere(2)
+                .approximate_convex_decomposition(5)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn hausdorff_distance() {
+        let sphere = octahedron_sphere(2);
+        assert_relative_eq!(
+            sphere.compute_hausdorff_distance(&sphere),
+            0.,
+            epsilon = 1e-12
+        );
+        let mut larger = sphere.clone();
+        for v in &mut larger.vertices {
+            for c in v.iter_mut() {
+                *c *= 1.1;
+            }
+        }
+        let d = sphere.compute_hausdorff_distance(&larger);
+        assert!(d > 0.09 && d <= 0.1 + 1e-9, "{}", d);
+        // The tip of a tetrahedron moved up by 1 is furthest away from the original.
+        let mut moved = tetrahedron();
+        moved.vertices[3][2] += 1.;
+        assert_relative_eq!(
+            tetrahedron().compute_hausdorff_distance(&moved),
+            1.,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn sdf_at() {
+        let sphere = octahedron_sphere(3);
+        let points = [
+            na::Point3::new(0., 0., 0.),
+            na::Point3::new(0.5, 0., 0.),
+            na::Point3::new(0., 2., 0.),
+            na::Point3::new(-1., -1., -1.),
+        ];
+        let expected = [-1., -0.5, 1., 3f64.sqrt() - 1.];
+        for (&d, &e) in sphere.compute_sdf_at(&points).iter().zip(&expected) {
+            assert!((d - e).abs() < 0.02, "{} != {}", d, e);
+        }
+    }
+
+    #[test]
+    fn winding_number() {
+        let t = tetrahedron();
+        let inside = na::Point3::new(0.1, 0.1, 0.1);
+        let outside = na::Point3::new(1., 1., 1.);
+        assert_relative_eq!(t.compute_winding_number(inside), 1.);
+        assert_relative_eq!(t.compute_winding_number(outside), 0.);
+        let mut inverted = t.clone();
+        for f in &mut inverted.faces {
+            f.swap(1, 2);
+        }
+        assert_relative_eq!(inverted.compute_winding_number(inside), -1.);
+        // With one face missing the winding number still separates inside from outside.
+        let mut open = t.clone();
+        open.faces.pop();
+        assert!(open.compute_winding_number(inside) > 0.5);
+        assert!(open.compute_winding_number(outside).abs() < 0.5);
+    }
+
+    #[test]
+    fn shape_diameter_function() {
+        let sphere = octahedron_sphere(3);
+        let sdf = sphere.compute_shape_diameter_function(200);
+        assert_eq!(sdf.len(), sphere.vertices.len());
+        // The chord at angle theta to the normal has length 2 cos(theta), which averages to 1.5
+        // over the cone.
+        for &d in &sdf {
+            assert!((d - 1.5).abs() < 0.1, "{}", d);
+        }
+    }
+
+    // Check that unfolded preserves the edge lengths and orientation of all faces of m.
+    fn assert_isometric_unfolding(m: &Mesh<f64>, unfolded: &[([f64; 2], usize)]) {
+        assert_eq!(unfolded.len(), 3 * m.faces.len());
+        for (f, face) in m.faces.iter().enumerate() {
+            let p: Vec<na::Point2<f64>> = (0..3)
+                .map(|i| {
+                    let (p, v) = unfolded[3 * f + i];
+                    assert_eq!(v, face[i]);
+                    na::Point2::new(p[0], p[1])
+                })
+                .collect();
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                assert_relative_eq!(
+                    na::distance(&p[i], &p[(i + 1) % 3]),
+                    na::distance(&m.point(a), &m.point(b)),
+                    epsilon = 1e-9
+                );
+            }
+            let (e, g) = (p[1] - p[0], p[2] - p[0]);
+            assert!(e.x * g.y - e.y * g.x > 0.);
+        }
+    }
+
+    #[test]
+    fn unfold_to_2d() {
+        let t = tetrahedron();
+        assert_isometric_unfolding(&t, &t.unfold_to_2d());
+        // A cylindrical strip is developable and unfolds into a rectangle of the same area.
+        let (n, height) = (8, 2.);
+        let mut strip = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..=n {
+            let angle = ::std::f64::consts::PI * i as f64 / n as f64;
+            strip.vertices.push([angle.cos(), angle.sin(), 0.]);
+            strip.vertices.push([angle.cos(), angle.sin(), height]);
+        }
+        for i in 0..n {
+            let (a, b, c, d) = (2 * i, 2 * i + 2, 2 * i + 3, 2 * i + 1);
+            strip.faces.push([a, b, c]);
+            strip.faces.push([a, c, d]);
+        }
+        let unfolded = strip.unfold_to_2d();
+        assert_isometric_unfolding(&strip, &unfolded);
+        let area: f64 = (0..strip.faces.len()).map(|f| strip.face_area(f)).sum();
+        let mut bbox = BoundingBox::neg_infinity();
+        for &(p, _) in &unfolded {
+            bbox.insert(&na::Point3::new(p[0], p[1], 0.));
+        }
+        assert_relative_eq!(bbox.dim().x * bbox.dim().y, area, epsilon = 1e-9);
+        // Two copies are laid out side by side.
+        let mut twice = strip.clone();
+        let offset = strip.vertices.len();
+        twice.vertices.extend_from_slice(&strip.vertices);
+        twice.faces.extend(
+            strip
+                .faces
+                .iter()
+                .map(|f| [f[0] + offset, f[1] + offset, f[2] + offset]),
+        );
+        let unfolded = twice.unfold_to_2d();
+        assert_isometric_unfolding(&twice, &unfolded);
+        let (first, second) = unfolded.split_at(unfolded.len() / 2);
+        let max_first = first
+            .iter()
+            .map(|p| p.0[0])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_second = second.iter().map(|p| p.0[0]).fold(f64::INFINITY, f64::min);
+        assert!(max_first < min_second);
+    }
+
+    #[test]
+    fn signed_volume_from_centroid() {
+        let t = tetrahedron();
+        assert_relative_eq!(t.volume(), 1. / 6.);
+        assert_relative_eq!(t.signed_volume_from_centroid(), 1. / 6.);
+        let mut far = t.clone();
+        for v in &mut far.vertices {
+            v[0] += 1e8;
+            v[1] -= 1e8;
+        }
+        assert_relative_eq!(far.signed_volume_from_centroid(), 1. / 6., epsilon = 1e-8);
+        let mut inverted = t.clone();
+        inverted.faces.iter_mut().for_each(|f| f.swap(1, 2));
+        assert_relative_eq!(inverted.signed_volume_from_centroid(), -1. / 6.);
+    }
+
+    #[test]
+    fn offset_mesh() {
+        let sphere = octahedron_sphere(3);
+        let normals = sphere.compute_vertex_normals();
+        let grown = sphere.offset_mesh(&normals, 0.5);
+        for v in &grown.vertices {
+            assert_relative_eq!(
+                na::Vector3::new(v[0], v[1], v[2]).norm(),
+                1.5,
+                epsilon = 1e-3
+            );
+        }
+        let shrunk = sphere.offset_mesh(&normals, -0.25);
+        assert_relative_eq!(shrunk.vertices[0][0], 0.75, epsilon = 1e-3);
+        // Offset the two planes of a valley towards each other.
+        let n = 6;
+        let mut valley = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..=2 * n {
+            for j in 0..=n {
+                let x = i as f64 - n as f64;
+                valley.vertices.push([x, j as f64, 0.5 * x.abs()]);
+            }
+        }
+        for i in 0..2 * n {
+            for j in 0..n {
+                let (a, b) = (i * (n + 1) + j, (i + 1) * (n + 1) + j);
+                valley.faces.push([a, b, b + 1]);
+                valley.faces.push([a, b + 1, a + 1]);
+            }
+        }
+        let normals = valley.compute_vertex_normals();
+        let offset = valley.offset_mesh(&normals, 3.);
+        let bvh = Bvh::new(&valley);
+        for (i, (v, w)) in valley.vertices.iter().zip(&offset.vertices).enumerate() {
+            let (p, q) = (valley.point(i), offset.point(i));
+            let moved = na::distance(&p, &q);
+            assert!(moved <= 3. + 1e-9);
+            if v[0].abs() >= 3. {
+                assert_relative_eq!(moved, 3., epsilon = 1e-9);
+            }
+            if v[0].abs() == 1. {
+                // Moving by 3 would cross the offset of the other plane.
+                assert!(moved < 2.9, "{:?} moved to {:?}", v, w);
+                assert_relative_eq!(bvh.distance(&q).unwrap(), moved, epsilon = 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn transform_faces_ccw() {
+        let t = tetrahedron();
+        let mut mixed = t.clone();
+        mixed.faces[1].swap(1, 2);
+        mixed.faces[3].swap(1, 2);
+        assert_eq!(mixed.transform_faces_ccw(None).faces, t.faces);
+        let up = mixed.transform_faces_ccw(Some(na::Vector3::z()));
+        for i in 0..up.faces.len() {
+            assert!(up.scaled_face_normal(i).z >= 0.);
+        }
+        // Face 0 lies in the plane z = 0 and faces down in t.
+        assert_eq!(up.faces[0], [0, 1, 2]);
+    }
+
+    #[test]
+    fn compute_thickness_field() {
+        let sphere = Sphere::unit();
+        let mesh = octahedron_sphere(2);
+        for thickness in mesh.compute_thickness_field(&sphere) {
+            assert_relative_eq!(thickness, 2., epsilon = 1e-3);
+        }
+        // A sphere of radius 0.5.
+        let small = sphere.offset(-0.5);
+        let mut mesh = octahedron_sphere(2);
+        for v in &mut mesh.vertices {
+            for c in v.iter_mut() {
+                *c *= 0.5;
+            }
+        }
+        for thickness in mesh.compute_thickness_field(&small) {
+            assert_relative_eq!(thickness, 1., epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn check_and_fix_flipped_normals() {
+        let sphere = Sphere::unit();
+        let expected = octahedron_sphere(2);
+        let mut m = expected.clone();
+        assert_eq!(m.check_and_fix_flipped_normals(&sphere), 0);
+        for i in &[0, 5, 17] {
+            m.faces[*i].swap(0, 1);
+        }
+        assert_eq!(m.check_and_fix_flipped_normals(&sphere), 3);
+        for (face, expected) in m.faces.iter().zip(&expected.faces) {
+            let mut face = *face;
+            let mut expected = *expected;
+            // Flipping changes the order of vertices, but not the orientation.
+            face.sort();
+            expected.sort();
+            assert_eq!(face, expected);
+        }
+        m.assert_consistent_winding();
+        assert!(m.volume() > 0.);
+    }
+
+    #[test]
+    fn watertight_volume() {
+        let t = tetrahedron();
+        assert!(t.is_closed());
+        assert!(t.check_watertight_volume(1. / 6., 1e-9));
+        assert!(!t.check_watertight_volume(1., 0.1));
+        t.assert_watertight_volume(0.17, 0.01);
+        let mut open = t.clone();
+        open.faces.pop();
+        assert!(!open.is_closed());
+        assert!(!open.check_watertight_volume(open.volume(), 1.));
+    }
+
+    #[test]
+    #[should_panic(expected = "differs from expected volume")]
+    fn assert_watertight_volume() {
+        tetrahedron().assert_watertight_volume(1., 0.1);
+    }
+
+    #[test]
+    fn geodesic_voronoi() {
+        // Strip of squares along x, with a seed at each end.
+        let mut m = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        for i in 0..10 {
+            m.vertices.push([i as f64, 0., 0.]);
+            m.vertices.push([i as f64, 1., 0.]);
+            if i > 0 {
+                let (a, b, c, d) = (2 * i - 2, 2 * i, 2 * i + 1, 2 * i - 1);
+                m.faces.push([a, b, c]);
+                m.faces.push([a, c, d]);
+            }
+        }
+        // An isolated vertex.
+        m.vertices.push([20., 0., 0.]);
+        let labels = m.compute_geodesic_voronoi(&[0, 19]);
+        assert_eq!(labels.len(), m.vertices.len());
+        for (v, &label) in m.vertices.iter().zip(&labels).take(20) {
+            assert_eq!(label, if v[0] < 4.5 { 0 } else { 1 }, "{:?}", v);
+        }
+        assert_eq!(labels[20], usize::MAX);
+    }
+
+    #[test]
+    fn edge_lengths() {
+        let t = tetrahedron();
+        let diagonal = 2f64.sqrt();
+        assert_eq!(t.check_min_edge_length(1.1), vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(t.check_max_edge_length(1.1), vec![(1, 2), (1, 3), (2, 3)]);
+        assert!(t.check_min_edge_length(1.).is_empty());
+        assert!(t.check_max_edge_length(diagonal).is_empty());
+        let (min, mean, max) = t.edge_length_statistics();
+        assert_relative_eq!(min, 1.);
+        assert_relative_eq!(mean, (3. + 3. * diagonal) / 6.);
+        assert_relative_eq!(max, diagonal);
+        let empty: Mesh<f64> = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+        };
+        assert_eq!(empty.edge_length_statistics(), (0., 0., 0.));
+    }
+
+    #[test]
+    fn obj() {
+        let mut m = tetrahedron();
+        let mut out = Vec::new();
+        m.write_obj(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\n\
+             f 1 3 2\nf 1 2 4\nf 2 3 4\nf 1 4 3\n"
+        );
+        assert_eq!(Mesh::read_obj(&mut &out[..]).unwrap(), m);
+        m.normals = m.compute_vertex_normals();
+        let mut out = Vec::new();
+        m.write_obj(&mut out).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert_eq!(text.lines().filter(|l| l.starts_with("vn ")).count(), 4);
+        assert!(text.ends_with("\nf 1//1 4//4 3//3\n"));
+        let read = Mesh::<f64>::read_obj(&mut &out[..]).unwrap();
+        assert_eq!(read.faces, m.faces);
+        assert_eq!(read.normals.len(), m.normals.len());
+        for (a, b) in read.normals.iter().zip(&m.normals) {
+            assert_relative_eq!(a[..], b[..], epsilon = 1e-12);
+        }
+        // Normals, that do not belong to the vertex with the same index, are dropped.
+        let triangle = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 1\nvn 0 0 1\n";
+        for (faces, has_normals) in &[
+            ("f 1//1 2//2 3//3\n", true),
+            ("f 1/1/1 2/1/2 3/1/3\n", true),
+            ("f 1//2 2//1 3//3\n", false),
+            ("f 1 2 3\n", false),
+            ("f 1/1 2/1 3/1\n", false),
+        ] {
+            let obj = format!("{}{}", triangle, faces);
+            let read = Mesh::<f64>::read_obj(&mut obj.as_bytes()).unwrap();
+            assert_eq!(read.faces, vec![[0, 1, 2]]);
+            assert_eq!(!read.normals.is_empty(), *has_normals, "{}", faces);
+        }
+        // Quads are split into two triangles.
+        let quad = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nf 1/1 2/1 3/1 4/1\n";
+        let read = Mesh::<f64>::read_obj(&mut quad.as_bytes()).unwrap();
+        assert_eq!(read.faces, vec![[0, 1, 2], [0, 2, 3]]);
+        assert!(Mesh::<f64>::read_obj(&mut "v 0 0 0\nf 1 2 3\n".as_bytes()).is_err());
+        m.normals.pop();
+        assert!(m.write_obj(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn separate_by_material() {
+        let sphere = octahedron_sphere(1);
+        let materials: Vec<usize> = (0..sphere.faces.len()).map(|f| [5, 2, 7][f % 3]).collect();
+        let parts = sphere.separate_by_material(&materials);
+        assert_eq!(parts.len(), 3);
+        let num_faces: usize = parts.iter().map(|part| part.faces.len()).sum();
+        assert_eq!(num_faces, sphere.faces.len());
+        for part in &parts {
+            // Only the vertices of the faces are kept.
+            let mut used = vec![false; part.vertices.len()];
+            for f in &part.faces {
+                for &v in f {
+                    used[v] = true;
+                }
+            }
+            assert!(used.iter().all(|&u| u));
+        }
+        let with_materials: Vec<(Mesh<f64>, usize)> =
+            parts.into_iter().zip(vec![2, 5, 7]).collect();
+        let (merged, merged_materials) = Mesh::merge_by_material(&with_materials);
+        let faces_by_material = |mesh: &Mesh<f64>, materials: &[usize]| {
+            let mut faces: Vec<(usize, Vec<[f64; 3]>)> = mesh
+                .faces
+                .iter()
+                .zip(materials)
+                .map(|(f, &m)| (m, f.iter().map(|&v| mesh.vertices[v]).collect()))
+                .collect();
+            faces.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            faces
+        };
+        assert_eq!(
+            faces_by_material(&merged, &merged_materials),
+            faces_by_material(&sphere, &materials)
+        );
+        assert!(merged.normals.is_empty());
+    }
+
+    #[test]
+    fn write_ply() {
+        let mut m = tetrahedron();
+        m.faces.truncate(1);
+        m.normals = vec![[0., 0., -1.]; 4];
+        let mut out = Vec::new();
+        m.write_ply(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "ply\nformat ascii 1.0\nelement vertex 4\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property float nx\nproperty float ny\nproperty float nz\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+             0 0 0 0 0 -1\n1 0 0 0 0 -1\n0 1 0 0 0 -1\n0 0 1 0 0 -1\n3 0 2 1\n"
+        );
+        m.normals.clear();
+        let dir = ::std::env::temp_dir().join("tessellation_write_ply");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("colored.ply");
+        let colors = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [1, 2, 3, 4],
+        ];
+        m.write_ply_with_colors(&colors, &path).unwrap();
+        let ply = fs::read_to_string(&path).unwrap();
+        assert!(ply.contains("property float z\nproperty uchar red\nproperty uchar green\n"));
+        assert!(ply.contains("end_header\n0 0 0 255 0 0 255\n"));
+        assert!(ply.ends_with("\n0 0 1 1 2 3 4\n3 0 2 1\n"));
+        assert!(m.write_ply_with_colors(&colors[1..], &path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "fbx")]
+    #[test]
+    fn write_fbx_ascii() {
+        let mut m = tetrahedron();
+        m.faces.truncate(1);
+        let mut out = Vec::new();
+        m.write_fbx_ascii(&mut out).unwrap();
+        let fbx = String::from_utf8(out).unwrap();
+        assert!(fbx.starts_with("; FBX 7.4.0 project file\n"));
+        assert!(fbx.contains("\t\tVertices: *12 {\n\t\t\ta: 0,0,0,1,0,0,0,1,0,0,0,1\n\t\t}\n"));
+        assert!(fbx.contains("\t\tPolygonVertexIndex: *3 {\n\t\t\ta: 0,2,-2\n\t\t}\n"));
+        assert!(!fbx.contains("LayerElementNormal"));
+        m.normals = vec![[0., 0., -1.]; 4];
+        let mut out = Vec::new();
+        m.write_fbx_ascii(&mut out).unwrap();
+        let fbx = String::from_utf8(out).unwrap();
+        assert!(fbx.contains("\t\t\tNormals: *12 {\n\t\t\t\ta: 0,0,-1,0,0,-1,0,0,-1,0,0,-1\n"));
+        assert!(fbx.contains("Type: \"LayerElementNormal\""));
+        m.normals.truncate(3);
+        assert!(m.write_fbx_ascii(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn write_obj_file() {
+        let m = tetrahedron();
+        let mut out = Vec::new();
+        m.export_wavefront_mtl("red", [1., 0., 0.], &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "newmtl red\nKa 0.2 0 0\nKd 1 0 0\nKs 0.5 0.5 0.5\nNs 32\n"
+        );
+        let dir = ::std::env::temp_dir().join("tessellation_write_obj_file");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tetrahedron.obj");
+        m.write_obj_file(&path, "red", [1., 0., 0.]).unwrap();
+        let obj = fs::read_to_string(&path).unwrap();
+        assert!(obj.starts_with("mtllib tetrahedron.mtl\nusemtl red\nv 0 0 0\n"));
+        assert_eq!(Mesh::read_obj(&mut obj.as_bytes()).unwrap(), m);
+        let mtl = fs::read_to_string(dir.join("tetrahedron.mtl")).unwrap();
+        assert!(mtl.starts_with("newmtl red\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv() {
+        let mut m = tetrahedron();
+        let mut out = Vec::new();
+        m.write_csv_vertices(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "0,0,0\n1,0,0\n0,1,0\n0,0,1\n"
+        );
+        assert_eq!(
+            Mesh::<f64>::read_csv_vertices(&mut &out[..])
+                .unwrap()
+                .vertices,
+            m.vertices
+        );
+        let mut out = Vec::new();
+        m.write_csv_faces(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0,2,1\n0,1,3\n1,2,3\n0,3,2\n"
+        );
+        m.normals = m.compute_vertex_normals();
+        let mut out = Vec::new();
+        m.write_csv_vertices(&mut out).unwrap();
+        let read = Mesh::<f64>::read_csv_vertices(&mut &out[..]).unwrap();
+        assert_eq!(read.vertices, m.vertices);
+        assert!(read.faces.is_empty());
+        for (a, b) in read.normals.iter().zip(&m.normals) {
+            assert_relative_eq!(a[..], b[..], epsilon = 1e-12);
+        }
+        assert!(Mesh::<f64>::read_csv_vertices(&mut "0,0,0\n1,0\n".as_bytes()).is_err());
+        assert!(Mesh::<f64>::read_csv_vertices(&mut "0,0,0\n1,0,0,0,0,1\n".as_bytes()).is_err());
+        m.normals.pop();
+        assert!(m.write_csv_vertices(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn stl_binary() {
+        let m = tetrahedron();
+        let mut out = Vec::new();
+        m.write_stl_binary(&mut out).unwrap();
+        assert_eq!(out.len(), 80 + 4 + 4 * 50);
+        assert_eq!(&out[80..84], &[4, 0, 0, 0]);
+        // The first face [0, 2, 1] has the normal (0, 0, -1) and starts at the origin.
+        let floats: Vec<f32> = out[84..84 + 48]
+            .chunks(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        assert_eq!(
+            floats,
+            vec![0., 0., -1., 0., 0., 0., 0., 1., 0., 1., 0., 0.]
+        );
+        assert_eq!(&out[84 + 48..84 + 50], &[0, 0]);
+        let read = Mesh::<f64>::read_stl_binary(&mut &out[..]).unwrap();
+        assert_eq!(read.faces.len(), 4);
+        assert_eq!(read.vertices.len(), 4);
+        // Vertices are numbered in order of appearance.
+        for (f, g) in read.faces.iter().zip(&m.faces) {
+            for i in 0..3 {
+                assert_eq!(read.vertices[f[i]], m.vertices[g[i]]);
+            }
+        }
+        assert!(Mesh::<f64>::read_stl_binary(&mut &out[..100]).is_err());
+    }
+
+    #[test]
+    fn aspect_ratios() {
+        let m = Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [4., 0., 0.]],
+            faces: vec![[0, 1, 2], [0, 3, 2]],
+            normals: Vec::new(),
+        };
+        let ratios = m.compute_face_aspect_ratios();
+        assert_relative_eq!(ratios[0], 2f64.sqrt());
+        assert_relative_eq!(ratios[1], 17f64.sqrt());
+        let (min, max, mean, stddev) = m.aspect_ratio_statistics();
+        assert_relative_eq!(min, ratios[0]);
+        assert_relative_eq!(max, ratios[1]);
+        assert_relative_eq!(mean, (ratios[0] + ratios[1]) / 2.);
+        assert_relative_eq!(stddev, (ratios[1] - ratios[0]) / 2.);
+        let mut degenerate = m.clone();
+        degenerate.faces[0] = [0, 0, 2];
+        assert!(degenerate.compute_face_aspect_ratios()[0].is_infinite());
+    }
+
+    #[test]
+    fn write_asc() {
+        let m = tetrahedron();
+        let mut out = Vec::new();
+        m.write_asc(&m.compute_vertex_normals(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("1 0 0 "));
+        let values: Vec<f64> = lines[3].split(' ').map(|v| v.parse().unwrap()).collect();
+        assert_eq!(values.len(), 6);
+        assert_eq!(&values[..3], &[0., 0., 1.]);
+        assert!(m.write_asc(&[], &mut Vec::new()).is_err());
+    }
 }
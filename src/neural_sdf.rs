@@ -0,0 +1,660 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use finite_difference;
+use na;
+use num_traits::Float;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// Step size of the central differences approximating normals.
+const NORMAL_STEP: f32 = 1e-3;
+
+// Data type of float tensors in ONNX models.
+const ONNX_FLOAT: u64 = 1;
+
+/// Activation function applied between the layers of NeuralSdf.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActivationFn {
+    /// max(x, 0)
+    Relu,
+    /// tanh(x)
+    Tanh,
+}
+
+impl ActivationFn {
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            ActivationFn::Relu => x.max(0.),
+            ActivationFn::Tanh => x.tanh(),
+        }
+    }
+}
+
+/// Signed distance function learned by a small feedforward network (multilayer perceptron),
+/// e.g. with DeepSDF. The network maps a point (x, y, z) to its value. The activation is applied
+/// after every layer except the last one. The network is evaluated in f32.
+/// Learned functions are usually no exact distance functions, so lipschitz_constant is None.
+pub struct NeuralSdf<S: Debug + RealField> {
+    // The weights of each layer as row major matrix with one row per output.
+    weights: Vec<Vec<f32>>,
+    biases: Vec<Vec<f32>>,
+    activation: ActivationFn,
+    bbox: BoundingBox<S>,
+}
+
+// Check, that the layers map three inputs to one output.
+fn check_layers(weights: &[Vec<f32>], biases: &[Vec<f32>]) -> Result<(), String> {
+    if weights.is_empty() || weights.len() != biases.len() {
+        return Err(format!(
+            "{} weights for {} biases",
+            weights.len(),
+            biases.len()
+        ));
+    }
+    let mut inputs = 3;
+    for (i, (w, b)) in weights.iter().zip(biases).enumerate() {
+        if w.len() != inputs * b.len() {
+            return Err(format!(
+                "layer {} has {} weights for {} inputs and {} outputs",
+                i,
+                w.len(),
+                inputs,
+                b.len()
+            ));
+        }
+        inputs = b.len();
+    }
+    if inputs != 1 {
+        return Err(format!("network has {} outputs", inputs));
+    }
+    Ok(())
+}
+
+impl<S: Debug + RealField + Float + From<f32>> NeuralSdf<S> {
+    /// Create a new NeuralSdf with the bounding box [-1, 1]³ (the normalization of DeepSDF).
+    /// Panics, if the layers do not map three inputs to one output.
+    /// weights: the weights of each layer as row major matrix with one row per output
+    /// biases: the biases of each layer
+    /// activation: activation function applied after every layer except the last one
+    pub fn new(
+        weights: Vec<Vec<f32>>,
+        biases: Vec<Vec<f32>>,
+        activation: ActivationFn,
+    ) -> NeuralSdf<S> {
+        if let Err(message) = check_layers(&weights, &biases) {
+            panic!("{}", message);
+        }
+        let one: S = From::from(1f32);
+        NeuralSdf {
+            weights,
+            biases,
+            activation,
+            bbox: BoundingBox::new(
+                &na::Point3::new(-one, -one, -one),
+                &na::Point3::new(one, one, one),
+            ),
+        }
+    }
+    /// Replace the bounding box, e.g. if the network was not trained on [-1, 1]³.
+    pub fn with_bbox(mut self, bbox: BoundingBox<S>) -> Self {
+        self.bbox = bbox;
+        self
+    }
+    /// Read the ONNX model at path, see read_onnx.
+    pub fn from_onnx(path: &Path) -> io::Result<NeuralSdf<S>> {
+        NeuralSdf::read_onnx(&mut File::open(path)?)
+    }
+    /// Read a feedforward network from an ONNX model. Supported are layers of Gemm or MatMul
+    /// followed by Add, with Relu or Tanh activations. Like in NeuralSdf::new, there must be one
+    /// activation after every layer except the last one. All weights must be float initializers.
+    pub fn read_onnx(reader: &mut impl Read) -> io::Result<NeuralSdf<S>> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let (weights, biases, activation) = parse_onnx(&data).map_err(invalid)?;
+        check_layers(&weights, &biases).map_err(invalid)?;
+        Ok(NeuralSdf::new(weights, biases, activation))
+    }
+    fn forward(&self, p: &na::Point3<S>) -> f32 {
+        let mut x: Vec<f32> = p.coords.iter().map(|c| c.to_f32().unwrap()).collect();
+        for (i, (w, b)) in self.weights.iter().zip(&self.biases).enumerate() {
+            let last = i + 1 == self.weights.len();
+            x = b
+                .iter()
+                .zip(w.chunks(x.len()))
+                .map(|(bias, row)| {
+                    let y = row.iter().zip(&x).fold(*bias, |sum, (w, x)| sum + w * x);
+                    if last {
+                        y
+                    } else {
+                        self.activation.apply(y)
+                    }
+                })
+                .collect();
+        }
+        x[0]
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for NeuralSdf<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        From::from(self.forward(p))
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        finite_difference::normal(|p| self.value(p), p, From::from(NORMAL_STEP))
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        None
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// A field of a protobuf message.
+enum Field<'a> {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64,
+    Bytes(&'a [u8]),
+}
+
+// Reader of the fields of a protobuf message, which ONNX models are stored in.
+struct ProtoReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ProtoReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if n > self.data.len() {
+            return Err("unexpected end of message".to_string());
+        }
+        let (result, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(result)
+    }
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut result = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err("invalid varint".to_string())
+    }
+    // Return the number and value of the next field, or None at the end of the message.
+    fn next_field(&mut self) -> Result<Option<(u64, Field<'a>)>, String> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = match key & 7 {
+            0 => Field::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Field::Fixed64
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Field::Bytes(self.take(len)?)
+            }
+            5 => {
+                let bytes = self.take(4)?;
+                Field::Fixed32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            wire_type => return Err(format!("unsupported wire type {}", wire_type)),
+        };
+        Ok(Some((key >> 3, field)))
+    }
+}
+
+fn string(bytes: &[u8]) -> Result<String, String> {
+    String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+}
+
+fn floats(bytes: &[u8]) -> Result<Vec<f32>, String> {
+    let chunks = bytes.chunks_exact(4);
+    if !chunks.remainder().is_empty() {
+        return Err(format!("{} bytes are no float data", bytes.len()));
+    }
+    Ok(chunks
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+// Float tensor of an ONNX model as dims and data in row major order.
+struct Tensor {
+    dims: Vec<usize>,
+    data: Vec<f32>,
+}
+
+// Parse a TensorProto and return its name along with the tensor.
+fn parse_tensor(bytes: &[u8]) -> Result<(String, Tensor), String> {
+    let mut reader = ProtoReader { data: bytes };
+    let mut name = String::new();
+    let mut tensor = Tensor {
+        dims: Vec::new(),
+        data: Vec::new(),
+    };
+    let mut data_type = 0;
+    while let Some((number, field)) = reader.next_field()? {
+        match (number, field) {
+            (1, Field::Varint(d)) => tensor.dims.push(d as usize),
+            (1, Field::Bytes(packed)) => {
+                let mut dims = ProtoReader { data: packed };
+                while !dims.data.is_empty() {
+                    tensor.dims.push(dims.varint()? as usize);
+                }
+            }
+            (2, Field::Varint(t)) => data_type = t,
+            (4, Field::Fixed32(f)) => tensor.data.push(f32::from_bits(f)),
+            (4, Field::Bytes(packed)) | (9, Field::Bytes(packed)) => {
+                tensor.data.extend(floats(packed)?)
+            }
+            (8, Field::Bytes(n)) => name = string(n)?,
+            _ => {}
+        }
+    }
+    if data_type != ONNX_FLOAT {
+        return Err(format!(
+            "tensor {} has unsupported type {}",
+            name, data_type
+        ));
+    }
+    let size = tensor
+        .dims
+        .iter()
+        .try_fold(1usize, |n, &d| n.checked_mul(d));
+    if size != Some(tensor.data.len()) {
+        return Err(format!("tensor {} has invalid size", name));
+    }
+    Ok((name, tensor))
+}
+
+// Node of an ONNX graph.
+struct Node {
+    op_type: String,
+    inputs: Vec<String>,
+    // The integer attributes.
+    attributes: HashMap<String, u64>,
+    // The float attributes.
+    float_attributes: HashMap<String, f32>,
+}
+
+fn parse_node(bytes: &[u8]) -> Result<Node, String> {
+    let mut reader = ProtoReader { data: bytes };
+    let mut node = Node {
+        op_type: String::new(),
+        inputs: Vec::new(),
+        attributes: HashMap::new(),
+        float_attributes: HashMap::new(),
+    };
+    while let Some((number, field)) = reader.next_field()? {
+        match (number, field) {
+            (1, Field::Bytes(input)) => node.inputs.push(string(input)?),
+            (4, Field::Bytes(op_type)) => node.op_type = string(op_type)?,
+            (5, Field::Bytes(attribute)) => {
+                let mut reader = ProtoReader { data: attribute };
+                let mut name = String::new();
+                let mut value = None;
+                let mut float_value = None;
+                while let Some((number, field)) = reader.next_field()? {
+                    match (number, field) {
+                        (1, Field::Bytes(n)) => name = string(n)?,
+                        (2, Field::Fixed32(f)) => float_value = Some(f32::from_bits(f)),
+                        (3, Field::Varint(i)) => value = Some(i),
+                        _ => {}
+                    }
+                }
+                if let Some(value) = value {
+                    node.attributes.insert(name.clone(), value);
+                }
+                if let Some(value) = float_value {
+                    node.float_attributes.insert(name, value);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(node)
+}
+
+// Parse the layers of a feedforward network from the ModelProto in data.
+#[allow(clippy::type_complexity)]
+fn parse_onnx(data: &[u8]) -> Result<(Vec<Vec<f32>>, Vec<Vec<f32>>, ActivationFn), String> {
+    let mut model = ProtoReader { data };
+    let mut graph = None;
+    while let Some((number, field)) = model.next_field()? {
+        if let (7, Field::Bytes(g)) = (number, field) {
+            graph = Some(g);
+        }
+    }
+    let mut graph = ProtoReader {
+        data: graph.ok_or_else(|| "model has no graph".to_string())?,
+    };
+    let mut initializers = HashMap::new();
+    let mut nodes = Vec::new();
+    while let Some((number, field)) = graph.next_field()? {
+        match (number, field) {
+            (1, Field::Bytes(node)) => nodes.push(parse_node(node)?),
+            (5, Field::Bytes(tensor)) => {
+                let (name, tensor) = parse_tensor(tensor)?;
+                initializers.insert(name, tensor);
+            }
+            _ => {}
+        }
+    }
+    let mut weights: Vec<Vec<f32>> = Vec::new();
+    let mut biases: Vec<Vec<f32>> = Vec::new();
+    let mut activation = None;
+    // Whether the last layer is followed by an activation.
+    let mut activated = false;
+    for node in &nodes {
+        let initializer = |i: usize| {
+            node.inputs
+                .get(i)
+                .and_then(|name| initializers.get(name))
+                .ok_or_else(|| format!("{} has no initializer as input {}", node.op_type, i))
+        };
+        match node.op_type.as_str() {
+            "Gemm" | "MatMul" => {
+                if !weights.is_empty() && !activated {
+                    return Err(format!("layer {} has no activation", weights.len() - 1));
+                }
+                activated = false;
+                if node.attributes.get("transA").is_some_and(|&t| t != 0) {
+                    return Err("Gemm with transA is not supported".to_string());
+                }
+                // Gemm computes alpha * x * W + beta * b.
+                for name in &["alpha", "beta"] {
+                    if node.float_attributes.get(*name).is_some_and(|&v| v != 1.) {
+                        return Err(format!("Gemm with {} other than 1 is not supported", name));
+                    }
+                }
+                let w = initializer(1)?;
+                if w.dims.len() != 2 {
+                    return Err(format!("{} weights must be a matrix", node.op_type));
+                }
+                // Gemm computes x * W + b or x * W^T + b with transB, i.e. W has a row per input
+                // unless transB is set.
+                let transposed = node.attributes.get("transB").is_some_and(|&t| t != 0);
+                let (outputs, inputs) = if transposed {
+                    (w.dims[0], w.dims[1])
+                } else {
+                    (w.dims[1], w.dims[0])
+                };
+                let mut matrix = vec![0.; w.data.len()];
+                for o in 0..outputs {
+                    for i in 0..inputs {
+                        matrix[o * inputs + i] = if transposed {
+                            w.data[o * inputs + i]
+                        } else {
+                            w.data[i * outputs + o]
+                        };
+                    }
+                }
+                weights.push(matrix);
+                biases.push(vec![0.; outputs]);
+                if node.op_type == "Gemm" && node.inputs.len() > 2 {
+                    add_bias(&mut biases, &initializer(2)?.data)?;
+                }
+            }
+            "Add" => {
+                if activated {
+                    return Err("Add after an activation is not supported".to_string());
+                }
+                let b = initializer(1).or_else(|_| initializer(0))?;
+                add_bias(&mut biases, &b.data)?;
+            }
+            "Relu" | "Tanh" => {
+                let a = if node.op_type == "Relu" {
+                    ActivationFn::Relu
+                } else {
+                    ActivationFn::Tanh
+                };
+                if activation.is_some_and(|activation| activation != a) {
+                    return Err("different activation functions are not supported".to_string());
+                }
+                if weights.is_empty() || activated {
+                    return Err(format!("{} must follow a layer", node.op_type));
+                }
+                activation = Some(a);
+                activated = true;
+            }
+            op_type => return Err(format!("unsupported operator {}", op_type)),
+        }
+    }
+    if activated {
+        return Err("activation after the last layer is not supported".to_string());
+    }
+    // Without hidden layers, the activation is never applied.
+    Ok((weights, biases, activation.unwrap_or(ActivationFn::Relu)))
+}
+
+// Add b to the biases of the last layer.
+fn add_bias(biases: &mut [Vec<f32>], b: &[f32]) -> Result<(), String> {
+    match biases.last_mut() {
+        Some(last) if last.len() == b.len() => {
+            for (a, b) in last.iter_mut().zip(b) {
+                *a += b;
+            }
+            Ok(())
+        }
+        _ => Err(format!("bias of size {} does not match a layer", b.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // |x| - 1 as network with two hidden units.
+    fn abs_network() -> NeuralSdf<f64> {
+        NeuralSdf::new(
+            vec![vec![1., 0., 0., -1., 0., 0.], vec![1., 1.]],
+            vec![vec![0., 0.], vec![-1.]],
+            ActivationFn::Relu,
+        )
+    }
+
+    #[test]
+    fn forward() {
+        let sdf = abs_network();
+        assert_relative_eq!(sdf.value(&na::Point3::new(-3., 1., 2.)), 2.);
+        assert_relative_eq!(sdf.value(&na::Point3::new(0.5, 0., 0.)), -0.5);
+        assert_relative_eq!(
+            sdf.normal(&na::Point3::new(2., 0., 0.)),
+            na::Vector3::x(),
+            epsilon = 1e-6
+        );
+        let tanh: NeuralSdf<f64> =
+            NeuralSdf::new(vec![vec![0., 2., 0.]], vec![vec![0.5]], ActivationFn::Tanh);
+        assert_relative_eq!(tanh.value(&na::Point3::new(7., 1., 0.)), 2.5);
+        assert_eq!(sdf.bbox().max, na::Point3::new(1., 1., 1.));
+        assert_eq!(sdf.lipschitz_constant(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_layers() {
+        let _: NeuralSdf<f64> =
+            NeuralSdf::new(vec![vec![1., 0.]], vec![vec![0.]], ActivationFn::Relu);
+    }
+
+    fn varint(mut v: u64, out: &mut Vec<u8>) {
+        while v >= 0x80 {
+            out.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn bytes_field(number: u64, data: &[u8], out: &mut Vec<u8>) {
+        varint(number << 3 | 2, out);
+        varint(data.len() as u64, out);
+        out.extend_from_slice(data);
+    }
+
+    fn tensor(name: &str, dims: &[u64], data: &[f32], raw: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        for &d in dims {
+            varint(1 << 3, &mut out);
+            varint(d, &mut out);
+        }
+        varint(2 << 3, &mut out);
+        varint(ONNX_FLOAT, &mut out);
+        let bytes: Vec<u8> = data.iter().flat_map(|f| f.to_le_bytes().to_vec()).collect();
+        bytes_field(if raw { 9 } else { 4 }, &bytes, &mut out);
+        bytes_field(8, name.as_bytes(), &mut out);
+        out
+    }
+
+    fn node(op_type: &str, inputs: &[&str], trans_b: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        for input in inputs {
+            bytes_field(1, input.as_bytes(), &mut out);
+        }
+        bytes_field(4, op_type.as_bytes(), &mut out);
+        if trans_b {
+            let mut attribute = Vec::new();
+            bytes_field(1, b"transB", &mut attribute);
+            varint(3 << 3, &mut attribute);
+            varint(1, &mut attribute);
+            bytes_field(5, &attribute, &mut out);
+        }
+        out
+    }
+
+    fn model(nodes: &[Vec<u8>], initializers: &[Vec<u8>]) -> Vec<u8> {
+        let mut graph = Vec::new();
+        for n in nodes {
+            bytes_field(1, n, &mut graph);
+        }
+        for i in initializers {
+            bytes_field(5, i, &mut graph);
+        }
+        let mut out = Vec::new();
+        varint(1 << 3, &mut out);
+        varint(7, &mut out);
+        bytes_field(7, &graph, &mut out);
+        out
+    }
+
+    #[test]
+    fn read_onnx() {
+        // abs_network as Gemm with transposed weights, Relu, MatMul and Add.
+        let data = model(
+            &[
+                node("Gemm", &["x", "w1", "b1"], true),
+                node("Relu", &["h"], false),
+                node("MatMul", &["r", "w2"], false),
+                node("Add", &["m", "b2"], false),
+            ],
+            &[
+                tensor("w1", &[2, 3], &[1., 0., 0., -1., 0., 0.], false),
+                tensor("b1", &[2], &[0., 0.], true),
+                tensor("w2", &[2, 1], &[1., 1.], true),
+                tensor("b2", &[1], &[-1.], false),
+            ],
+        );
+        let sdf: NeuralSdf<f64> = NeuralSdf::read_onnx(&mut &data[..]).unwrap();
+        let expected = abs_network();
+        assert_eq!(sdf.weights, expected.weights);
+        assert_eq!(sdf.biases, expected.biases);
+        assert_eq!(sdf.activation, ActivationFn::Relu);
+        let unsupported = model(
+            &[node("Sigmoid", &["x"], false)],
+            &[tensor("w1", &[2, 3], &[0.; 6], false)],
+        );
+        assert!(NeuralSdf::<f64>::read_onnx(&mut &unsupported[..]).is_err());
+        assert!(NeuralSdf::<f64>::read_onnx(&mut &data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn read_onnx_activations() {
+        let initializers = [
+            tensor("w1", &[2, 3], &[1., 0., 0., -1., 0., 0.], false),
+            tensor("w2", &[2, 1], &[1., 1.], true),
+        ];
+        let read =
+            |nodes: &[Vec<u8>]| NeuralSdf::<f64>::read_onnx(&mut &model(nodes, &initializers)[..]);
+        let gemm = || node("Gemm", &["x", "w1"], true);
+        let mat_mul = || node("MatMul", &["r", "w2"], false);
+        let relu = || node("Relu", &["h"], false);
+        let tanh = || node("Tanh", &["h"], false);
+        assert!(read(&[gemm(), relu(), mat_mul()]).is_ok());
+        // Without activation between the layers.
+        assert!(read(&[gemm(), mat_mul()]).is_err());
+        // With activation after the last layer.
+        assert!(read(&[gemm(), tanh(), mat_mul(), tanh()]).is_err());
+        // With two activations after one layer.
+        assert!(read(&[gemm(), relu(), relu(), mat_mul()]).is_err());
+        // Without hidden layers, no activation is needed.
+        let linear = model(&[mat_mul()], &[tensor("w2", &[3, 1], &[0., 2., 0.], true)]);
+        let sdf: NeuralSdf<f64> = NeuralSdf::read_onnx(&mut &linear[..]).unwrap();
+        assert_relative_eq!(sdf.value(&na::Point3::new(7., 1., 0.)), 2.);
+    }
+
+    #[test]
+    fn read_onnx_unsupported() {
+        let initializers = [
+            tensor("w1", &[2, 3], &[1., 0., 0., -1., 0., 0.], false),
+            tensor("w2", &[2, 1], &[1., 1.], true),
+        ];
+        let read =
+            |nodes: &[Vec<u8>]| NeuralSdf::<f64>::read_onnx(&mut &model(nodes, &initializers)[..]);
+        // Gemm with a float attribute.
+        let gemm = |name: &[u8], value: f32| {
+            let mut out = node("Gemm", &["x", "w1"], true);
+            let mut attribute = Vec::new();
+            bytes_field(1, name, &mut attribute);
+            varint(2 << 3 | 5, &mut attribute);
+            attribute.extend_from_slice(&value.to_le_bytes());
+            bytes_field(5, &attribute, &mut out);
+            out
+        };
+        let relu = || node("Relu", &["h"], false);
+        let mat_mul = || node("MatMul", &["r", "w2"], false);
+        assert!(read(&[gemm(b"alpha", 1.), relu(), mat_mul()]).is_ok());
+        assert!(read(&[gemm(b"beta", 1.), relu(), mat_mul()]).is_ok());
+        assert!(read(&[gemm(b"alpha", 2.), relu(), mat_mul()]).is_err());
+        assert!(read(&[gemm(b"beta", 0.), relu(), mat_mul()]).is_err());
+        // The size of a tensor, whose dims overflow.
+        let overflow = model(
+            &[node("MatMul", &["x", "w"], false)],
+            &[tensor("w", &[1 << 32, 1 << 32, 3], &[0.; 3], true)],
+        );
+        let error = NeuralSdf::<f64>::read_onnx(&mut &overflow[..])
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(super::floats(&1.5f32.to_le_bytes()), Ok(vec![1.5]));
+        assert!(super::floats(&[0; 5]).is_err());
+        // Raw data of a 3x1 tensor, that is one byte short.
+        let mut w = Vec::new();
+        for &d in &[3, 1] {
+            varint(1 << 3, &mut w);
+            varint(d, &mut w);
+        }
+        varint(2 << 3, &mut w);
+        varint(ONNX_FLOAT, &mut w);
+        bytes_field(9, &[0; 11], &mut w);
+        bytes_field(8, b"w", &mut w);
+        let data = model(&[node("MatMul", &["x", "w"], false)], &[w]);
+        let error = NeuralSdf::<f64>::read_onnx(&mut &data[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
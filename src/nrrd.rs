@@ -0,0 +1,337 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use grid_function::{BoxedGridFn, GridFunction};
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use vertex_index::Index;
+use AsUSize;
+
+// Magic of NRRD headers, which is followed by the version of the format.
+const NRRD_MAGIC: &str = "NRRD000";
+
+/// Signed distance function given by the voxels of a NRRD (Nearly Raw Raster Data) volume,
+/// which is common in medical imaging. Voxel [i, j, k] is located at space origin + [i, j, k].
+/// Between voxels values are interpolated trilinearly. Surfaces touching the sides of the volume
+/// are closed there.
+pub struct NrrdSdf<S: RealField> {
+    grid: GridFunction<S, BoxedGridFn<S>>,
+    dims: [usize; 3],
+}
+
+// Sample type of the voxels.
+#[derive(Clone, Copy)]
+enum DataType {
+    Int16,
+    Float32,
+}
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Raw,
+    Ascii,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Parse a vector "(x,y,z)" of a NRRD header.
+fn parse_vector(s: &str) -> io::Result<[f64; 3]> {
+    let error = || invalid(format!("invalid vector {:?}", s));
+    let inner = s
+        .trim()
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(error)?;
+    let mut result = [0.; 3];
+    let mut components = inner.split(',');
+    for r in &mut result {
+        *r = components
+            .next()
+            .and_then(|c| c.trim().parse().ok())
+            .ok_or_else(error)?;
+    }
+    if components.next().is_some() {
+        return Err(error());
+    }
+    Ok(result)
+}
+
+impl<S> NrrdSdf<S>
+where
+    S: 'static + Debug + RealField + Float + From<f32> + AsUSize,
+{
+    /// Read the NRRD file at path, see read.
+    pub fn from_file(path: &Path) -> io::Result<NrrdSdf<S>> {
+        NrrdSdf::read(&mut BufReader::new(File::open(path)?))
+    }
+    /// Read a three dimensional NRRD volume with attached data. Supported are the types int16
+    /// and float32 with raw or ascii encoding. Space directions and spacings, if given, must be
+    /// the identity, i.e. voxels are one unit apart.
+    pub fn read(reader: &mut impl BufRead) -> io::Result<NrrdSdf<S>> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if !line.starts_with(NRRD_MAGIC) {
+            return Err(invalid("missing NRRD magic".to_string()));
+        }
+        let mut data_type = None;
+        let mut sizes = None;
+        let mut encoding = None;
+        let mut big_endian = false;
+        let mut origin = [0.; 3];
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid("missing data".to_string()));
+            }
+            let field = line.trim_end_matches(&['\r', '\n'][..]);
+            // The header ends with an empty line. Lines starting with # are comments and
+            // key:=value pairs are ignored.
+            if field.is_empty() {
+                break;
+            }
+            if field.starts_with('#') || field.contains(":=") {
+                continue;
+            }
+            let (name, value) = match field.find(": ") {
+                Some(i) => (&field[..i], field[i + 2..].trim()),
+                None => return Err(invalid(format!("invalid field {:?}", field))),
+            };
+            match name {
+                "type" => {
+                    data_type = Some(match value {
+                        "short" | "short int" | "signed short" | "signed short int" | "int16"
+                        | "int16_t" => DataType::Int16,
+                        "float" | "float32" => DataType::Float32,
+                        _ => return Err(invalid(format!("unsupported type {:?}", value))),
+                    })
+                }
+                "dimension" if value != "3" => {
+                    return Err(invalid(format!("unsupported dimension {}", value)));
+                }
+                "sizes" => {
+                    let parsed: Vec<usize> = value
+                        .split_whitespace()
+                        .map(|s| {
+                            s.parse()
+                                .map_err(|_| invalid(format!("invalid size {:?}", s)))
+                        })
+                        .collect::<io::Result<_>>()?;
+                    if parsed.len() != 3 || parsed.contains(&0) {
+                        return Err(invalid(format!("invalid sizes {:?}", value)));
+                    }
+                    sizes = Some([parsed[0], parsed[1], parsed[2]]);
+                }
+                "encoding" => {
+                    encoding = Some(match value {
+                        "raw" => Encoding::Raw,
+                        "ascii" | "text" | "txt" => Encoding::Ascii,
+                        _ => return Err(invalid(format!("unsupported encoding {:?}", value))),
+                    })
+                }
+                "endian" => {
+                    big_endian = match value {
+                        "little" => false,
+                        "big" => true,
+                        _ => return Err(invalid(format!("unsupported endian {:?}", value))),
+                    }
+                }
+                "space origin" => origin = parse_vector(value)?,
+                "space directions" => {
+                    let directions: Vec<&str> = value.split_whitespace().collect();
+                    if directions.len() != 3 {
+                        return Err(invalid(format!("invalid space directions {:?}", value)));
+                    }
+                    for (i, d) in directions.iter().enumerate() {
+                        let mut unit = [0.; 3];
+                        unit[i] = 1.;
+                        if parse_vector(d)? != unit {
+                            return Err(invalid(format!(
+                                "unsupported space directions {:?}",
+                                value
+                            )));
+                        }
+                    }
+                }
+                "spacings" if value.split_whitespace().any(|s| s.parse() != Ok(1.)) => {
+                    return Err(invalid(format!("unsupported spacings {:?}", value)));
+                }
+                "data file" | "datafile" => {
+                    return Err(invalid("detached data files are not supported".to_string()))
+                }
+                _ => {}
+            }
+        }
+        let data_type = data_type.ok_or_else(|| invalid("missing type".to_string()))?;
+        let dims = sizes.ok_or_else(|| invalid("missing sizes".to_string()))?;
+        let encoding = encoding.ok_or_else(|| invalid("missing encoding".to_string()))?;
+        let n = dims[0]
+            .checked_mul(dims[1])
+            .and_then(|n| n.checked_mul(dims[2]))
+            .ok_or_else(|| invalid(format!("too many voxels {:?}", dims)))?;
+        let values: Vec<S> = match encoding {
+            Encoding::Raw => {
+                let size = match data_type {
+                    DataType::Int16 => 2,
+                    DataType::Float32 => 4,
+                };
+                let len = n
+                    .checked_mul(size)
+                    .ok_or_else(|| invalid(format!("too many voxels {:?}", dims)))?;
+                // Read at most len bytes, so a short file does not allocate all of them upfront.
+                let mut bytes = Vec::new();
+                reader.take(len as u64).read_to_end(&mut bytes)?;
+                if bytes.len() < len {
+                    return Err(invalid(format!("expected {} bytes of data", len)));
+                }
+                bytes
+                    .chunks(size)
+                    .map(|chunk| match data_type {
+                        DataType::Int16 => {
+                            let b = [chunk[0], chunk[1]];
+                            let v = if big_endian {
+                                i16::from_be_bytes(b)
+                            } else {
+                                i16::from_le_bytes(b)
+                            };
+                            From::from(f32::from(v))
+                        }
+                        DataType::Float32 => {
+                            let b = [chunk[0], chunk[1], chunk[2], chunk[3]];
+                            From::from(if big_endian {
+                                f32::from_be_bytes(b)
+                            } else {
+                                f32::from_le_bytes(b)
+                            })
+                        }
+                    })
+                    .collect()
+            }
+            Encoding::Ascii => {
+                let mut text = String::new();
+                reader.read_to_string(&mut text)?;
+                let mut tokens = text.split_whitespace();
+                let values = tokens
+                    .by_ref()
+                    .take(n)
+                    .map(|s| {
+                        let v = match data_type {
+                            DataType::Int16 => s.parse::<i16>().map(f32::from).ok(),
+                            DataType::Float32 => s.parse::<f32>().ok(),
+                        };
+                        v.map(From::from)
+                            .ok_or_else(|| invalid(format!("invalid value {:?}", s)))
+                    })
+                    .collect::<io::Result<Vec<S>>>()?;
+                if values.len() < n || tokens.next().is_some() {
+                    return Err(invalid(format!("expected {} values", n)));
+                }
+                values
+            }
+        };
+        let origin = na::Point3::new(
+            From::from(origin[0] as f32),
+            From::from(origin[1] as f32),
+            From::from(origin[2] as f32),
+        );
+        let lookup: BoxedGridFn<S> =
+            Box::new(move |idx: Index| values[idx[0] + dims[0] * (idx[1] + dims[1] * idx[2])]);
+        Ok(NrrdSdf {
+            grid: GridFunction::new(dims, origin, From::from(1f32), lookup),
+            dims,
+        })
+    }
+    /// The number of voxels in each dimension.
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+}
+
+impl<S> ImplicitFunction<S> for NrrdSdf<S>
+where
+    S: 'static + Debug + RealField + Float + From<f32> + AsUSize,
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        self.grid.bbox()
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.grid.value(p)
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        self.grid.normal(p)
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        self.grid.lipschitz_constant()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_float32() {
+        let mut data = b"NRRD0004\n# comment\ntype: float\ndimension: 3\nsizes: 2 3 2\n\
+                         space origin: (1,0,-1)\nencoding: raw\nendian: little\nkey:=value\n\n"
+            .to_vec();
+        for i in 0..12 {
+            data.extend_from_slice(&(i as f32 - 5.5).to_le_bytes());
+        }
+        let sdf = NrrdSdf::<f64>::read(&mut Cursor::new(data)).unwrap();
+        assert_eq!(sdf.dims(), [2, 3, 2]);
+        assert_eq!(sdf.bbox().min, na::Point3::new(1., 0., -1.));
+        assert_eq!(sdf.bbox().max, na::Point3::new(2., 2., 0.));
+        // Voxel [1, 2, 1] is value 1 + 2 * 2 + 6 * 1.
+        assert_relative_eq!(sdf.value(&na::Point3::new(2., 2., 0.)), 11. - 5.5);
+        assert_relative_eq!(sdf.value(&na::Point3::new(1.5, 0., -1.)), 0.5 - 5.5);
+    }
+
+    #[test]
+    fn read_int16() {
+        let mut data = b"NRRD0005\ntype: int16\ndimension: 3\nsizes: 2 1 1\nencoding: raw\n\
+                         endian: big\nspace directions: (1,0,0) (0,1,0) (0,0,1)\n\n"
+            .to_vec();
+        data.extend_from_slice(&(-300i16).to_be_bytes());
+        data.extend_from_slice(&(100i16).to_be_bytes());
+        let sdf = NrrdSdf::<f64>::read(&mut Cursor::new(data)).unwrap();
+        assert_relative_eq!(sdf.value(&na::Point3::new(0.75, 0., 0.)), 0.);
+        let ascii = "NRRD0004\ntype: short\ndimension: 3\nsizes: 2 1 1\nencoding: ascii\n\n-3 1\n";
+        let sdf = NrrdSdf::<f64>::read(&mut Cursor::new(ascii)).unwrap();
+        assert_relative_eq!(sdf.value(&na::Point3::new(0.75, 0., 0.)), 0.);
+    }
+
+    #[test]
+    fn unsupported() {
+        for header in &[
+            "PNG",
+            "NRRD0004\ntype: double\ndimension: 3\nsizes: 1 1 1\nencoding: raw\n\n",
+            "NRRD0004\ntype: float\ndimension: 2\nsizes: 1 1\nencoding: raw\n\n",
+            "NRRD0004\ntype: float\ndimension: 3\nsizes: 1 1 1\nencoding: gzip\n\n",
+            "NRRD0004\ntype: float\ndimension: 3\nsizes: 1 1 1\nencoding: raw\n\
+             space directions: (2,0,0) (0,1,0) (0,0,1)\n\n",
+            "NRRD0004\ntype: float\ndimension: 3\nsizes: 2 1 1\nencoding: raw\n\n\0\0\0\0",
+            "NRRD0004\ntype: float\ndimension: 3\nsizes: 2 1 1\nencoding: raw\nendian: middle\n\n",
+            "NRRD0004\ntype: float\ndimension: 3\nsizes: 4294967296 4294967296 2\n\
+             encoding: raw\n\n",
+            "NRRD0004\ntype: float\ndimension: 3\nsizes: 65536 65536 65536\nencoding: raw\n\n\0",
+            "NRRD0004\ntype: short\ndimension: 3\nsizes: 2 1 1\nencoding: ascii\n\n-3 1 2\n",
+        ] {
+            let error = NrrdSdf::<f64>::read(&mut Cursor::new(header))
+                .err()
+                .unwrap();
+            assert!(
+                error.kind() == io::ErrorKind::InvalidData
+                    || error.kind() == io::ErrorKind::UnexpectedEof,
+                "{:?}: {:?}",
+                header,
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use finite_difference;
+use na;
+use num_traits::{Float, ToPrimitive};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::fmt::Debug;
+
+// Bound of the magnitude of the gradient of PerlinNoise::noise. Sampling gives values below 3.4,
+// while the analytic bound is several times larger.
+const NOISE_LIPSCHITZ_CONSTANT: f32 = 4.;
+
+/// Seeded 3d gradient noise (Ken Perlin's improved noise).
+#[derive(Clone)]
+pub struct PerlinNoise {
+    // Permutation of 0..256, repeated twice to avoid wrapping indexes.
+    permutation: Vec<usize>,
+}
+
+fn fade<S: RealField + From<f32>>(t: S) -> S {
+    let six: S = From::from(6f32);
+    let fifteen: S = From::from(15f32);
+    let ten: S = From::from(10f32);
+    t * t * t * (t * (t * six - fifteen) + ten)
+}
+
+fn lerp<S: RealField>(t: S, a: S, b: S) -> S {
+    a + t * (b - a)
+}
+
+// Dot product of a pseudo random gradient (selected by hash) with (x, y, z).
+fn grad<S: RealField>(hash: usize, x: S, y: S, z: S) -> S {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+impl PerlinNoise {
+    /// Create a new noise generator. Equal seeds yield equal noise.
+    pub fn new(seed: u64) -> PerlinNoise {
+        let mut permutation: Vec<usize> = (0..256).collect();
+        permutation.shuffle(&mut StdRng::seed_from_u64(seed));
+        let repeated = permutation.clone();
+        permutation.extend(repeated);
+        PerlinNoise { permutation }
+    }
+    /// Evaluate the noise at p. The result is in [-1, 1] and zero on all integer lattice points.
+    pub fn noise<S: RealField + Float + From<f32>>(&self, p: &na::Point3<S>) -> S {
+        let one: S = From::from(1f32);
+        let floor = p.coords.map(Float::floor);
+        let cell: Vec<usize> = floor
+            .iter()
+            .map(|&c| (ToPrimitive::to_i64(&c).unwrap() & 255) as usize)
+            .collect();
+        let (x, y, z) = (p.x - floor.x, p.y - floor.y, p.z - floor.z);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let perm = &self.permutation;
+        let a = perm[cell[0]] + cell[1];
+        let aa = perm[a] + cell[2];
+        let ab = perm[a + 1] + cell[2];
+        let b = perm[cell[0] + 1] + cell[1];
+        let ba = perm[b] + cell[2];
+        let bb = perm[b + 1] + cell[2];
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(perm[aa], x, y, z), grad(perm[ba], x - one, y, z)),
+                lerp(
+                    u,
+                    grad(perm[ab], x, y - one, z),
+                    grad(perm[bb], x - one, y - one, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(perm[aa + 1], x, y, z - one),
+                    grad(perm[ba + 1], x - one, y, z - one),
+                ),
+                lerp(
+                    u,
+                    grad(perm[ab + 1], x, y - one, z - one),
+                    grad(perm[bb + 1], x - one, y - one, z - one),
+                ),
+            ),
+        )
+    }
+    /// Sum of octaves layers of noise, each with double the frequency and half the amplitude of
+    /// the previous one.
+    pub fn fractal_noise<S: RealField + Float + From<f32>>(
+        &self,
+        p: &na::Point3<S>,
+        octaves: usize,
+    ) -> S {
+        let two: S = From::from(2f32);
+        let mut result: S = From::from(0f32);
+        let mut scale: S = From::from(1f32);
+        for _ in 0..octaves {
+            result += self.noise(&(p * scale)) / scale;
+            scale *= two;
+        }
+        result
+    }
+}
+
+/// Bumpy organic surface, defined by the level set of Perlin noise.
+/// The noise is not a proper distance function, so the gradient may exceed 1.
+pub struct PerlinNoiseSurface<S: Debug + RealField> {
+    frequency: S,
+    amplitude: S,
+    octaves: usize,
+    noise: PerlinNoise,
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField> PerlinNoiseSurface<S> {
+    /// Create a new PerlinNoiseSurface.
+    /// frequency: scale applied to points before evaluating the noise
+    /// amplitude: noise level at which the surface lies
+    /// octaves: number of noise layers
+    /// seed: seed of the noise generator
+    /// bbox: the region in which to tessellate the surface
+    pub fn new(
+        frequency: S,
+        amplitude: S,
+        octaves: usize,
+        seed: u64,
+        bbox: BoundingBox<S>,
+    ) -> PerlinNoiseSurface<S> {
+        PerlinNoiseSurface {
+            frequency,
+            amplitude,
+            octaves,
+            noise: PerlinNoise::new(seed),
+            bbox,
+        }
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for PerlinNoiseSurface<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.noise
+            .fractal_noise(&(p * self.frequency), self.octaves)
+            - self.amplitude
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        let h: S = From::from(1e-3f32);
+        finite_difference::normal(|p| self.value(p), p, h / self.frequency)
+    }
+}
+
+/// Implicit function with a surface perturbed by Perlin noise, e.g. to make it look rough or
+/// organic. The value is inner.value(p) + noise_amplitude * noise(p * noise_frequency).
+pub struct NoisyObject<S: Debug + RealField, O: ImplicitFunction<S>> {
+    inner: O,
+    noise_amplitude: S,
+    noise_frequency: S,
+    noise: PerlinNoise,
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField + Float + From<f32>, O: ImplicitFunction<S>> NoisyObject<S, O> {
+    /// Create a new NoisyObject.
+    /// inner: the function to perturb
+    /// noise_amplitude: maximum displacement of the surface
+    /// noise_frequency: scale applied to points before evaluating the noise
+    /// noise_seed: seed of the noise generator
+    pub fn new(
+        inner: O,
+        noise_amplitude: S,
+        noise_frequency: S,
+        noise_seed: u64,
+    ) -> NoisyObject<S, O> {
+        let mut bbox = inner.bbox().clone();
+        bbox.dilate(Float::abs(noise_amplitude));
+        NoisyObject {
+            inner,
+            noise_amplitude,
+            noise_frequency,
+            noise: PerlinNoise::new(noise_seed),
+            bbox,
+        }
+    }
+    /// The wrapped function.
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>, O: ImplicitFunction<S>> ImplicitFunction<S>
+    for NoisyObject<S, O>
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.inner.value(p) + self.noise_amplitude * self.noise.noise(&(p * self.noise_frequency))
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        let h: S = From::from(1e-3f32);
+        finite_difference::normal(|p| self.value(p), p, h / self.noise_frequency)
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        let noise: S = From::from(NOISE_LIPSCHITZ_CONSTANT);
+        self.inner
+            .lipschitz_constant()
+            .map(|l| l + Float::abs(self.noise_amplitude * self.noise_frequency) * noise)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::Sphere;
+
+    #[test]
+    fn noise() {
+        let noise = PerlinNoise::new(42);
+        assert_eq!(noise.noise(&na::Point3::new(1., 2., -3.)), 0.);
+        let mut differs = false;
+        for i in 0..100 {
+            let p = na::Point3::new(i as f64 * 0.37, i as f64 * -0.71, i as f64 * 0.13);
+            let v = noise.noise(&p);
+            assert!((-1. ..=1.).contains(&v));
+            assert_eq!(v, PerlinNoise::new(42).noise(&p));
+            differs |= v != PerlinNoise::new(43).noise(&p);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn surface() {
+        let one = na::Point3::new(1., 1., 1.);
+        let surface = PerlinNoiseSurface::new(2., 0.1, 3, 7, BoundingBox::new(&-one, &one));
+        let p = na::Point3::new(0.3, 0.2, 0.1);
+        let expected = PerlinNoise::new(7).fractal_noise(&(p * 2.), 3) - 0.1;
+        assert_relative_eq!(surface.value(&p), expected);
+        assert_relative_eq!(surface.normal(&p).norm(), 1., epsilon = 1e-9);
+        assert_eq!(surface.bbox(), &BoundingBox::new(&-one, &one));
+    }
+
+    #[test]
+    fn noisy_object() {
+        let noisy = NoisyObject::new(Sphere::unit(), 0.1, 3., 5);
+        let p = na::Point3::new(0.3, -0.8, 0.4);
+        let expected = p.coords.norm() - 1. + 0.1 * PerlinNoise::new(5).noise(&(p * 3.));
+        assert_relative_eq!(noisy.value(&p), expected);
+        assert_relative_eq!(noisy.normal(&p).norm(), 1., epsilon = 1e-9);
+        let inflated = na::Point3::new(1.1, 1.1, 1.1);
+        assert_eq!(noisy.bbox(), &BoundingBox::new(&-inflated, &inflated));
+        assert_relative_eq!(noisy.lipschitz_constant().unwrap(), 1. + 0.3 * 4.);
+        // The surface moves by at most the amplitude.
+        for i in 1..100 {
+            let d = na::Vector3::new(i as f64 * 0.37, i as f64 * -0.71, i as f64 * 0.13)
+                .map(f64::sin)
+                .normalize();
+            assert!(noisy.value(&na::Point3::from(d * 0.89)) < 0.);
+            assert!(noisy.value(&na::Point3::from(d * 1.11)) > 0.);
+        }
+    }
+}
@@ -2,8 +2,11 @@ use alga::general::RealField;
 use na;
 use std::fmt::Debug;
 
+/// Tangent plane of the surface, where it intersects an edge of the sampling grid.
 #[derive(Clone, Copy, Debug)]
 pub struct Plane<S: 'static + RealField + Debug> {
+    /// The point where the surface intersects the edge.
     pub p: na::Point3<S>,
+    /// The normal of the surface at p.
     pub n: na::Vector3<S>,
 }
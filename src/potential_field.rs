@@ -0,0 +1,103 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Equipotential surface of point charges (generalized metaballs). The potential at p is the sum
+/// of charge / |p - center| over all charges. The value is threshold minus the potential, so
+/// points with a potential above threshold are inside.
+/// Warning: This is not a proper distance function. Values underestimate the distance close to
+/// the charges and the gradient is unbounded, so lipschitz_constant is None.
+pub struct PotentialField<S: Debug + RealField> {
+    charges: Vec<(na::Point3<S>, S)>,
+    threshold: S,
+    bbox: BoundingBox<S>,
+}
+
+impl<S: Debug + RealField + Float + From<f32>> PotentialField<S> {
+    /// Create a new PotentialField.
+    /// charges: the centers of the charges along with their magnitude
+    /// threshold: the potential on the surface, which must be positive
+    pub fn new(charges: Vec<(na::Point3<S>, S)>, threshold: S) -> PotentialField<S> {
+        // The potential is below threshold at distances larger than the sum of positive charges
+        // / threshold from all centers.
+        let max_distance = charges.iter().fold(S::zero(), |sum, &(_, charge)| {
+            sum + Float::max(charge, S::zero())
+        }) / threshold;
+        let mut bbox = BoundingBox::neg_infinity();
+        for (center, _) in &charges {
+            bbox.insert(center);
+        }
+        bbox.dilate(max_distance);
+        PotentialField {
+            charges,
+            threshold,
+            bbox,
+        }
+    }
+    /// The potential at p.
+    pub fn potential(&self, p: &na::Point3<S>) -> S {
+        self.charges
+            .iter()
+            .fold(S::zero(), |sum, &(ref center, charge)| {
+                sum + charge / na::distance(p, center)
+            })
+    }
+}
+
+impl<S: Debug + RealField + Float + From<f32>> ImplicitFunction<S> for PotentialField<S> {
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.threshold - self.potential(p)
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        // The gradient of -charge / |p - center| is charge * (p - center) / |p - center|³.
+        self.charges
+            .iter()
+            .fold(na::Vector3::zeros(), |gradient, &(ref center, charge)| {
+                let d = p - center;
+                let r = d.norm();
+                gradient + d * (charge / (r * r * r))
+            })
+            .normalize()
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn potential_field() {
+        // A single charge is a sphere of radius charge / threshold.
+        let field = PotentialField::new(vec![(na::Point3::new(1., 0., 0.), 2.)], 1.);
+        assert_relative_eq!(field.value(&na::Point3::new(5., 0., 0.)), 0.5);
+        assert_relative_eq!(field.value(&na::Point3::new(1., 2., 0.)), 0.);
+        assert_relative_eq!(field.value(&na::Point3::new(1., 0., -1.)), -1.);
+        assert_relative_eq!(
+            field.normal(&na::Point3::new(1., 3., 0.)),
+            na::Vector3::new(0., 1., 0.)
+        );
+        assert_relative_eq!(field.bbox().min, na::Point3::new(-1., -2., -2.));
+        assert_relative_eq!(field.bbox().max, na::Point3::new(3., 2., 2.));
+        // Two charges merge into one blob.
+        let field = PotentialField::new(
+            vec![
+                (na::Point3::new(-1., 0., 0.), 1.),
+                (na::Point3::new(1., 0., 0.), 1.),
+            ],
+            1.5,
+        );
+        assert!(field.value(&na::Point3::origin()) < 0.);
+        assert!(field.value(&na::Point3::new(0., 1., 0.)) > 0.);
+        assert_relative_eq!(field.normal(&na::Point3::new(3., 0., 0.)), na::Vector3::x());
+        assert_eq!(field.lipschitz_constant(), None);
+    }
+}
@@ -1,5 +1,6 @@
 use alga::general::RealField;
 use bbox::BoundingBox;
+use manifold_dual_contouring::ClampStrategy;
 use na;
 use num_traits::Float;
 use plane::Plane;
@@ -65,7 +66,13 @@ impl<S: 'static + RealField + Float + Debug + From<f32>> Qef<S> {
         }
         qef
     }
-    pub fn solve(&mut self) {
+    // Solve the QEF. If the solution is not contained in bbox, it is replaced according to
+    // strategy. candidates returns the positions of the tangent planes and is only called for
+    // ClampStrategy::NearestEdgeCrossing. Returns whether the solution was replaced.
+    pub fn solve<F>(&mut self, strategy: ClampStrategy, candidates: F) -> bool
+    where
+        F: FnOnce() -> Vec<na::Point3<S>>,
+    {
         let m = &self.ata;
         let ma = na::Matrix3::new(m[0], m[1], m[2], m[1], m[3], m[4], m[2], m[4], m[5]);
         let sum_as_s: S = convert::From::from(self.num as f32);
@@ -75,27 +82,70 @@ impl<S: 'static + RealField + Float + Debug + From<f32>> Qef<S> {
             self.solution = inv * b_rel_mean + mean;
         }
 
-        // If solution is not contained in cell bbox, start a binary search for a proper solution.
+        // If solution is not contained in cell bbox, replace it according to strategy.
         // NAN-solution will also not be contained in the bbox.
-        if !self.bbox.contains(&na::Point3::new(
+        let clamped = !self.bbox.contains(&na::Point3::new(
             self.solution.x,
             self.solution.y,
             self.solution.z,
-        )) {
-            let accuracy = (self.bbox.max.x - self.bbox.min.x) / convert::From::from(100.0);
-            self.solution = self.search_solution(accuracy, &mut self.bbox.clone(), &ma);
-            debug_assert!(
-                self.bbox.dilate(accuracy).contains(&na::Point3::new(
-                    self.solution.x,
-                    self.solution.y,
-                    self.solution.z
-                )),
-                "{:?} outside of {:?}",
-                self.solution,
-                self
-            );
+        ));
+        if clamped {
+            self.solution = self.clamp_solution(strategy, candidates, &mean, &ma);
         }
         self.error = self.error(&self.solution, &ma);
+        clamped
+    }
+    fn clamp_solution<F>(
+        &self,
+        strategy: ClampStrategy,
+        candidates: F,
+        mean: &na::Vector3<S>,
+        ma: &na::Matrix3<S>,
+    ) -> na::Vector3<S>
+    where
+        F: FnOnce() -> Vec<na::Point3<S>>,
+    {
+        match strategy {
+            ClampStrategy::BinarySearch => self.binary_search_solution(ma),
+            ClampStrategy::MeanOfPlanes => *mean,
+            ClampStrategy::ClampToBounds => {
+                let mut solution = self.solution;
+                if solution.iter().any(|x| x.is_nan()) {
+                    solution = *mean;
+                }
+                for i in 0..3 {
+                    solution[i] =
+                        Float::min(Float::max(solution[i], self.bbox.min[i]), self.bbox.max[i]);
+                }
+                solution
+            }
+            ClampStrategy::NearestEdgeCrossing => candidates()
+                .into_iter()
+                .map(|p| p.coords)
+                .map(|p| (self.error(&p, ma), p))
+                .fold(
+                    None,
+                    |best: Option<(S, na::Vector3<S>)>, (error, p)| match best {
+                        Some((best_error, _)) if best_error <= error => best,
+                        _ => Some((error, p)),
+                    },
+                )
+                .map_or(*mean, |(_, p)| p),
+        }
+    }
+    fn binary_search_solution(&self, ma: &na::Matrix3<S>) -> na::Vector3<S> {
+        let accuracy = (self.bbox.max.x - self.bbox.min.x) / convert::From::from(100.0);
+        let solution = self.search_solution(accuracy, &mut self.bbox.clone(), ma);
+        debug_assert!(
+            self.bbox
+                .clone()
+                .dilate(accuracy)
+                .contains(&na::Point3::new(solution.x, solution.y, solution.z)),
+            "{:?} outside of {:?}",
+            solution,
+            self
+        );
+        solution
     }
     // Do a binary search. Stop, if bbox is smaller then accuracy.
     fn search_solution(
@@ -151,7 +201,7 @@ impl<S: 'static + RealField + Float + Debug + From<f32>> Qef<S> {
 #[cfg(test)]
 mod tests {
     use super::Plane;
-    use super::{BoundingBox, Qef};
+    use super::{BoundingBox, ClampStrategy, Qef};
     use na;
 
     #[test]
@@ -174,7 +224,7 @@ mod tests {
             ],
             BoundingBox::<f64>::new(&na::Point3::new(0., 0., 0.), &na::Point3::new(1., 1., 1.)),
         );
-        qef.solve();
+        qef.solve(ClampStrategy::BinarySearch, Vec::new);
         assert!(
             qef.solution.norm() < 0.01,
             "{:?} nowhere near origin",
@@ -201,7 +251,7 @@ mod tests {
             ],
             BoundingBox::<f64>::new(&na::Point3::new(0., 0., 0.), &na::Point3::new(1., 1., 1.)),
         );
-        qef.solve();
+        qef.solve(ClampStrategy::BinarySearch, Vec::new);
         assert!(relative_eq!(qef.solution, &na::Vector3::new(0., 0., 0.)));
     }
 
@@ -224,7 +274,7 @@ mod tests {
             ],
             BoundingBox::<f64>::new(&na::Point3::new(0., 0., 0.), &na::Point3::new(1., 2., 3.)),
         );
-        qef.solve();
+        qef.solve(ClampStrategy::BinarySearch, Vec::new);
         let expected_solution = na::Vector3::new(1., 2., 3.);
         assert!(
             relative_eq!(qef.solution, &expected_solution),
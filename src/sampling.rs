@@ -0,0 +1,236 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use finite_difference;
+use na;
+use num_traits::Float;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Debug;
+
+// Maximum number of steps along a ray in ray_march.
+const MAX_RAY_MARCH_STEPS: usize = 1000;
+// Maximum number of rays tried to find a first point on the surface.
+const MAX_RAYS: usize = 100;
+// Number of Newton steps to project a point onto the surface.
+const PROJECTION_STEPS: usize = 5;
+// Step length of the random walk relative to the diagonal of the bounding box.
+const RELATIVE_STEP_LENGTH: f32 = 0.02;
+// Tolerance for points to count as being on the surface, relative to the step length.
+const RELATIVE_TOLERANCE: f32 = 1e-3;
+// Half the width of the band of values around the surface in approximate_surface_area, relative
+// to the diagonal of the bounding box.
+#[cfg(feature = "monte-carlo")]
+const RELATIVE_SURFACE_BAND: f32 = 0.01;
+
+// Estimate the distance of p to the surface of f, using the Lipschitz constant if known.
+fn distance_estimate<S, F>(f: &F, p: &na::Point3<S>, h: S) -> S
+where
+    S: Debug + RealField + Float + From<f32>,
+    F: ImplicitFunction<S> + ?Sized,
+{
+    let value = f.value(p);
+    let gradient_magnitude = match f.lipschitz_constant() {
+        Some(l) => l,
+        None => f.approximate_gradient_magnitude(p, h),
+    };
+    if gradient_magnitude > From::from(0f32) {
+        Float::abs(value) / gradient_magnitude
+    } else {
+        Float::abs(value)
+    }
+}
+
+// March along the ray origin + t * direction (sphere tracing) and return the first point within
+// tolerance of the surface of f, if it is found before t exceeds max_t.
+pub fn ray_march<S, F>(
+    f: &F,
+    origin: &na::Point3<S>,
+    direction: &na::Vector3<S>,
+    max_t: S,
+    tolerance: S,
+) -> Option<na::Point3<S>>
+where
+    S: Debug + RealField + Float + From<f32>,
+    F: ImplicitFunction<S> + ?Sized,
+{
+    let direction = direction.normalize();
+    let mut t = S::zero();
+    for _ in 0..MAX_RAY_MARCH_STEPS {
+        let p = origin + direction * t;
+        let d = distance_estimate(f, &p, tolerance);
+        if d < tolerance {
+            return Some(p);
+        }
+        t += d;
+        if t > max_t {
+            break;
+        }
+    }
+    None
+}
+
+// Move p onto the surface of f with Newton steps along the gradient.
+fn project<S, F>(f: &F, p: na::Point3<S>, h: S) -> na::Point3<S>
+where
+    S: Debug + RealField + Float + From<f32>,
+    F: ImplicitFunction<S> + ?Sized,
+{
+    let mut p = p;
+    for _ in 0..PROJECTION_STEPS {
+        let g = finite_difference::gradient(|q| f.value(q), &p, h);
+        let norm_squared = g.norm_squared();
+        if norm_squared == S::zero() {
+            break;
+        }
+        p -= g * (f.value(&p) / norm_squared);
+    }
+    p
+}
+
+// Sample n points on the surface of f with a random walk (Metropolis-Hastings with symmetric
+// tangential proposals), starting at the first surface point hit by a random ray through the
+// bounding box. The target distribution is uniform over the connected component of the surface
+// the walk starts on. Proposals are projected onto the surface and rejected, if they end up off
+// the surface or outside the bounding box. Returns an empty Vec, if no surface point is found.
+pub fn importance_sample<S, F>(f: &F, n: usize, seed: u64) -> Vec<na::Point3<S>>
+where
+    S: Debug + RealField + Float + From<f32>,
+    F: ImplicitFunction<S> + ?Sized,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let bbox = f.bbox();
+    let diagonal = bbox.dim().norm();
+    let step = diagonal * From::from(RELATIVE_STEP_LENGTH);
+    let tolerance = step * From::from(RELATIVE_TOLERANCE);
+    let random_point = |rng: &mut StdRng| {
+        let r = na::Vector3::new(
+            From::from(rng.gen::<f32>()),
+            From::from(rng.gen::<f32>()),
+            From::from(rng.gen::<f32>()),
+        );
+        bbox.min + bbox.dim().component_mul(&r)
+    };
+    // Cast rays between random points of the bounding box until one hits the surface.
+    let mut current = None;
+    for _ in 0..MAX_RAYS {
+        let (a, b) = (random_point(&mut rng), random_point(&mut rng));
+        if a == b {
+            continue;
+        }
+        if let Some(p) = ray_march(f, &a, &(b - a), diagonal, tolerance) {
+            current = Some(project(f, p, tolerance));
+            break;
+        }
+    }
+    let mut current = match current {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+    let two_pi: S = From::from(2. * ::std::f32::consts::PI);
+    let mut result = Vec::with_capacity(n);
+    while result.len() < n {
+        // Propose a move in a random direction in the tangent plane.
+        let normal = finite_difference::normal(|q| f.value(q), &current, tolerance);
+        let helper = if Float::abs(normal.x) < From::from(0.9f32) {
+            na::Vector3::x()
+        } else {
+            na::Vector3::y()
+        };
+        let u = normal.cross(&helper).normalize();
+        let v = normal.cross(&u);
+        let phi = two_pi * From::from(rng.gen::<f32>());
+        let proposal = project(
+            f,
+            current + (u * Float::cos(phi) + v * Float::sin(phi)) * step,
+            tolerance,
+        );
+        if distance_estimate(f, &proposal, tolerance) < tolerance
+            && (0..3).all(|i| proposal[i] >= bbox.min[i] && proposal[i] <= bbox.max[i])
+        {
+            current = proposal;
+        }
+        result.push(current);
+    }
+    result
+}
+
+// Estimate the surface area of f with the co-area formula: The integral of delta(f) |grad f|
+// over the bounding box is approximated by the mean of |grad f| / (2 t) over n uniform samples,
+// where samples with |f| >= t contribute zero.
+#[cfg(feature = "monte-carlo")]
+pub fn approximate_surface_area<S, F>(f: &F, n: usize) -> S
+where
+    S: Debug + RealField + Float + From<f32>,
+    F: ImplicitFunction<S> + ?Sized,
+{
+    let mut rng = StdRng::seed_from_u64(0);
+    let bbox = f.bbox();
+    let dim = bbox.dim();
+    let threshold = dim.norm() * From::from(RELATIVE_SURFACE_BAND);
+    let h = threshold / From::from(10f32);
+    let mut sum = S::zero();
+    for _ in 0..n {
+        let r = na::Vector3::new(
+            From::from(rng.gen::<f32>()),
+            From::from(rng.gen::<f32>()),
+            From::from(rng.gen::<f32>()),
+        );
+        let p = bbox.min + dim.component_mul(&r);
+        if Float::abs(f.value(&p)) < threshold {
+            sum += f.approximate_gradient_magnitude(&p, h);
+        }
+    }
+    let volume = dim.x * dim.y * dim.z;
+    let samples: S = From::from(n as f32);
+    volume * sum / (samples * (threshold + threshold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bbox::BoundingBox;
+    use test_util::Sphere;
+
+    fn sphere() -> Sphere {
+        Sphere {
+            bbox: BoundingBox::new(
+                &na::Point3::new(-2., -2., -2.),
+                &na::Point3::new(2., 2., 2.),
+            ),
+            ..Sphere::unit()
+        }
+    }
+
+    #[test]
+    fn ray_march_hits_sphere() {
+        let s = sphere();
+        let origin = na::Point3::new(-3., 0., 0.);
+        let hit = ray_march(&s, &origin, &na::Vector3::new(2., 0., 0.), 10., 1e-6).unwrap();
+        assert_relative_eq!(hit, na::Point3::new(-1., 0., 0.), epsilon = 1e-5);
+        assert!(ray_march(&s, &origin, &na::Vector3::new(0., 1., 0.), 10., 1e-6).is_none());
+    }
+
+    #[test]
+    fn importance_sample_covers_sphere() {
+        let s = sphere();
+        let samples = s.importance_sample(20000, 7);
+        assert_eq!(samples.len(), 20000);
+        let mut mean = na::Vector3::zeros();
+        for p in &samples {
+            assert!(s.value(p).abs() < 1e-3);
+            mean += p.coords;
+        }
+        // The walk covers the whole sphere, so the samples are centered at the origin.
+        mean /= samples.len() as f64;
+        assert!(mean.norm() < 0.2, "{:?}", mean);
+        assert_eq!(samples, s.importance_sample(20000, 7));
+    }
+
+    #[cfg(feature = "monte-carlo")]
+    #[test]
+    fn approximate_surface_area() {
+        let area = sphere().approximate_surface_area(200000);
+        assert_relative_eq!(area, 4. * ::std::f64::consts::PI, max_relative = 0.05);
+        assert_eq!(area, sphere().approximate_surface_area(200000));
+    }
+}
@@ -0,0 +1,315 @@
+use rayon::prelude::*;
+use vertex_index::Index;
+
+// Bricks store 2^BRICK_BITS values in each dimension.
+const BRICK_BITS: usize = 2;
+const BRICK_SIZE: usize = 1 << BRICK_BITS;
+
+// Dense block of values at the leafs of SparseOctree. Values are indexed by
+// z << 2 * BRICK_BITS | y << BRICK_BITS | x, relative to the origin of the brick.
+#[derive(Clone, Debug)]
+struct Brick<S> {
+    values: [S; BRICK_SIZE * BRICK_SIZE * BRICK_SIZE],
+    // Bit i is set, if values[i] is present.
+    present: u64,
+}
+
+impl<S: Copy> Brick<S> {
+    // Iterate over all present values along with their index, given the origin of the brick.
+    fn iter(&self, origin: Index) -> impl Iterator<Item = (Index, S)> + '_ {
+        (0..self.values.len())
+            .filter(move |&i| self.present & 1 << i != 0)
+            .map(move |i| {
+                let idx = [
+                    origin[0] + (i & (BRICK_SIZE - 1)),
+                    origin[1] + (i >> BRICK_BITS & (BRICK_SIZE - 1)),
+                    origin[2] + (i >> (2 * BRICK_BITS)),
+                ];
+                (idx, self.values[i])
+            })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Node<S> {
+    // The eight children are ordered z << 2 | y << 1 | x.
+    Inner(Box<[Option<Node<S>>; 8]>),
+    Leaf(Box<Brick<S>>),
+}
+
+// Index of the child of a node at level containing idx.
+fn child_index(idx: &Index, level: usize) -> usize {
+    let shift = level - 1 + BRICK_BITS;
+    (idx[0] >> shift & 1) | (idx[1] >> shift & 1) << 1 | (idx[2] >> shift & 1) << 2
+}
+
+// Index of idx in the values of its brick.
+fn brick_index(idx: &Index) -> usize {
+    let mask = BRICK_SIZE - 1;
+    (idx[0] & mask) | (idx[1] & mask) << BRICK_BITS | (idx[2] & mask) << (2 * BRICK_BITS)
+}
+
+impl<S: Copy> Node<S> {
+    fn get(&self, idx: &Index, level: usize) -> Option<&S> {
+        match *self {
+            Node::Inner(ref children) => children[child_index(idx, level)]
+                .as_ref()?
+                .get(idx, level - 1),
+            Node::Leaf(ref brick) => {
+                let i = brick_index(idx);
+                if brick.present & 1 << i != 0 {
+                    Some(&brick.values[i])
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    // Insert value at idx into node, which is created, if it is empty.
+    fn insert(node: &mut Option<Node<S>>, idx: &Index, level: usize, value: S) -> Option<S> {
+        let node = node.get_or_insert_with(|| {
+            if level == 0 {
+                Node::Leaf(Box::new(Brick {
+                    values: [value; BRICK_SIZE * BRICK_SIZE * BRICK_SIZE],
+                    present: 0,
+                }))
+            } else {
+                Node::Inner(Box::default())
+            }
+        });
+        match *node {
+            Node::Inner(ref mut children) => Node::insert(
+                &mut children[child_index(idx, level)],
+                idx,
+                level - 1,
+                value,
+            ),
+            Node::Leaf(ref mut brick) => {
+                let i = brick_index(idx);
+                let previous = if brick.present & 1 << i != 0 {
+                    Some(brick.values[i])
+                } else {
+                    None
+                };
+                brick.values[i] = value;
+                brick.present |= 1 << i;
+                previous
+            }
+        }
+    }
+    // Remove the value at idx from node. Nodes, that become empty, are pruned.
+    fn remove(node: &mut Option<Node<S>>, idx: &Index, level: usize) -> Option<S> {
+        let (removed, empty) = match *node.as_mut()? {
+            Node::Inner(ref mut children) => {
+                let removed = Node::remove(&mut children[child_index(idx, level)], idx, level - 1);
+                (removed, children.iter().all(Option::is_none))
+            }
+            Node::Leaf(ref mut brick) => {
+                let i = brick_index(idx);
+                if brick.present & 1 << i == 0 {
+                    return None;
+                }
+                brick.present &= !(1 << i);
+                (Some(brick.values[i]), brick.present == 0)
+            }
+        };
+        if empty {
+            *node = None;
+        }
+        removed
+    }
+    // Append all bricks below node at level with the given origin to bricks.
+    fn collect_bricks<'a>(
+        &'a self,
+        origin: Index,
+        level: usize,
+        bricks: &mut Vec<(Index, &'a Brick<S>)>,
+    ) {
+        match *self {
+            Node::Inner(ref children) => {
+                let size = 1 << (level - 1 + BRICK_BITS);
+                for (i, child) in children.iter().enumerate() {
+                    if let Some(ref child) = *child {
+                        let child_origin = [
+                            origin[0] + (i & 1) * size,
+                            origin[1] + (i >> 1 & 1) * size,
+                            origin[2] + (i >> 2) * size,
+                        ];
+                        child.collect_bricks(child_origin, level - 1, bricks);
+                    }
+                }
+            }
+            Node::Leaf(ref brick) => bricks.push((origin, brick)),
+        }
+    }
+}
+
+// Sparse storage of values on grid indexes. Values are kept in dense bricks of
+// BRICK_SIZE^3 values at the leafs of an octree, which only contains subtrees with values.
+// Lookups take O(log n) for grids of n indexes in each dimension. The octree grows as needed to
+// cover all inserted indexes.
+#[derive(Clone, Debug)]
+pub struct SparseOctree<S> {
+    // Number of levels above the bricks. The octree covers indexes up to
+    // 2^(levels + BRICK_BITS) in each dimension.
+    levels: usize,
+    root: Option<Node<S>>,
+    len: usize,
+}
+
+impl<S: Copy> SparseOctree<S> {
+    pub fn new() -> SparseOctree<S> {
+        SparseOctree {
+            levels: 0,
+            root: None,
+            len: 0,
+        }
+    }
+    fn covers(&self, idx: &Index) -> bool {
+        idx.iter().all(|&i| i >> (self.levels + BRICK_BITS) == 0)
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn clear(&mut self) {
+        *self = SparseOctree::new();
+    }
+    pub fn get(&self, idx: &Index) -> Option<&S> {
+        if !self.covers(idx) {
+            return None;
+        }
+        self.root.as_ref()?.get(idx, self.levels)
+    }
+    pub fn contains_key(&self, idx: &Index) -> bool {
+        self.get(idx).is_some()
+    }
+    // Insert value at idx and return the previous value.
+    pub fn insert(&mut self, idx: Index, value: S) -> Option<S> {
+        while !self.covers(&idx) {
+            // The old root becomes the first child of the new root.
+            if let Some(root) = self.root.take() {
+                let mut children: [Option<Node<S>>; 8] = Default::default();
+                children[0] = Some(root);
+                self.root = Some(Node::Inner(Box::new(children)));
+            }
+            self.levels += 1;
+        }
+        let previous = Node::insert(&mut self.root, &idx, self.levels, value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+    // Remove the value at idx and return it.
+    pub fn remove(&mut self, idx: &Index) -> Option<S> {
+        if !self.covers(idx) {
+            return None;
+        }
+        let removed = Node::remove(&mut self.root, idx, self.levels);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+    // Keep only the values, for which f returns true.
+    pub fn retain<F: FnMut(&Index, &S) -> bool>(&mut self, mut f: F) {
+        let removed: Vec<Index> = self
+            .iter()
+            .filter(|(idx, value)| !f(idx, value))
+            .map(|(idx, _)| idx)
+            .collect();
+        for idx in removed {
+            self.remove(&idx);
+        }
+    }
+    fn bricks(&self) -> Vec<(Index, &Brick<S>)> {
+        let mut bricks = Vec::new();
+        if let Some(ref root) = self.root {
+            root.collect_bricks([0, 0, 0], self.levels, &mut bricks);
+        }
+        bricks
+    }
+    // Iterate over all indexes and their values in the order of the octree.
+    pub fn iter(&self) -> impl Iterator<Item = (Index, S)> + '_ {
+        self.bricks()
+            .into_iter()
+            .flat_map(|(origin, brick)| brick.iter(origin))
+    }
+}
+
+impl<S: Copy + Send + Sync> SparseOctree<S> {
+    // Iterate over all indexes and their values in parallel.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (Index, S)> + '_ {
+        self.bricks()
+            .into_par_iter()
+            .flat_map_iter(|(origin, brick)| brick.iter(origin))
+    }
+}
+
+impl<S: Copy> Extend<(Index, S)> for SparseOctree<S> {
+    fn extend<T: IntoIterator<Item = (Index, S)>>(&mut self, iter: T) {
+        for (idx, value) in iter {
+            self.insert(idx, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    #[test]
+    fn matches_hash_map() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut octree = SparseOctree::new();
+        let mut map = HashMap::new();
+        for i in 0..2000 {
+            let idx = [
+                rng.gen_range(0, 40),
+                rng.gen_range(0, 40),
+                rng.gen_range(0, 70),
+            ];
+            if i % 3 == 0 {
+                assert_eq!(octree.remove(&idx), map.remove(&idx));
+            } else {
+                assert_eq!(octree.insert(idx, i), map.insert(idx, i));
+            }
+            assert_eq!(octree.len(), map.len());
+        }
+        for x in 0..41 {
+            for y in 0..41 {
+                for z in 0..71 {
+                    assert_eq!(octree.get(&[x, y, z]), map.get(&[x, y, z]));
+                }
+            }
+        }
+        let mut values: Vec<_> = octree.iter().collect();
+        values.sort();
+        let mut expected: Vec<_> = map.into_iter().collect();
+        expected.sort();
+        assert_eq!(values, expected);
+        let mut parallel: Vec<_> = octree.par_iter().collect();
+        parallel.sort();
+        assert_eq!(parallel, expected);
+    }
+
+    #[test]
+    fn prune() {
+        let mut octree = SparseOctree::new();
+        octree.insert([1, 2, 3], 1.);
+        octree.insert([1000, 0, 5], 2.);
+        assert_eq!(octree.get(&[1000, 0, 5]), Some(&2.));
+        assert_eq!(octree.get(&[1, 2, 3]), Some(&1.));
+        assert_eq!(octree.get(&[1 << 40, 0, 0]), None);
+        assert_eq!(octree.bricks().len(), 2);
+        octree.retain(|idx, _| idx[0] < 1000);
+        assert_eq!(octree.len(), 1);
+        assert_eq!(octree.bricks().len(), 1);
+        assert_eq!(octree.remove(&[1, 2, 3]), Some(1.));
+        assert_eq!(octree.len(), 0);
+        assert!(octree.root.is_none());
+    }
+}
@@ -0,0 +1,178 @@
+use mesh::MeshSink;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Writer for meshes in Wavefront OBJ format (see Mesh::write_obj), that writes each vertex and
+/// face directly to the underlying writer. So meshes never need to be in memory as a whole,
+/// e.g. when passed as MeshSink to ManifoldDualContouring::tessellate_incremental_into.
+pub struct StreamingMeshWriter<W: Write> {
+    inner: W,
+    vertex_count: usize,
+    normal_count: usize,
+    face_count: usize,
+    // The first error of the MeshSink methods, which cannot return it.
+    error: Option<io::Error>,
+}
+
+impl<W: Write> StreamingMeshWriter<W> {
+    /// Create a writer writing to inner. Wrap inner in a BufWriter to avoid a write call per
+    /// line.
+    pub fn new(inner: W) -> StreamingMeshWriter<W> {
+        StreamingMeshWriter {
+            inner,
+            vertex_count: 0,
+            normal_count: 0,
+            face_count: 0,
+            error: None,
+        }
+    }
+    /// Create a writer appending to the output of an interrupted writer, which wrote
+    /// vertex_count vertices, normal_count normals and face_count faces, e.g. to continue an
+    /// interrupted tessellation with IncrementalTessellator::resume_from.
+    pub fn resume(
+        inner: W,
+        vertex_count: usize,
+        normal_count: usize,
+        face_count: usize,
+    ) -> StreamingMeshWriter<W> {
+        StreamingMeshWriter {
+            inner,
+            vertex_count,
+            normal_count,
+            face_count,
+            error: None,
+        }
+    }
+    /// Write vertex v as "v x y z" line. Vertices are numbered in the order they are written.
+    pub fn write_vertex<S: Display>(&mut self, v: [S; 3]) -> io::Result<()> {
+        writeln!(self.inner, "v {} {} {}", v[0], v[1], v[2])?;
+        self.vertex_count += 1;
+        Ok(())
+    }
+    /// Write normal n as "vn x y z" line. Normals belong to the vertex with the same index.
+    pub fn write_normal<S: Display>(&mut self, n: [S; 3]) -> io::Result<()> {
+        writeln!(self.inner, "vn {} {} {}", n[0], n[1], n[2])?;
+        self.normal_count += 1;
+        Ok(())
+    }
+    /// Write face f of 0-based vertex indexes as "f a b c" line with 1-based indexes, or as
+    /// "f a//a b//b c//c" line, if normals were written. Fails, if f refers to a vertex or
+    /// normal, that was not written yet.
+    pub fn write_face(&mut self, f: [usize; 3]) -> io::Result<()> {
+        if let Some(&v) = f.iter().find(|&&v| v >= self.vertex_count) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "face refers to vertex {}, but only {} vertices were written",
+                    v, self.vertex_count
+                ),
+            ));
+        }
+        let (a, b, c) = (f[0] + 1, f[1] + 1, f[2] + 1);
+        if self.normal_count == 0 {
+            writeln!(self.inner, "f {} {} {}", a, b, c)?;
+        } else if let Some(&v) = f.iter().find(|&&v| v >= self.normal_count) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "face refers to normal {}, but only {} normals were written",
+                    v, self.normal_count
+                ),
+            ));
+        } else {
+            writeln!(self.inner, "f {}//{} {}//{} {}//{}", a, a, b, b, c, c)?;
+        }
+        self.face_count += 1;
+        Ok(())
+    }
+    /// The number of vertices written so far.
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+    /// The number of normals written so far.
+    pub fn normal_count(&self) -> usize {
+        self.normal_count
+    }
+    /// The number of faces written so far.
+    pub fn face_count(&self) -> usize {
+        self.face_count
+    }
+    /// Flush the underlying writer. Returns the first error of the MeshSink methods instead,
+    /// if there was one.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.inner.flush()
+    }
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+// Writes each vertex along with its normal. After the first error, nothing is written anymore and
+// flush returns the error.
+impl<S: Display, W: Write> MeshSink<S> for StreamingMeshWriter<W> {
+    fn add_vertex(&mut self, position: [S; 3], normal: [S; 3]) -> usize {
+        let index = self.vertex_count;
+        if self.error.is_none() {
+            if let Err(e) = self
+                .write_vertex(position)
+                .and_then(|()| self.write_normal(normal))
+            {
+                self.error = Some(e);
+            }
+        }
+        index
+    }
+    fn add_face(&mut self, face: [usize; 3]) {
+        if self.error.is_none() {
+            if let Err(e) = self.write_face(face) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingMeshWriter;
+    use mesh::MeshSink;
+
+    #[test]
+    fn write_obj() {
+        let mut writer = StreamingMeshWriter::new(Vec::new());
+        writer.write_vertex([0., 0., 0.]).unwrap();
+        writer.write_vertex([1., 0., 0.]).unwrap();
+        assert!(writer.write_face([0, 1, 2]).is_err());
+        writer.write_vertex([0., 1.5, 0.]).unwrap();
+        writer.write_face([0, 1, 2]).unwrap();
+        assert_eq!(writer.vertex_count(), 3);
+        assert_eq!(writer.face_count(), 1);
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "v 0 0 0\nv 1 0 0\nv 0 1.5 0\nf 1 2 3\n"
+        );
+    }
+
+    #[test]
+    fn mesh_sink() {
+        let mut writer = StreamingMeshWriter::new(Vec::new());
+        assert_eq!(writer.add_vertex([0., 0., 0.], [0., 0., 1.]), 0);
+        assert_eq!(writer.add_vertex([1., 0., 0.], [0., 0., 1.]), 1);
+        assert_eq!(writer.add_vertex([0., 1., 0.], [0., 0., 1.]), 2);
+        MeshSink::<f64>::add_face(&mut writer, [0, 1, 2]);
+        writer.flush().unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "v 0 0 0\nvn 0 0 1\nv 1 0 0\nvn 0 0 1\nv 0 1 0\nvn 0 0 1\nf 1//1 2//2 3//3\n"
+        );
+        let mut writer = StreamingMeshWriter::new(Vec::new());
+        // A face before its vertices fails, and nothing is written afterwards.
+        MeshSink::<f64>::add_face(&mut writer, [0, 1, 2]);
+        writer.add_vertex([0., 0., 0.], [0., 0., 1.]);
+        assert!(writer.flush().is_err());
+        assert!(writer.into_inner().is_empty());
+    }
+}
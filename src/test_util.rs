@@ -0,0 +1,39 @@
+// Fixtures shared by the tests of several modules.
+use bbox::BoundingBox;
+use na;
+use ImplicitFunction;
+
+// Sphere of radius around center.
+pub struct Sphere {
+    pub center: na::Point3<f64>,
+    pub radius: f64,
+    pub bbox: BoundingBox<f64>,
+}
+
+impl Sphere {
+    // Sphere with its tight bounding box.
+    pub fn new(center: na::Point3<f64>, radius: f64) -> Sphere {
+        let r = na::Vector3::new(radius, radius, radius);
+        Sphere {
+            center,
+            radius,
+            bbox: BoundingBox::new(&(center - r), &(center + r)),
+        }
+    }
+    // Sphere of radius 1 around the origin.
+    pub fn unit() -> Sphere {
+        Sphere::new(na::Point3::origin(), 1.)
+    }
+}
+
+impl ImplicitFunction<f64> for Sphere {
+    fn bbox(&self) -> &BoundingBox<f64> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<f64>) -> f64 {
+        na::distance(p, &self.center) - self.radius
+    }
+    fn normal(&self, p: &na::Point3<f64>) -> na::Vector3<f64> {
+        (p - self.center).normalize()
+    }
+}
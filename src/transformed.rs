@@ -0,0 +1,134 @@
+use super::ImplicitFunction;
+use alga::general::RealField;
+use bbox::BoundingBox;
+use na;
+use num_traits::Float;
+use std::fmt::Debug;
+
+// Return the bounding box of bbox transformed by transform.
+pub fn transform_bbox<S: Debug + RealField + Float>(
+    bbox: &BoundingBox<S>,
+    transform: &na::Matrix4<S>,
+) -> BoundingBox<S> {
+    let mut result = BoundingBox::neg_infinity();
+    for i in 0..8 {
+        let corner = na::Point3::new(
+            if i & 1 == 0 { bbox.min.x } else { bbox.max.x },
+            if i & 2 == 0 { bbox.min.y } else { bbox.max.y },
+            if i & 4 == 0 { bbox.min.z } else { bbox.max.z },
+        );
+        result.insert(&transform.transform_point(&corner));
+    }
+    result
+}
+
+/// Implicit function transformed by a matrix, as returned by ImplicitFunction::transform_by.
+pub struct Transformed<S: Debug + RealField, F> {
+    f: F,
+    inverse: na::Matrix4<S>,
+    bbox: BoundingBox<S>,
+    // The largest factor by which inverse stretches distances.
+    inverse_norm: S,
+}
+
+impl<S: Debug + RealField + Float, F: ImplicitFunction<S>> Transformed<S, F> {
+    /// Transform f by transform from object to world space. Panics, if transform is not
+    /// invertible.
+    pub fn new(f: F, transform: na::Matrix4<S>) -> Transformed<S, F> {
+        let inverse = transform
+            .try_inverse()
+            .expect("transform is not invertible");
+        let bbox = transform_bbox(f.bbox(), &transform);
+        let inverse_norm = inverse
+            .fixed_slice::<na::U3, na::U3>(0, 0)
+            .into_owned()
+            .singular_values()
+            .iter()
+            .cloned()
+            .fold(S::zero(), Float::max);
+        Transformed {
+            f,
+            inverse,
+            bbox,
+            inverse_norm,
+        }
+    }
+}
+
+impl<S: Debug + RealField + Float, F: ImplicitFunction<S>> ImplicitFunction<S>
+    for Transformed<S, F>
+{
+    fn bbox(&self) -> &BoundingBox<S> {
+        &self.bbox
+    }
+    fn value(&self, p: &na::Point3<S>) -> S {
+        self.f.value(&self.inverse.transform_point(p))
+    }
+    fn normal(&self, p: &na::Point3<S>) -> na::Vector3<S> {
+        // Normals transform with the inverse transpose.
+        let n = self.f.normal(&self.inverse.transform_point(p));
+        (self.inverse.fixed_slice::<na::U3, na::U3>(0, 0).transpose() * n).normalize()
+    }
+    fn is_inside(&self, p: &na::Point3<S>) -> bool {
+        self.f.is_inside(&self.inverse.transform_point(p))
+    }
+    fn lipschitz_constant(&self) -> Option<S> {
+        self.f.lipschitz_constant().map(|l| l * self.inverse_norm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ImplicitFunction;
+    use na;
+    use test_util::Sphere;
+
+    #[test]
+    fn translate_and_scale() {
+        let t = Sphere::unit()
+            .scale_uniform(2.)
+            .translate(na::Vector3::new(3., 0., 0.));
+        assert_relative_eq!(t.bbox().min, na::Point3::new(1., -2., -2.));
+        assert_relative_eq!(t.bbox().max, na::Point3::new(5., 2., 2.));
+        assert_relative_eq!(t.value(&na::Point3::new(5., 0., 0.)), 0.);
+        assert_relative_eq!(t.value(&na::Point3::new(3., 0., 0.)), -1.);
+        assert_relative_eq!(
+            t.normal(&na::Point3::new(3., 2., 0.)),
+            na::Vector3::new(0., 1., 0.)
+        );
+        assert!(t.is_inside(&na::Point3::new(4.9, 0., 0.)));
+        assert!(!t.is_inside(&na::Point3::new(0.9, 0., 0.)));
+        // Shrinking the sphere by 2 made values half as steep.
+        assert_relative_eq!(t.lipschitz_constant().unwrap(), 0.5);
+    }
+
+    #[test]
+    fn rotate() {
+        // Stretch along x, then rotate x onto y.
+        let t = Sphere::unit()
+            .transform_by(na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(
+                2., 1., 1.,
+            )))
+            .rotate_z(::std::f64::consts::FRAC_PI_2);
+        assert_relative_eq!(t.value(&na::Point3::new(0., 2., 0.)), 0., epsilon = 1e-12);
+        assert_relative_eq!(t.value(&na::Point3::new(1., 0., 0.)), 0., epsilon = 1e-12);
+        assert_relative_eq!(
+            t.normal(&na::Point3::new(0., 2., 0.)),
+            na::Vector3::new(0., 1., 0.),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            t.normal(&na::Point3::new(0.6, 1.6, 0.)),
+            na::Vector3::new(0.6, 0.4, 0.).normalize(),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(t.bbox().max, na::Point3::new(1., 2., 1.), epsilon = 1e-12);
+        let t = Sphere::unit().rotate_x(0.3).rotate_y(0.4);
+        assert_relative_eq!(
+            t.value(&na::Point3::new(0., 0., 0.5)),
+            -0.5,
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(t.lipschitz_constant().unwrap(), 1., epsilon = 1e-12);
+    }
+}
@@ -49,6 +49,20 @@ pub fn neg_offset(idx: Index, offset: Index) -> Index {
     [idx[0] - offset[0], idx[1] - offset[1], idx[2] - offset[2]]
 }
 
+// For periodic grids with period cells in each dimension, indexes are kept in [period, 2 *
+// period), so offset and neg_offset by one never leave the range of usize. Wrap idx into that
+// range. Indexes of non-periodic grids are returned unchanged.
+pub fn wrap(idx: Index, period: Option<Index>) -> Index {
+    match period {
+        Some(p) => [
+            p[0] + idx[0] % p[0],
+            p[1] + idx[1] % p[1],
+            p[2] + idx[2] % p[2],
+        ],
+        None => idx,
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct VertexIndex {
     pub edges: BitSet,
@@ -84,7 +98,7 @@ pub enum VarIndex {
 #[cfg(test)]
 mod tests {
     use super::super::bitset::BitSet;
-    use super::{VertexIndex, EDGES_ON_FACE};
+    use super::{wrap, VertexIndex, EDGES_ON_FACE};
 
     #[test]
     fn neighbor() {
@@ -107,4 +121,12 @@ mod tests {
             assert_eq!(v.neighbor(i).unwrap().edges, EDGES_ON_FACE[i - 1]);
         }
     }
+
+    #[test]
+    fn wrap_index() {
+        assert_eq!(wrap([3, 4, 5], None), [3, 4, 5]);
+        let period = Some([4, 5, 6]);
+        assert_eq!(wrap([4, 9, 12], period), [4, 9, 6]);
+        assert_eq!(wrap([3, 10, 17], period), [7, 5, 11]);
+    }
 }